@@ -68,6 +68,32 @@ impl Default for AccTimer {
     }
 }
 
+/// 耗时直方图中每个 2 倍区间 (octave) 所分的桶数. 相邻桶边界之比为 `2^(1/4)`,
+/// 即分辨率约为 19%.
+const BUCKETS_PER_OCTAVE: u32 = 4;
+
+/// 耗时直方图的桶数. 第 0 个桶记录不足 1 微秒的耗时, 最后一个桶记录所有
+/// 不少于 `2^40` 微秒 (约 12 天) 的耗时.
+const TIME_BUCKETS: usize = 40 * BUCKETS_PER_OCTAVE as usize + 2;
+
+/// 耗时 `d` 所在的直方图桶. 第 `i` 个桶 (`i >= 1`) 的范围为
+/// `[2^((i - 1) / 4), 2^(i / 4))` 微秒.
+fn time_bucket(d: Duration) -> usize {
+    match d.as_micros() {
+        0 => 0,
+        us => {
+            let idx = ((us as f64).log2() * BUCKETS_PER_OCTAVE as f64).floor() as usize + 1;
+            idx.min(TIME_BUCKETS - 1)
+        }
+    }
+}
+
+/// 第 `idx` 个直方图桶的上界.
+fn bucket_upper_bound(idx: usize) -> Duration {
+    let us = 2f64.powf(idx as f64 / BUCKETS_PER_OCTAVE as f64);
+    Duration::from_secs_f64(us * 1e-6)
+}
+
 /// ablation/benchmark 数据统计.
 #[derive(Clone, Debug)]
 pub struct Profile {
@@ -88,6 +114,9 @@ pub struct Profile {
 
     /// 在具有唯一大肝脏对象的图片上运行算法损失的总肝脏像素 (体素) 个数.
     eroded: u64,
+
+    /// 处理每张普通图片耗时的直方图 (按对数等距分桶), 内存占用与图片个数无关.
+    time_hist: [u64; TIME_BUCKETS],
 }
 
 impl Profile {
@@ -101,6 +130,7 @@ impl Profile {
             real_time: AccTimer::default(),
             most: Duration::MAX,
             eroded: 0,
+            time_hist: [0; TIME_BUCKETS],
         }
     }
 
@@ -131,10 +161,16 @@ impl Profile {
     #[inline]
     pub fn target_elapsed(&mut self) {
         let d = self.target_time.elapsed();
+        self.record_time(d);
+    }
+
+    /// 记录一次普通图像处理的耗时 `d`.
+    fn record_time(&mut self, d: Duration) {
         self.most = match self.most {
             Duration::MAX => d,
             once_duration => std::cmp::max(d, once_duration),
         };
+        self.time_hist[time_bucket(d)] += 1;
     }
 
     /// 如果 `count` 不为 0, 则添加一次腐蚀记录.
@@ -198,6 +234,34 @@ impl Profile {
         }
     }
 
+    /// 获得处理普通图像的吞吐量 (每秒处理的普通图像个数).
+    ///
+    /// 仅计入处理普通图像的时间. 如果不存在普通图像或计时为 0, 则返回 `None`.
+    #[inline]
+    pub fn images_per_second(&self) -> Option<f64> {
+        match (self.target, self.get_target_time_us()) {
+            (0, _) | (_, 0) => None,
+            (target, us) => Some(target as f64 * 1e6 / us as f64),
+        }
+    }
+
+    /// 获得纯背景图像占全部图像的比例. 如果没有遇到任何图像, 则返回 0.0.
+    #[inline]
+    pub fn trivial_fraction(&self) -> f64 {
+        match self.trivial + self.target {
+            0 => 0.0,
+            total => self.trivial as f64 / total as f64,
+        }
+    }
+
+    /// 以微秒为单位获得处理普通图像以外的开销时间, 即 `real_time - target_time`
+    /// (包括 IO, 配置外部环境以及处理纯背景图像的时间).
+    #[inline]
+    pub fn overhead_us(&self) -> u64 {
+        self.get_real_time_us()
+            .saturating_sub(self.get_target_time_us())
+    }
+
     /// 获取处理普通图片最耗时的一次任务所消耗的时间.
     ///
     /// 如果不存在任务, 则返回 `None`.
@@ -207,6 +271,40 @@ impl Profile {
             d => Some(d),
         }
     }
+
+    /// 获取处理单张普通图片耗时的 `p` (0.0 ~ 1.0) 分位数, 用于报告尾部延迟.
+    ///
+    /// 耗时按对数等距分桶记录 (相邻桶边界之比为 `2^(1/4)`), 因此返回值是
+    /// 第 `ceil(p * n)` 小的耗时所在桶的上界, 精度约为 19%.
+    /// 如果不存在任务, 或 `p` 不在 `[0, 1]` 之间, 则返回 `None`.
+    pub fn get_time_percentile(&self, p: f64) -> Option<Duration> {
+        if !(0.0..=1.0).contains(&p) {
+            return None;
+        }
+        let total: u64 = self.time_hist.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((p * total as f64).ceil() as u64).max(1);
+        let mut acc = 0;
+        self.time_hist.iter().enumerate().find_map(|(idx, &c)| {
+            acc += c;
+            (acc >= rank).then(|| bucket_upper_bound(idx))
+        })
+    }
+
+    /// 获取处理普通图片耗时的直方图, 可以看出是否少数耗时很长的图片主导了总时间.
+    ///
+    /// 返回所有非空的桶 `(桶的上界, 落入该桶的图片个数)`, 按耗时升序排列.
+    /// 第 `i` 个桶的范围为 `[上界 / 2^(1/4), 上界)`.
+    pub fn get_time_histogram(&self) -> Vec<(Duration, u64)> {
+        self.time_hist
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c > 0)
+            .map(|(idx, &c)| (bucket_upper_bound(idx), c))
+            .collect()
+    }
 }
 
 impl Default for Profile {
@@ -214,3 +312,76 @@ impl Default for Profile {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timer(us: u64) -> AccTimer {
+        AccTimer {
+            consumed: Duration::from_micros(us),
+            since: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_profile_derived_metrics() {
+        let empty = Profile::new();
+        assert_eq!(empty.images_per_second(), None);
+        assert_eq!(empty.trivial_fraction(), 0.0);
+
+        let profile = Profile {
+            trivial: 30,
+            target: 10,
+            target_time: timer(2_000_000),
+            real_time: timer(2_500_000),
+            ..Profile::new()
+        };
+        assert_eq!(profile.images_per_second(), Some(5.0));
+        assert_eq!(profile.trivial_fraction(), 0.75);
+        assert_eq!(profile.overhead_us(), 500_000);
+        assert_eq!(profile.get_avg_target_time_us(), Some(200_000.0));
+    }
+
+    #[test]
+    fn test_time_histogram() {
+        let mut profile = Profile::new();
+        assert_eq!(profile.get_time_percentile(0.5), None);
+        assert!(profile.get_time_histogram().is_empty());
+
+        // 90 张 1 毫秒, 7 张 10 毫秒, 3 张 1 秒.
+        let (fast, slow, slowest) = (
+            Duration::from_millis(1),
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+        );
+        for (d, n) in [(fast, 90), (slow, 7), (slowest, 3)] {
+            (0..n).for_each(|_| profile.record_time(d));
+        }
+        assert_eq!(profile.get_most_time_consuming(), Some(slowest));
+
+        // 分位数为所在桶的上界, 与真实耗时相差不超过一个桶宽.
+        let in_bucket = |upper: Duration, d: Duration| {
+            let ratio = upper.as_secs_f64() / d.as_secs_f64();
+            1.0 < ratio && ratio <= 2f64.powf(0.25) + 1e-9
+        };
+        assert!(in_bucket(profile.get_time_percentile(0.5).unwrap(), fast));
+        assert!(in_bucket(profile.get_time_percentile(0.9).unwrap(), fast));
+        assert!(in_bucket(profile.get_time_percentile(0.95).unwrap(), slow));
+        assert!(in_bucket(
+            profile.get_time_percentile(0.99).unwrap(),
+            slowest
+        ));
+        assert!(in_bucket(profile.get_time_percentile(0.0).unwrap(), fast));
+        assert_eq!(profile.get_time_percentile(1.5), None);
+
+        let hist = profile.get_time_histogram();
+        assert_eq!(hist.iter().map(|h| h.1).collect::<Vec<_>>(), [90, 7, 3]);
+        assert!(in_bucket(hist[0].0, fast) && in_bucket(hist[2].0, slowest));
+
+        // 不足 1 微秒的耗时落入第 0 个桶.
+        let mut tiny = Profile::new();
+        tiny.record_time(Duration::from_nanos(10));
+        assert_eq!(tiny.get_time_histogram(), [(Duration::from_micros(1), 1)]);
+    }
+}
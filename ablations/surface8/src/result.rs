@@ -1,6 +1,8 @@
 //! 实验结果.
 
 use crate::algos::Profile;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
 use std::io::{self, Write};
 
 /// 将 `profile` 的结果写进 `w` 中.
@@ -44,6 +46,99 @@ fn describe_into<W: Write>(name: &str, p: &Profile, w: &mut W) -> io::Result<()>
     Ok(())
 }
 
+/// 导出结构化结果时, 每个算法的字段名 (除算法名 `name` 外).
+const COLUMNS: [&str; 11] = [
+    "trivial",
+    "target",
+    "target_time_us",
+    "avg_target_time_us",
+    "real_time_us",
+    "overhead_us",
+    "images_per_second",
+    "trivial_fraction",
+    "eroded",
+    "avg_eroded",
+    "most_time_us",
+];
+
+/// 结构化导出用的字段值. `None` 表示该值不存在.
+enum Field {
+    U64(Option<u64>),
+    F64(Option<f64>),
+}
+
+/// 按照 `COLUMNS` 的顺序获取 `p` 的各字段值.
+fn fields(p: &Profile) -> [Field; 11] {
+    use Field::*;
+    [
+        U64(Some(p.get_trivial())),
+        U64(Some(p.get_target())),
+        U64(Some(p.get_target_time_us())),
+        F64(p.get_avg_target_time_us()),
+        U64(Some(p.get_real_time_us())),
+        U64(Some(p.overhead_us())),
+        F64(p.images_per_second()),
+        F64(Some(p.trivial_fraction())),
+        U64(Some(p.get_eroded())),
+        F64(p.get_avg_eroded()),
+        U64(p.get_most_time_consuming().map(|d| d.as_micros() as u64)),
+    ]
+}
+
+impl Field {
+    /// CSV 字段. 不存在的值为空字段, 其余值以最短的可还原形式输出.
+    fn to_csv(&self) -> String {
+        match self {
+            Field::U64(Some(u)) => u.to_string(),
+            Field::F64(Some(f)) => f.to_string(),
+            Field::U64(None) | Field::F64(None) => String::new(),
+        }
+    }
+
+    /// JSON 值. 不存在的值以及 JSON 无法表示的 NaN 和无穷大均为 `null`.
+    fn to_json(&self) -> Value {
+        match *self {
+            Field::U64(u) => json!(u),
+            Field::F64(f) => json!(f),
+        }
+    }
+}
+
+/// 按 RFC 4180 转义 CSV 字段: 含有逗号、引号或换行时以引号包围, 内部引号重复一次.
+fn csv_escape(s: &str) -> Cow<'_, str> {
+    if s.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", s.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// 以 CSV 格式导出 `rows`, 每行为 (算法名, 按照 `COLUMNS` 顺序的字段值).
+fn rows_to_csv<'a>(rows: impl IntoIterator<Item = (&'a str, [Field; 11])>) -> String {
+    let mut out = format!("name,{}\n", COLUMNS.join(","));
+    for (name, fields) in rows {
+        let row: Vec<String> = fields.iter().map(Field::to_csv).collect();
+        out.push_str(&format!("{},{}\n", csv_escape(name), row.join(",")));
+    }
+    out
+}
+
+/// 以 JSON 格式导出 `rows`, 参数含义同 [`rows_to_csv`].
+fn rows_to_json<'a>(rows: impl IntoIterator<Item = (&'a str, [Field; 11])>) -> String {
+    let objects: Vec<Value> = rows
+        .into_iter()
+        .map(|(name, fields)| {
+            let mut obj = Map::new();
+            obj.insert("name".to_string(), json!(name));
+            for (col, f) in COLUMNS.iter().zip(fields.iter()) {
+                obj.insert(col.to_string(), f.to_json());
+            }
+            Value::Object(obj)
+        })
+        .collect();
+    serde_json::to_string(&objects).expect("JSON 序列化失败")
+}
+
 /// 消融实验最终结果.
 pub struct AblationResult {
     data: Vec<(&'static str, Profile)>,
@@ -56,6 +151,23 @@ impl AblationResult {
         }
     }
 
+    /// 以 CSV 格式导出所有算法的统计结果.
+    ///
+    /// 第一行为表头, 其后每个算法一行, 第一列为算法名. 不存在的值为空字段.
+    ///
+    /// 含有逗号、引号或换行的算法名会被引号包围并转义.
+    pub fn to_csv(&self) -> String {
+        rows_to_csv(self.data.iter().map(|(key, p)| (*key, fields(p))))
+    }
+
+    /// 以 JSON 格式导出所有算法的统计结果.
+    ///
+    /// 结果为对象数组, 每个算法一个对象, 其中 `name` 为算法名.
+    /// 不存在的值, 以及 NaN 和无穷大为 `null`.
+    pub fn to_json(&self) -> String {
+        rows_to_json(self.data.iter().map(|(key, p)| (*key, fields(p))))
+    }
+
     /// 分析运行结果.
     pub fn analyze(&self) {
         utils::sep();
@@ -70,3 +182,96 @@ impl AblationResult {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result() -> AblationResult {
+        let mut busy = Profile::new();
+        for _ in 0..3 {
+            busy.count_trivial();
+        }
+        for _ in 0..4 {
+            busy.count_target(true);
+            busy.target_elapsed();
+        }
+        busy.count_eroded(10);
+        AblationResult::from_iter([("busy", busy.finish()), ("idle", Profile::new().finish())])
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let r = result();
+        let csv = r.to_csv();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), 1 + r.data.len());
+        assert_eq!(rows[0][0], "name");
+        assert_eq!(rows[0][1..], COLUMNS);
+        assert!(rows.iter().all(|row| row.len() == COLUMNS.len() + 1));
+        assert_eq!(rows[1][..3], ["busy", "3", "4"]);
+        assert_eq!(rows[1][9], "10");
+        // 没有普通图像时, 平均值不存在.
+        assert_eq!(rows[2][0], "idle");
+        assert_eq!(rows[2][4], "");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let r = result();
+        let json: serde_json::Value = serde_json::from_str(&r.to_json()).unwrap();
+        let arr = json.as_array().unwrap();
+        assert_eq!(arr.len(), r.data.len());
+        for (obj, (key, p)) in arr.iter().zip(r.data.iter()) {
+            assert_eq!(obj["name"], *key);
+            assert_eq!(obj["trivial"], p.get_trivial());
+            assert_eq!(obj["target"], p.get_target());
+            assert_eq!(obj["target_time_us"], p.get_target_time_us());
+            assert_eq!(obj["real_time_us"], p.get_real_time_us());
+            assert_eq!(obj["overhead_us"], p.overhead_us());
+            assert_eq!(obj["eroded"], p.get_eroded());
+            assert_eq!(obj["trivial_fraction"].as_f64(), Some(p.trivial_fraction()));
+            assert_eq!(obj["avg_eroded"].as_f64(), p.get_avg_eroded());
+            assert_eq!(
+                obj["avg_target_time_us"].as_f64(),
+                p.get_avg_target_time_us()
+            );
+            assert_eq!(obj["images_per_second"].as_f64(), p.images_per_second());
+        }
+    }
+
+    #[test]
+    fn test_export_escaping() {
+        let rows = || {
+            [(
+                "a, \"b\"",
+                [
+                    Field::U64(Some(1)),
+                    Field::U64(None),
+                    Field::U64(Some(0)),
+                    Field::F64(Some(f64::NAN)),
+                    Field::U64(Some(2)),
+                    Field::U64(Some(3)),
+                    Field::F64(Some(f64::INFINITY)),
+                    Field::F64(Some(0.5)),
+                    Field::U64(Some(4)),
+                    Field::F64(None),
+                    Field::U64(None),
+                ],
+            )]
+        };
+
+        let csv = rows_to_csv(rows());
+        let line = csv.lines().nth(1).unwrap();
+        assert_eq!(line, "\"a, \"\"b\"\"\",1,,0,NaN,2,3,inf,0.5,4,,");
+
+        let json: serde_json::Value = serde_json::from_str(&rows_to_json(rows())).unwrap();
+        let obj = &json[0];
+        assert_eq!(obj["name"], "a, \"b\"");
+        assert!(obj["avg_target_time_us"].is_null());
+        assert!(obj["images_per_second"].is_null());
+        assert_eq!(obj["trivial_fraction"], 0.5);
+        assert!(obj["target"].is_null());
+        assert_eq!(obj["eroded"], 4);
+    }
+}
@@ -14,6 +14,12 @@ pub mod gray {
     /// LiTS 数据集切片中, 肝脏边缘的 (预留) 像素值.
     pub const LITS_BOUNDARY: u8 = 3;
 
+    /// 差异标注 (见 [`crate::CtLabel::diff_mask`]) 中, 新增为肝脏的体素值.
+    pub const DIFF_ADDED: u8 = 1;
+
+    /// 差异标注 (见 [`crate::CtLabel::diff_mask`]) 中, 不再是肝脏的体素值.
+    pub const DIFF_REMOVED: u8 = 2;
+
     /// 单通道黑色.
     pub const BLACK: u8 = 0b_0000_0000;
 
@@ -95,3 +101,57 @@ impl ElemType {
         !self.is_foreground()
     }
 }
+
+/// 前景的构成, 即哪些标注像素被视为 [`ElemType::Foreground`].
+///
+/// 用于代替含义不够直观的 `include_tumor: bool` 参数.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Foreground {
+    /// 仅 `LITS_LIVER` 为前景.
+    LiverOnly,
+
+    /// `LITS_LIVER` 和 `LITS_TUMOR` 均为前景.
+    LiverAndTumor,
+}
+
+impl Foreground {
+    /// 从旧式的 `include_tumor` 参数转换.
+    #[inline]
+    pub const fn from_include_tumor(include_tumor: bool) -> Self {
+        if include_tumor {
+            Self::LiverAndTumor
+        } else {
+            Self::LiverOnly
+        }
+    }
+
+    /// 像素 `p` 是否为前景?
+    #[inline]
+    pub const fn contains(&self, p: u8) -> bool {
+        match self {
+            Self::LiverOnly => gray::is_liver(p),
+            Self::LiverAndTumor => gray::is_liver_or_tumor(p),
+        }
+    }
+
+    /// 求像素 `p` 的类型.
+    #[inline]
+    pub const fn eval(&self, p: u8) -> ElemType {
+        if self.contains(p) {
+            ElemType::Foreground
+        } else {
+            ElemType::Background
+        }
+    }
+}
+
+/// 二维像素的连通规则.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Connectivity {
+    /// 4-相邻 (上下左右).
+    #[default]
+    Four,
+
+    /// 8-相邻 (上下左右及四个对角).
+    Eight,
+}
@@ -0,0 +1,203 @@
+//! 多尺度 LoG (Laplacian of Gaussian) 斑点检测.
+
+use super::blur::{convolve_axis, gaussian_kernel};
+use super::{CtLabel, CtScan, NiftiHeaderAttr};
+use crate::consts::Foreground;
+use crate::Idx3d;
+use ndarray::{Array3, Axis};
+
+impl CtScan {
+    /// 多尺度 LoG 斑点检测, 可以作为病灶候选点的生成器.
+    ///
+    /// 对 `sigmas` (单位: 毫米, 必须严格递增) 中的每个尺度 `σ`, 先以 `σ` 对扫描做
+    /// 三维高斯平滑, 再计算尺度归一化的响应 `-σ² ∇²`. 在空间 (26-邻域) 和相邻尺度上
+    /// 同时为局部极值, 且绝对值不小于 `threshold` 的体素被视为斑点.
+    ///
+    /// 返回 `(中心, 尺度 σ, 响应)`, 按响应的绝对值降序排列. 比周围亮的斑点
+    /// (如高密度病灶) 响应为正, 比周围暗的斑点 (如多数低密度肝脏病灶) 响应为负.
+    /// 对于标准差为 `s` 的三维高斯斑点, 响应在 `σ = s * sqrt(2 / 3)` 处最大;
+    /// 对于半径为 `r` 的实心球, 约在 `σ = r / sqrt(3)` 处最大.
+    ///
+    /// 体积最外层的体素不参与检测. 若 `sigmas` 为空, 不严格递增, 含有非正数或非有限值,
+    /// 或 `threshold` 为负数, 则程序 panic.
+    pub fn detect_blobs(&self, sigmas: &[f64], threshold: f32) -> Vec<(Idx3d, f64, f32)> {
+        self.detect_blobs_imp(sigmas, threshold, |_| true)
+    }
+
+    /// 同 [`Self::detect_blobs`], 但只在 `label` 中属于 `fg` 的体素处寻找斑点中心.
+    /// 平滑和极值比较仍然使用完整的扫描.
+    ///
+    /// 除 [`Self::detect_blobs`] 的要求以外, 若 `self` 与 `label` 形状不一致, 则程序 panic.
+    pub fn detect_blobs_in_label(
+        &self,
+        sigmas: &[f64],
+        threshold: f32,
+        label: &CtLabel,
+        fg: Foreground,
+    ) -> Vec<(Idx3d, f64, f32)> {
+        assert_eq!(self.shape(), label.shape(), "CT 扫描和标注形状不一致");
+        self.detect_blobs_imp(sigmas, threshold, |pos| fg.contains(label.data[pos]))
+    }
+
+    fn detect_blobs_imp(
+        &self,
+        sigmas: &[f64],
+        threshold: f32,
+        include: impl Fn(Idx3d) -> bool,
+    ) -> Vec<(Idx3d, f64, f32)> {
+        assert!(!sigmas.is_empty(), "至少需要一个尺度");
+        assert!(
+            sigmas.iter().all(|s| s.is_finite() && *s > 0.0)
+                && sigmas.windows(2).all(|w| w[0] < w[1]),
+            "非法的尺度 {sigmas:?}"
+        );
+        assert!(threshold >= 0.0, "阈值不能为负数");
+
+        let spacing = self.pix_dim();
+        let data = self.data.mapv(|v| v as f64);
+        let response = |sigma: f64| normalized_log(&data, sigma, spacing);
+
+        // 只保留相邻的三个尺度, 内存占用与尺度个数无关.
+        let mut ans = vec![];
+        let mut prev: Option<Array3<f64>> = None;
+        let mut cur = response(sigmas[0]);
+        for (i, &sigma) in sigmas.iter().enumerate() {
+            let next = sigmas.get(i + 1).map(|&s| response(s));
+            let scales: Vec<&Array3<f64>> = [prev.as_ref(), Some(&cur), next.as_ref()]
+                .into_iter()
+                .flatten()
+                .collect();
+            for (pos, v) in local_extrema(&cur, &scales, threshold as f64) {
+                if include(pos) {
+                    ans.push((pos, sigma, v as f32));
+                }
+            }
+            prev = Some(cur);
+            match next {
+                Some(n) => cur = n,
+                None => break,
+            }
+        }
+        ans.sort_by(|a, b| b.2.abs().total_cmp(&a.2.abs()));
+        ans
+    }
+}
+
+/// 以 `sigma` (单位: 毫米) 平滑 `data` 后, 计算尺度归一化的响应 `-σ² ∇²`.
+/// 拉普拉斯算子以中心差分按 `spacing` 换算为物理距离, 边界处取最近的边缘值.
+fn normalized_log(data: &Array3<f64>, sigma: f64, spacing: [f64; 3]) -> Array3<f64> {
+    let mut smooth = data.clone();
+    for (axis, dim) in spacing.iter().enumerate() {
+        smooth = convolve_axis(&smooth, Axis(axis), &gaussian_kernel(sigma / dim));
+    }
+    let (z, h, w) = smooth.dim();
+    let shape = [z, h, w];
+    Array3::from_shape_fn((z, h, w), |(i, j, k)| {
+        let c = [i, j, k];
+        let center = smooth[(i, j, k)];
+        let lap: f64 = (0..3)
+            .map(|a| {
+                let at = |pos: usize| {
+                    let mut p = c;
+                    p[a] = pos;
+                    smooth[(p[0], p[1], p[2])]
+                };
+                let (bwd, fwd) = (at(c[a].saturating_sub(1)), at((c[a] + 1).min(shape[a] - 1)));
+                (fwd - 2.0 * center + bwd) / spacing[a].powi(2)
+            })
+            .sum();
+        -sigma * sigma * lap
+    })
+}
+
+/// `cur` 中在 `scales` (包括 `cur` 本身) 的 3×3×3 邻域内都为极值 (正值取最大, 负值取最小),
+/// 且绝对值不小于 `threshold` 的非边缘体素.
+fn local_extrema(cur: &Array3<f64>, scales: &[&Array3<f64>], threshold: f64) -> Vec<(Idx3d, f64)> {
+    let (z, h, w) = cur.dim();
+    if z < 3 || h < 3 || w < 3 {
+        return vec![];
+    }
+    let mut ans = vec![];
+    for i in 1..z - 1 {
+        for j in 1..h - 1 {
+            for k in 1..w - 1 {
+                let v = cur[(i, j, k)];
+                if v.abs() < threshold || v == 0.0 {
+                    continue;
+                }
+                let sign = v.signum();
+                let is_extremum = scales.iter().all(|s| {
+                    (i - 1..=i + 1).all(|a| {
+                        (j - 1..=j + 1)
+                            .all(|b| (k - 1..=k + 1).all(|c| sign * s[(a, b, c)] <= sign * v))
+                    })
+                });
+                if is_extremum {
+                    ans.push(((i, j, k), v));
+                }
+            }
+        }
+    }
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::{LITS_BACKGROUND, LITS_LIVER};
+
+    #[test]
+    fn test_detect_blobs() {
+        // [w, h, z] = [32, 32, 32], 各向同性 1 毫米. 两个标准差不同的高斯斑点:
+        // 小而亮的位于 (z, h, w) = (8, 10, 10), 大而暗的位于 (20, 19, 18).
+        let (small, large) = ((8usize, 10usize, 10usize), (20usize, 19usize, 18usize));
+        let gauss = |(z, h, w): Idx3d, c: Idx3d, s: f64| {
+            let d2 = [(z, c.0), (h, c.1), (w, c.2)]
+                .map(|(a, b)| (a as f64 - b as f64).powi(2))
+                .iter()
+                .sum::<f64>();
+            (-d2 / (2.0 * s * s)).exp()
+        };
+        let data = Array3::from_shape_fn((32, 32, 32), |(w, h, z)| {
+            let p = (z, h, w);
+            (60.0 + 100.0 * gauss(p, small, 2.0) - 80.0 * gauss(p, large, 4.0)) as f32
+        });
+        let scan = CtScan::fake(data, [1.0; 3], 1, [0.0, 0.0, 1.0]);
+
+        let sigmas: Vec<f64> = (0..12).map(|i| 1.0 + 0.25 * i as f64).collect();
+        let blobs = scan.detect_blobs(&sigmas, 5.0);
+        assert_eq!(blobs.len(), 2, "{blobs:?}");
+        let find = |c: Idx3d| *blobs.iter().find(|b| b.0 == c).unwrap();
+
+        // 选出的尺度为离 `s * sqrt(2 / 3)` 最近的候选尺度.
+        let expected = |s: f64| {
+            let t = s * (2.0f64 / 3.0).sqrt();
+            *sigmas
+                .iter()
+                .min_by(|a, b| (*a - t).abs().total_cmp(&(*b - t).abs()))
+                .unwrap()
+        };
+        let (_, s, r) = find(small);
+        assert_eq!(s, expected(2.0));
+        assert!(r > 0.0);
+        let (_, s, r) = find(large);
+        assert_eq!(s, expected(4.0));
+        assert!(r < 0.0);
+
+        // 限制在标注内时, 只保留标注覆盖的斑点.
+        let label = Array3::from_shape_fn((32, 32, 32), |(_, _, z)| {
+            if z >= 15 {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let label = CtLabel::fake(label, [1.0; 3], 1, [0.0, 0.0, 1.0]);
+        let inside = scan.detect_blobs_in_label(&sigmas, 5.0, &label, Foreground::LiverOnly);
+        assert_eq!(inside.len(), 1);
+        assert_eq!(inside[0].0, large);
+
+        // 阈值过高时没有结果.
+        assert!(scan.detect_blobs(&sigmas, 1e6).is_empty());
+    }
+}
@@ -0,0 +1,363 @@
+//! 3D 连通分量及其统计信息.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
+
+use ndarray::{s, Array3};
+
+use super::{CtData3d, CtLabel, NiftiHeaderAttr};
+use crate::consts::gray::{is_liver, is_tumor, LITS_TUMOR};
+use crate::{Idx3d, Predicate};
+
+/// 单个 3D 连通分量的统计信息.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStat {
+    /// 体素个数.
+    pub voxel_count: usize,
+
+    /// 实际体积, 以立方毫米为单位.
+    pub volume_mm3: f64,
+
+    /// 包围盒 `[min, max)`, 以 (z, H, W) 格式给出.
+    pub bbox: (Idx3d, Idx3d),
+
+    /// 体素坐标意义下的质心, 按 \[z, H, W\] 格式给出.
+    pub centroid_voxel: [f64; 3],
+
+    /// 该分量覆盖的水平切片范围.
+    pub z_extent: Range<usize>,
+}
+
+impl ComponentStat {
+    /// 根据分量内的全部体素坐标计算统计信息. `voxel` 为单个体素体积.
+    ///
+    /// `component` 不能为空.
+    fn new(component: &[Idx3d], voxel: f64) -> Self {
+        let (z, h, w) = component[0];
+        let (mut min, mut max) = ((z, h, w), (z, h, w));
+        let mut sum = [0.0; 3];
+        for &(z, h, w) in component {
+            min = (min.0.min(z), min.1.min(h), min.2.min(w));
+            max = (max.0.max(z), max.1.max(h), max.2.max(w));
+            sum[0] += z as f64;
+            sum[1] += h as f64;
+            sum[2] += w as f64;
+        }
+        let n = component.len();
+        Self {
+            voxel_count: n,
+            volume_mm3: n as f64 * voxel,
+            bbox: (min, (max.0 + 1, max.1 + 1, max.2 + 1)),
+            centroid_voxel: sum.map(|s| s / n as f64),
+            z_extent: min.0..max.0 + 1,
+        }
+    }
+}
+
+/// 肿瘤负荷汇总, 由 [`CtData3d::tumor_burden`] 计算.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TumorBurden {
+    /// 肿瘤总体积, 以立方毫米为单位.
+    pub tumor_volume_mm3: f64,
+
+    /// 整个肝脏 (肝实质 + 肿瘤) 的体积, 以立方毫米为单位.
+    pub liver_volume_mm3: f64,
+
+    /// 肿瘤体积占整个肝脏体积的比例. 若不存在肝脏体素, 则为 NaN.
+    pub burden_ratio: f64,
+
+    /// 病灶个数, 即肿瘤体素的 3D 连通分量 (6-相邻) 个数.
+    pub lesion_count: usize,
+}
+
+impl CtLabel {
+    /// 按照 6-相邻 (前后上下左右) 规则获取所有满足 `pred` 的 3D 连通分量.
+    /// 两个体素属于同一个分量, 当且仅当存在一条连接它们的 6-相邻路径,
+    /// 且路径上的所有体素都满足谓词 `pred`.
+    ///
+    /// 分量按照其首个体素的行优先顺序排列.
+    pub fn components_3d(&self, pred: Predicate) -> Vec<Vec<Idx3d>> {
+        let mut visited = Array3::from_elem(self.data.dim(), false);
+        let mut ans = Vec::new();
+        let mut bfs_q = VecDeque::new();
+
+        for (pos, &p) in self.data.indexed_iter() {
+            if visited[pos] || !pred(p) {
+                continue;
+            }
+            visited[pos] = true;
+            bfs_q.push_back(pos);
+            let mut component = Vec::new();
+            while let Some(cur) = bfs_q.pop_front() {
+                component.push(cur);
+                for nb in self.diamond_neighbours(cur) {
+                    if !visited[nb] && pred(self[nb]) {
+                        visited[nb] = true;
+                        bfs_q.push_back(nb);
+                    }
+                }
+            }
+            ans.push(component);
+        }
+        ans
+    }
+
+    /// 获取所有满足 `pred` 的 3D 连通分量 (6-相邻) 的统计信息,
+    /// 按照体素个数降序排列.
+    pub fn component_stats_3d(&self, pred: Predicate) -> Vec<ComponentStat> {
+        let voxel = self.voxel();
+        let mut stats: Vec<_> = self
+            .components_3d(pred)
+            .iter()
+            .map(|c| ComponentStat::new(c, voxel))
+            .collect();
+        stats.sort_by_key(|s| std::cmp::Reverse(s.voxel_count));
+        stats
+    }
+
+    /// 肿瘤与肝脏区域的邻接关系, 用于报告每个病灶所在的肝脏区域 (如肝叶).
+    ///
+    /// 分别以 `is_tumor` 和 `is_liver` 求出 3D 连通分量 (见 [`Self::components_3d`],
+    /// 下文的索引即为分量在其返回值中的索引). 对于每个肿瘤分量, 统计它与各肝脏分量
+    /// 共享的 6-相邻面的个数, 并与共享面最多的肝脏分量配对 (相同时取索引较小者).
+    ///
+    /// 返回值为按肿瘤分量索引升序排列的 `(肿瘤分量索引, 肝脏分量索引)`.
+    /// 不与任何肝脏体素相邻的肿瘤分量不出现在结果中.
+    pub fn tumor_adjacency(&self) -> Vec<(usize, usize)> {
+        let mut owner = Array3::from_elem(self.data.dim(), usize::MAX);
+        for (i, component) in self.components_3d(is_liver).iter().enumerate() {
+            for &pos in component {
+                owner[pos] = i;
+            }
+        }
+
+        let mut ans = Vec::new();
+        for (i, tumor) in self.components_3d(is_tumor).iter().enumerate() {
+            let mut faces: BTreeMap<usize, usize> = BTreeMap::new();
+            for &pos in tumor {
+                for nb in self.diamond_neighbours(pos) {
+                    if owner[nb] != usize::MAX {
+                        *faces.entry(owner[nb]).or_default() += 1;
+                    }
+                }
+            }
+            // 按索引升序迭代, `max_by_key` 在相同时保留最后一个, 因此以 `Reverse` 取最小索引.
+            if let Some((&liver, _)) = faces
+                .iter()
+                .max_by_key(|&(&liver, &count)| (count, std::cmp::Reverse(liver)))
+            {
+                ans.push((i, liver));
+            }
+        }
+        ans
+    }
+
+    /// 满足 `pred` 的前景的 3D 欧拉示性数: 连通分量数 - 隧道数 + 空腔数.
+    ///
+    /// 每个前景体素被视为一个闭的单位立方体, 统计它们的并集中顶点 `V`, 棱 `E`,
+    /// 面 `F` 和立方体 `C` 的个数, 返回 `V - E + F - C`. 在这一约定下,
+    /// 前景按照 26-相邻, 背景按照 6-相邻计算连通性.
+    ///
+    /// 例如实心球为 1, 被打穿一条隧道的球 (亏格为 1) 为 0, 两个分离的球为 2.
+    /// 该值可以作为肝脏碎片化程度或血管孔隙的拓扑描述量.
+    pub fn euler_characteristic_3d(&self, pred: Predicate) -> i64 {
+        let (z, h, w) = self.data.dim();
+        // 四周填充一层背景, 使得位于数据边界上的单元也能被完整地统计.
+        let mut mask = Array3::from_elem((z + 2, h + 2, w + 2), false);
+        mask.slice_mut(s![1..=z, 1..=h, 1..=w])
+            .assign(&self.data.mapv(pred));
+
+        let mut chi = 0;
+        // `cells` 的第 `i` 位表示单元在第 `i` 个轴上跨越一个体素 (窗口宽 1),
+        // 否则位于格点上, 由该轴两侧的体素共享 (窗口宽 2). 例如 0 为顶点, 7 为立方体.
+        for cells in 0..8u32 {
+            let window = [0, 1, 2].map(|i| if cells >> i & 1 == 1 { 1 } else { 2 });
+            let count = mask
+                .windows(window)
+                .into_iter()
+                .filter(|win| win.iter().any(|&b| b))
+                .count() as i64;
+            chi += if cells.count_ones() % 2 == 0 {
+                count
+            } else {
+                -count
+            };
+        }
+        chi
+    }
+}
+
+impl CtData3d {
+    /// 汇总标注中的肿瘤负荷: 肿瘤与整个肝脏的体积 (根据 `pixdim` 换算), 两者之比,
+    /// 以及病灶个数.
+    pub fn tumor_burden(&self) -> TumorBurden {
+        let label = &self.label;
+        let voxel = label.voxel();
+        let [_, liver, tumor] = label.numeric_statistics();
+        let tumor_volume_mm3 = tumor as f64 * voxel;
+        let liver_volume_mm3 = (liver + tumor) as f64 * voxel;
+        TumorBurden {
+            tumor_volume_mm3,
+            liver_volume_mm3,
+            burden_ratio: if liver + tumor == 0 {
+                f64::NAN
+            } else {
+                tumor as f64 / (liver + tumor) as f64
+            },
+            lesion_count: label.components_3d(|p| p == LITS_TUMOR).len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::{is_liver_or_tumor, LITS_LIVER};
+    use crate::CtScan;
+    use ndarray::s;
+
+    #[test]
+    fn test_component_stats_3d() {
+        // [w, h, z] = [10, 10, 6].
+        let mut raw = Array3::<u8>::zeros((10, 10, 6));
+        // 大块: w 1..4, h 1..3, z 0..2, 共 12 个体素.
+        raw.slice_mut(s![1..4, 1..3, 0..2]).fill(LITS_LIVER);
+        raw[[2, 1, 1]] = LITS_TUMOR;
+        // 小块: 单个体素, 与大块不相邻.
+        raw[[8, 8, 4]] = LITS_LIVER;
+        let label = CtLabel::fake(raw, [0.5, 0.5, 2.0], 1, [0.0, 0.0, 1.0]);
+
+        let stats = label.component_stats_3d(is_liver_or_tumor);
+        assert_eq!(stats.len(), 2);
+
+        let big = &stats[0];
+        assert_eq!(big.voxel_count, 12);
+        assert_eq!(big.volume_mm3, 6.0);
+        assert_eq!(big.bbox, ((0, 1, 1), (2, 3, 4)));
+        assert_eq!(big.centroid_voxel, [0.5, 1.5, 2.0]);
+        assert_eq!(big.z_extent, 0..2);
+
+        let small = &stats[1];
+        assert_eq!(small.voxel_count, 1);
+        assert_eq!(small.bbox, ((4, 8, 8), (5, 9, 9)));
+        assert_eq!(small.centroid_voxel, [4.0, 8.0, 8.0]);
+        assert_eq!(small.z_extent, 4..5);
+
+        // 仅统计肿瘤时, 大块被拆解为单个肿瘤体素.
+        let stats = label.component_stats_3d(|p| p == LITS_TUMOR);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].voxel_count, 1);
+    }
+
+    #[test]
+    fn test_tumor_burden() {
+        // [w, h, z] = [12, 10, 4], 肝脏占据 w 1..11, h 1..9, 共 10 * 8 * 4 = 320 个体素.
+        let mut raw = Array3::<u8>::zeros((12, 10, 4));
+        raw.slice_mut(s![1..11, 1..9, ..]).fill(LITS_LIVER);
+        // 两个互不相邻的肿瘤: 2 * 2 * 2 = 8 个体素和 2 * 3 * 1 = 6 个体素.
+        raw.slice_mut(s![2..4, 2..4, 1..3]).fill(LITS_TUMOR);
+        raw.slice_mut(s![7..9, 4..7, 2..3]).fill(LITS_TUMOR);
+        let label = CtLabel::fake(raw, [0.5, 0.5, 2.0], 1, [0.0, 0.0, 1.0]);
+        let scan = CtScan {
+            header: label.header.clone(),
+            data: Array3::zeros(label.data.dim()),
+        };
+        let ct = CtData3d { scan, label };
+
+        let burden = ct.tumor_burden();
+        assert_eq!(burden.lesion_count, 2);
+        assert_eq!(burden.tumor_volume_mm3, 14.0 * 0.5);
+        assert_eq!(burden.liver_volume_mm3, 320.0 * 0.5);
+        assert_eq!(burden.burden_ratio, 14.0 / 320.0);
+
+        // 无肝脏时比例为 NaN.
+        let empty = CtLabel::fake(Array3::zeros((4, 4, 2)), [1.0; 3], 1, [0.0; 3]);
+        let scan = CtScan {
+            header: empty.header.clone(),
+            data: Array3::zeros(empty.data.dim()),
+        };
+        let burden = CtData3d { scan, label: empty }.tumor_burden();
+        assert_eq!(burden.lesion_count, 0);
+        assert!(burden.burden_ratio.is_nan());
+    }
+
+    #[test]
+    fn test_euler_characteristic_3d() {
+        /// 以 `(w, h, z)` 为球心, 半径为 `r` 的球.
+        fn ball(raw: &mut Array3<u8>, (cw, ch, cz): (f64, f64, f64), r: f64) {
+            for ((w, h, z), p) in raw.indexed_iter_mut() {
+                let d = (w as f64 - cw).hypot(h as f64 - ch).hypot(z as f64 - cz);
+                if d <= r {
+                    *p = LITS_LIVER;
+                }
+            }
+        }
+        let fake = |raw| CtLabel::fake(raw, [1.0; 3], 0, [0.0; 3]);
+
+        let mut raw = Array3::<u8>::zeros((16, 16, 16));
+        assert_eq!(fake(raw.clone()).euler_characteristic_3d(is_liver), 0);
+        ball(&mut raw, (8.0, 8.0, 8.0), 6.0);
+        let solid = fake(raw.clone());
+        assert_eq!(solid.euler_characteristic_3d(is_liver), 1);
+
+        // 沿 z 方向打穿一条隧道.
+        let mut tunnel = raw.clone();
+        tunnel.slice_mut(s![7..10, 7..10, ..]).fill(0);
+        assert_eq!(fake(tunnel).euler_characteristic_3d(is_liver), 0);
+
+        // 内部空腔.
+        let mut hollow = raw.clone();
+        hollow.slice_mut(s![7..10, 7..10, 7..10]).fill(0);
+        assert_eq!(fake(hollow).euler_characteristic_3d(is_liver), 2);
+
+        // 两个分离的球, 其中一个紧贴数据边界.
+        let mut raw = Array3::<u8>::zeros((24, 12, 12));
+        ball(&mut raw, (5.0, 5.0, 5.0), 4.0);
+        ball(&mut raw, (19.0, 6.0, 6.0), 6.0);
+        assert_eq!(fake(raw).euler_characteristic_3d(is_liver), 2);
+    }
+
+    #[test]
+    fn test_tumor_adjacency() {
+        // [w, h, z] = [20, 8, 4]: 两个分离的肝叶, 各含一个肿瘤.
+        let mut raw = Array3::<u8>::zeros((20, 8, 4));
+        raw.slice_mut(s![0..8, .., ..]).fill(LITS_LIVER);
+        raw.slice_mut(s![11..20, .., ..]).fill(LITS_LIVER);
+        // 右叶内部的肿瘤先被行优先扫描到 (z 更小).
+        raw.slice_mut(s![13..16, 3..5, 0..2]).fill(LITS_TUMOR);
+        raw.slice_mut(s![2..4, 2..4, 2..4]).fill(LITS_TUMOR);
+        // 位于两叶之间的肿瘤与右叶共享更多的面.
+        raw.slice_mut(s![8..11, 0..2, 3..4]).fill(LITS_TUMOR);
+        raw[[10, 2, 3]] = LITS_TUMOR;
+        // 孤立的肿瘤.
+        raw[[9, 7, 0]] = LITS_TUMOR;
+        let label = CtLabel::fake(raw, [1.0; 3], 0, [0.0; 3]);
+
+        let livers = label.components_3d(is_liver);
+        let tumors = label.components_3d(is_tumor);
+        assert_eq!((livers.len(), tumors.len()), (2, 4));
+        // 左叶 (w < 8) 的首个体素在行优先顺序下先于右叶.
+        let lobe_of = |t: usize| {
+            let (_, _, w) = tumors[t][0];
+            if w < 8 {
+                0
+            } else {
+                1
+            }
+        };
+        let find = |w: usize, z: usize| {
+            tumors
+                .iter()
+                .position(|c| c.iter().any(|&(cz, _, cw)| cw == w && cz == z))
+                .unwrap()
+        };
+        let (right, left, between, isolated) = (find(13, 0), find(2, 2), find(8, 3), find(9, 0));
+        assert_eq!((lobe_of(left), lobe_of(right)), (0, 1));
+
+        let pairs = label.tumor_adjacency();
+        let mut expected = vec![(right, 1), (left, 0), (between, 1)];
+        expected.sort();
+        assert_eq!(pairs, expected);
+        assert!(pairs.iter().all(|&(t, _)| t != isolated));
+    }
+}
@@ -0,0 +1,612 @@
+//! CT 扫描 HU 值的汇总统计.
+
+use super::{CtData3d, CtLabel, CtScan, CtWindow, NiftiHeaderAttr};
+use crate::consts::gray::{LITS_BACKGROUND, LITS_LIVER};
+use crate::consts::Foreground;
+
+/// CT HU 值的汇总统计信息. 仅统计有限值 (忽略 NaN 和 inf).
+///
+/// 若没有任何有限值参与统计, 则 `count` 为 0, 其余字段均为 NaN.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HuStats {
+    /// 最小值.
+    pub min: f64,
+
+    /// 最大值.
+    pub max: f64,
+
+    /// 平均值.
+    pub mean: f64,
+
+    /// 总体标准差.
+    pub std: f64,
+
+    /// 参与统计的体素个数.
+    pub count: u64,
+}
+
+/// 中心 ROI 内的肝衰减估计, 见 [`CtData3d::estimate_liver_attenuation`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiverAttenuation {
+    /// 参与统计的体素个数 (ROI 内 HU 值为有限值的肝脏体素).
+    pub count: u64,
+
+    /// 平均 HU 值.
+    pub mean: f64,
+
+    /// 截尾平均 HU 值: 去掉最低和最高各 10% 的体素后的平均值,
+    /// 对 ROI 内偶然出现的血管或钙化等离群值不敏感.
+    pub trimmed_mean: f64,
+
+    /// HU 值的中位数.
+    pub median: f64,
+
+    /// HU 值的总体标准差.
+    pub std: f64,
+}
+
+/// [`LiverAttenuation::trimmed_mean`] 在两端各去掉的比例.
+const TRIM_FRACTION: f64 = 0.1;
+
+/// Welford 在线算法的累加器. 单遍扫描即可得到数值稳定的均值和方差.
+struct Welford {
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+    count: u64,
+}
+
+impl Welford {
+    fn new() -> Self {
+        Self {
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            count: 0,
+        }
+    }
+
+    /// 加入一个值. 非有限值会被忽略.
+    #[inline]
+    fn push(&mut self, value: f32) {
+        if !value.is_finite() {
+            return;
+        }
+        let value = value as f64;
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn finish(self) -> HuStats {
+        if self.count == 0 {
+            return HuStats {
+                min: f64::NAN,
+                max: f64::NAN,
+                mean: f64::NAN,
+                std: f64::NAN,
+                count: 0,
+            };
+        }
+        HuStats {
+            min: self.min,
+            max: self.max,
+            mean: self.mean,
+            std: (self.m2 / self.count as f64).sqrt(),
+            count: self.count,
+        }
+    }
+}
+
+impl CtScan {
+    /// 单遍计算所有有限体素 HU 值的最小值, 最大值, 平均值和标准差.
+    pub fn hu_stats(&self) -> HuStats {
+        let mut acc = Welford::new();
+        self.data.iter().for_each(|&v| acc.push(v));
+        acc.finish()
+    }
+
+    /// 同 `hu_stats`, 但仅统计 `label` 中值为 `target` 的体素.
+    ///
+    /// 若 `self` 与 `label` 形状不一致, 则程序 panic.
+    pub fn hu_stats_in_label(&self, label: &CtLabel, target: u8) -> HuStats {
+        assert_eq!(self.shape(), label.shape(), "CT 扫描和标注形状不一致");
+        let mut acc = Welford::new();
+        self.data
+            .iter()
+            .zip(label.data.iter())
+            .filter(|(_, &l)| l == target)
+            .for_each(|(&v, _)| acc.push(v));
+        acc.finish()
+    }
+
+    /// 非有限 (NaN 或 ±inf) 体素的个数.
+    ///
+    /// 真实的 nii 文件中偶尔会出现这样的体素, 它们会使 [`CtScan::mean_hu`]
+    /// 等不做过滤的计算结果变为 NaN 或 inf.
+    pub fn nonfinite_count(&self) -> usize {
+        self.data.iter().filter(|v| !v.is_finite()).count()
+    }
+
+    /// 将所有非有限 (NaN 或 ±inf) 体素的 HU 值替换为 `with`, 返回被替换的体素个数.
+    pub fn replace_nonfinite(&mut self, with: f32) -> usize {
+        let mut count = 0;
+        self.data
+            .iter_mut()
+            .filter(|v| !v.is_finite())
+            .for_each(|v| {
+                *v = with;
+                count += 1;
+            });
+        count
+    }
+}
+
+/// 在升序排列的 `sorted` 上以线性插值计算 `q` (0.0 ~ 1.0) 分位数.
+///
+/// `sorted` 不能为空.
+fn percentile(sorted: &[f32], q: f64) -> f64 {
+    let pos = (sorted.len() - 1) as f64 * q;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    let frac = pos - lo as f64;
+    sorted[lo] as f64 * (1.0 - frac) + sorted[hi] as f64 * frac
+}
+
+impl CtScan {
+    /// 根据 `label` 中肝脏区域 (不含肿瘤) 的 HU 值分布自动构建 CT 窗口.
+    ///
+    /// 窗口的下限和上限分别为肝脏区域 HU 值的第 5 和第 95 百分位数
+    /// (线性插值, 忽略非有限值), 从而使显示效果适应每位患者的实际肝衰减.
+    ///
+    /// 若肝脏区域为空, 或两个百分位数相等 (窗宽为 0), 或超出 `CtWindow`
+    /// 的合法范围, 则返回 `None`. 若 `self` 与 `label` 形状不一致, 则程序 panic.
+    pub fn auto_liver_window(&self, label: &CtLabel) -> Option<CtWindow> {
+        assert_eq!(self.shape(), label.shape(), "CT 扫描和标注形状不一致");
+        let mut hu: Vec<f32> = self
+            .data
+            .iter()
+            .zip(label.data.iter())
+            .filter_map(|(&v, &l)| (l == LITS_LIVER && v.is_finite()).then_some(v))
+            .collect();
+        if hu.is_empty() {
+            return None;
+        }
+        hu.sort_unstable_by(f32::total_cmp);
+        let (lo, hi) = (percentile(&hu, 0.05), percentile(&hu, 0.95));
+        CtWindow::new(((lo + hi) / 2.0) as f32, (hi - lo) as f32)
+    }
+}
+
+/// Otsu 阈值法所用直方图的分箱个数.
+const OTSU_BINS: usize = 256;
+
+impl CtScan {
+    /// 以 Otsu 方法计算最大化类间方差的 HU 阈值.
+    ///
+    /// 在所有有限体素的 `[min, max]` 范围内建立等宽直方图 (共 256 个分箱),
+    /// 枚举所有分箱边界作为候选阈值, 取类间方差最大者. 返回值即为该分箱边界的 HU 值,
+    /// 其精度为一个分箱宽度 `(max - min) / 256`.
+    ///
+    /// 若所有有限体素的 HU 值都相同, 则返回该值; 若不存在有限体素, 则返回 NaN.
+    pub fn otsu_threshold(&self) -> f32 {
+        let stats = self.hu_stats();
+        if stats.count == 0 {
+            return f32::NAN;
+        }
+        let (min, max) = (stats.min, stats.max);
+        if min == max {
+            return min as f32;
+        }
+        let width = (max - min) / OTSU_BINS as f64;
+        let mut hist = [0u64; OTSU_BINS];
+        for v in self.data.iter().filter(|v| v.is_finite()) {
+            let bin = ((*v as f64 - min) / width) as usize;
+            hist[bin.min(OTSU_BINS - 1)] += 1;
+        }
+
+        let total = stats.count as f64;
+        let sum_all: f64 = hist
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| i as f64 * c as f64)
+            .sum();
+        let (mut w0, mut sum0) = (0.0, 0.0);
+        let (mut best, mut best_var) = (0, f64::NEG_INFINITY);
+        // 类 0 为分箱 `0..=k`, 类 1 为其余分箱.
+        for (k, &c) in hist.iter().enumerate().take(OTSU_BINS - 1) {
+            w0 += c as f64;
+            sum0 += k as f64 * c as f64;
+            let w1 = total - w0;
+            if w0 == 0.0 || w1 == 0.0 {
+                continue;
+            }
+            let diff = sum0 / w0 - (sum_all - sum0) / w1;
+            let var = w0 * w1 * diff * diff;
+            if var > best_var {
+                (best, best_var) = (k, var);
+            }
+        }
+        (min + (best + 1) as f64 * width) as f32
+    }
+
+    /// 以 [`Self::otsu_threshold`] 二值化扫描: HU 值大于阈值的体素为 `LITS_LIVER`,
+    /// 其余 (包括非有限值) 为 `LITS_BACKGROUND`. 可以作为无参数的初始分割.
+    ///
+    /// 返回标注的 header 复制自扫描, 仅把数据类型改为 `u8`.
+    pub fn binarize_otsu(&self) -> CtLabel {
+        let t = self.otsu_threshold();
+        CtLabel {
+            header: self.derived_label_header(),
+            data: self
+                .data
+                .mapv(|v| if v > t { LITS_LIVER } else { LITS_BACKGROUND }),
+        }
+    }
+}
+
+impl CtData3d {
+    /// 检查扫描与标注是否匹配: 返回肝脏体素 (`LITS_LIVER`, 不含肿瘤) 中
+    /// HU 值落在闭区间 `expected_range` (如 `(0.0, 150.0)`) 内的比例.
+    ///
+    /// 比例过低通常意味着扫描与标注错配, 或扫描的 HU 值本身有误.
+    /// 非有限的 HU 值总是被视为在区间以外. 若不存在肝脏体素, 则返回 NaN.
+    pub fn label_hu_sanity(&self, (lo, hi): (f64, f64)) -> f64 {
+        let (mut inside, mut total) = (0usize, 0usize);
+        for (&v, _) in self.iter().filter(|(_, &l)| l == LITS_LIVER) {
+            total += 1;
+            if (lo..=hi).contains(&(v as f64)) {
+                inside += 1;
+            }
+        }
+        inside as f64 / total as f64
+    }
+
+    /// 端到端的肝衰减估计: 以 [`CtLabel::center_roi_3d_fg`] 在标注中找到肝脏的形态学中心,
+    /// 取半径为 `radius` (单位: 毫米) 的球形 ROI 内的肝脏体素 (`LITS_LIVER`, 不含肿瘤),
+    /// 统计其 HU 值的平均值, 截尾平均值, 中位数和标准差. `anisotropic` 的含义同
+    /// [`CtLabel::center_roi_3d_fg`].
+    ///
+    /// 与 [`CtLabel::center_roi_3d_fg`] 相同, 标注中不应当存在背景空洞.
+    /// 非有限的 HU 值被忽略. 若 ROI 为空或其中不存在有限的 HU 值, 则返回 `None`.
+    pub fn estimate_liver_attenuation(
+        &self,
+        radius: f64,
+        anisotropic: bool,
+    ) -> Option<LiverAttenuation> {
+        let roi = self
+            .label
+            .center_roi_3d_fg(radius, anisotropic, Foreground::LiverOnly);
+        let mut hu: Vec<f32> = roi
+            .into_iter()
+            .map(|pos| self.scan.data[pos])
+            .filter(|v| v.is_finite())
+            .collect();
+        if hu.is_empty() {
+            return None;
+        }
+
+        let mut acc = Welford::new();
+        hu.iter().for_each(|&v| acc.push(v));
+        let stats = acc.finish();
+
+        hu.sort_unstable_by(f32::total_cmp);
+        let trim = (hu.len() as f64 * TRIM_FRACTION) as usize;
+        let kept = &hu[trim..hu.len() - trim];
+        let trimmed_mean = kept.iter().map(|&v| v as f64).sum::<f64>() / kept.len() as f64;
+
+        Some(LiverAttenuation {
+            count: stats.count,
+            mean: stats.mean,
+            trimmed_mean,
+            median: percentile(&hu, 0.5),
+            std: stats.std,
+        })
+    }
+
+    /// 逐层的肝衰减剖面: 对每张含有肝脏体素 (`LITS_LIVER`, 不含肿瘤) 的水平切片,
+    /// 返回 `(z 索引, 该层肝脏体素的平均 HU 值)`, 按 z 升序排列.
+    ///
+    /// 可用于观察肝衰减沿 z 轴的变化 (如扫描伪影或脂肪肝的分布不均).
+    /// 非有限的 HU 值被忽略; 肝脏体素全部为非有限值的切片被跳过.
+    pub fn liver_attenuation_profile(&self) -> Vec<(usize, f64)> {
+        self.scan
+            .data
+            .outer_iter()
+            .zip(self.label.data.outer_iter())
+            .enumerate()
+            .filter_map(|(z, (scan, label))| {
+                let (sum, count) = scan
+                    .iter()
+                    .zip(label.iter())
+                    .filter(|&(v, &l)| l == LITS_LIVER && v.is_finite())
+                    .fold((0.0, 0usize), |(sum, count), (&v, _)| {
+                        (sum + v as f64, count + 1)
+                    });
+                (count > 0).then(|| (z, sum / count as f64))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::LITS_TUMOR;
+    use ndarray::Array3;
+
+    #[test]
+    fn test_hu_stats() {
+        // [w, h, z] = [10, 10, 4]. 0 ~ 99 的整数各出现 4 次.
+        let raw =
+            Array3::<u8>::from_shape_fn(
+                (10, 10, 4),
+                |(w, _, _)| {
+                    if w < 5 {
+                        LITS_LIVER
+                    } else {
+                        LITS_TUMOR
+                    }
+                },
+            );
+        let label = CtLabel::fake(raw, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+        let mut data = Array3::from_shape_fn(label.data.dim(), |(_, h, w)| (h * 10 + w) as f32);
+        let scan = CtScan {
+            header: label.header.clone(),
+            data: data.clone(),
+        };
+
+        let stats = scan.hu_stats();
+        assert_eq!(stats.count, 400);
+        assert_eq!((stats.min, stats.max), (0.0, 99.0));
+        assert!((stats.mean - 49.5).abs() < 1e-9);
+        // 离散均匀分布 {0, ..., n - 1} 的标准差为 sqrt((n^2 - 1) / 12).
+        assert!((stats.std - (9999.0f64 / 12.0).sqrt()).abs() < 1e-9);
+
+        // 非有限值被忽略.
+        data[(0, 0, 0)] = f32::NAN;
+        data[(1, 0, 0)] = f32::INFINITY;
+        data[(2, 0, 0)] = f32::NEG_INFINITY;
+        let scan = CtScan {
+            header: label.header.clone(),
+            data,
+        };
+        let stats = scan.hu_stats();
+        assert_eq!(stats.count, 397);
+        assert!(stats.mean.is_finite() && stats.std.is_finite());
+
+        // 肝脏区域: 列号 0 ~ 4; 第 3 层的 (0, 0) 仍为有限值.
+        let stats = scan.hu_stats_in_label(&label, LITS_LIVER);
+        assert_eq!(stats.count, 197);
+        assert_eq!((stats.min, stats.max), (0.0, 94.0));
+
+        let stats = scan.hu_stats_in_label(&label, 7);
+        assert_eq!(stats.count, 0);
+        assert!(stats.mean.is_nan());
+    }
+
+    #[test]
+    fn test_auto_liver_window() {
+        // [w, h, z] = [11, 11, 2]. 第 0 层 (h, w) < (10, 10) 的区域为肝脏,
+        // HU 值为 h * 10 + w, 即 0 ~ 99 各一次; 其余为 -1000 HU 的背景.
+        let label = Array3::from_shape_fn((11, 11, 2), |(w, h, z)| {
+            if z == 0 && h < 10 && w < 10 {
+                LITS_LIVER
+            } else {
+                0
+            }
+        });
+        let scan = Array3::from_shape_fn((11, 11, 2), |(w, h, z)| {
+            if z == 0 && h < 10 && w < 10 {
+                (h * 10 + w) as f32
+            } else {
+                -1000.0
+            }
+        });
+        let label = CtLabel::fake(label, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+        let scan = CtScan::fake(scan, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+
+        let window = scan.auto_liver_window(&label).unwrap();
+        // 第 5 / 95 百分位数: 99 * 0.05 = 4.95, 99 * 0.95 = 94.05.
+        assert!((window.lower_bound() - 4.95).abs() < 1e-4);
+        assert!((window.upper_bound() - 94.05).abs() < 1e-4);
+
+        // 没有肝脏时无法构建窗口.
+        let empty = CtLabel::fake(
+            Array3::zeros((11, 11, 2)),
+            [1.0, 1.0, 1.0],
+            1,
+            [0.0, 0.0, 1.0],
+        );
+        assert!(scan.auto_liver_window(&empty).is_none());
+    }
+
+    #[test]
+    fn test_label_hu_sanity() {
+        // [w, h, z] = [16, 16, 2]. 左半部分为肝脏 (60 HU), 右半部分为脂肪 (-100 HU).
+        let label = Array3::from_shape_fn((16, 16, 2), |(w, _, _)| (w < 8) as u8);
+        let scan = label.mapv(|l| if l == LITS_LIVER { 60.0 } else { -100.0 });
+        let data = CtData3d::fake(scan.clone(), label.clone(), [1.0; 3], 0, [0.0; 3]);
+        assert_eq!(data.label_hu_sanity((0.0, 150.0)), 1.0);
+
+        // 左右翻转后的扫描与标注错配.
+        let mismatched = CtData3d::fake(
+            scan.slice(ndarray::s![..;-1, .., ..]).to_owned(),
+            label.clone(),
+            [1.0; 3],
+            0,
+            [0.0; 3],
+        );
+        assert_eq!(mismatched.label_hu_sanity((0.0, 150.0)), 0.0);
+
+        // 部分错配, 且非有限值被视为越界.
+        let mut partial = scan.clone();
+        partial.slice_mut(ndarray::s![..4, .., ..]).fill(-100.0);
+        partial[(4, 0, 0)] = f32::NAN;
+        let partial = CtData3d::fake(partial, label.clone(), [1.0; 3], 0, [0.0; 3]);
+        let frac = partial.label_hu_sanity((0.0, 150.0));
+        assert!((frac - 127.0 / 256.0).abs() < 1e-12, "{frac}");
+
+        let empty = CtData3d::fake(scan, Array3::zeros((16, 16, 2)), [1.0; 3], 0, [0.0; 3]);
+        assert!(empty.label_hu_sanity((0.0, 150.0)).is_nan());
+    }
+
+    #[test]
+    fn test_liver_attenuation_profile() {
+        // [w, h, z] = [8, 8, 6]. 肝脏的 HU 值沿 z 轴线性增加 (40 + 5z), 并叠加随 w 变化的扰动;
+        // 第 0 层和第 5 层没有肝脏, 肿瘤与背景的 HU 值不参与统计.
+        let label = Array3::from_shape_fn((8, 8, 6), |(w, h, z)| match (w, h, z) {
+            (_, _, 0 | 5) => LITS_BACKGROUND,
+            (0, 0, _) => LITS_TUMOR,
+            (_, 0..=5, _) => LITS_LIVER,
+            _ => LITS_BACKGROUND,
+        });
+        let mut scan = Array3::from_shape_fn((8, 8, 6), |(w, _, z)| {
+            40.0 + 5.0 * z as f32 + w as f32 - 3.5
+        });
+        scan[(0, 0, 2)] = 1000.0;
+        scan[(1, 1, 3)] = f32::NAN;
+        let data = CtData3d::fake(scan, label, [1.0; 3], 0, [0.0; 3]);
+
+        let profile = data.liver_attenuation_profile();
+        assert_eq!(
+            profile.iter().map(|p| p.0).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        // 单调递增.
+        assert!(profile.windows(2).all(|p| p[0].1 < p[1].1));
+        // 肿瘤体素 (h, w) = (0, 0) (含 1000 HU 的离群值) 被排除,
+        // 其余 47 个肝脏体素的扰动之和为 3.5; 第 3 层的 NaN 被忽略.
+        let expected = |z: usize| 40.0 + 5.0 * z as f64 + 3.5 / 47.0;
+        for &(z, mean) in &profile {
+            if z != 3 {
+                assert!((mean - expected(z)).abs() < 1e-4, "{z}: {mean}");
+            }
+        }
+        assert!(profile[2].1.is_finite());
+
+        let empty = CtData3d::fake(
+            Array3::zeros((8, 8, 6)),
+            Array3::zeros((8, 8, 6)),
+            [1.0; 3],
+            0,
+            [0.0; 3],
+        );
+        assert!(empty.liver_attenuation_profile().is_empty());
+    }
+
+    #[test]
+    fn test_otsu_threshold() {
+        // 双峰分布: 背景约 -100 HU, 肝脏约 60 HU, 两者之间没有体素.
+        let label = CtLabel::fake(Array3::zeros((20, 20, 4)), [1.0; 3], 1, [0.0; 3]);
+        let data = Array3::from_shape_fn(label.data.dim(), |(z, h, w)| {
+            let jitter = ((z * 7 + h * 3 + w) % 11) as f32;
+            if w < 8 {
+                -105.0 + jitter
+            } else {
+                55.0 + jitter
+            }
+        });
+        let scan = CtScan {
+            header: label.header.clone(),
+            data,
+        };
+
+        // 两类之间的任何阈值都能完美分开两类; Otsu 阈值应落在 (-95, 55] 之间,
+        // 允许一个分箱宽度的误差.
+        let t = scan.otsu_threshold();
+        let bin = (65.0 - -105.0) / 256.0;
+        assert!(t > -95.0 - bin && t <= 55.0 + bin, "{t}");
+
+        let mask = scan.binarize_otsu();
+        for ((_, _, w), &p) in mask.data.indexed_iter() {
+            assert_eq!(p == LITS_LIVER, w >= 8);
+        }
+
+        let flat = CtScan {
+            header: label.header.clone(),
+            data: Array3::from_elem(label.data.dim(), 40.0),
+        };
+        assert_eq!(flat.otsu_threshold(), 40.0);
+        assert!(flat
+            .binarize_otsu()
+            .data
+            .iter()
+            .all(|&p| p == LITS_BACKGROUND));
+    }
+
+    #[test]
+    fn test_replace_nonfinite() {
+        let label = CtLabel::fake(Array3::zeros((4, 4, 2)), [1.0; 3], 0, [0.0; 3]);
+        let mut scan = CtScan {
+            header: label.header.clone(),
+            data: Array3::from_elem(label.data.dim(), 50.0),
+        };
+        scan.data[(0, 0, 0)] = f32::NAN;
+        scan.data[(1, 2, 3)] = f32::INFINITY;
+        scan.data[(1, 3, 3)] = f32::NEG_INFINITY;
+        let all: Vec<_> = scan.data.indexed_iter().map(|(p, _)| p).collect();
+        assert_eq!(scan.nonfinite_count(), 3);
+        assert!(scan.mean_hu(all.iter().copied()).is_nan());
+
+        assert_eq!(scan.replace_nonfinite(50.0), 3);
+        assert_eq!(scan.nonfinite_count(), 0);
+        assert_eq!(scan.mean_hu(all.iter().copied()), 50.0);
+        assert_eq!(scan.replace_nonfinite(0.0), 0);
+    }
+
+    #[test]
+    fn test_estimate_liver_attenuation() {
+        // [w, h, z] = [30, 30, 12] 的体积, 中间为肝脏长方体.
+        let label = Array3::from_shape_fn((30, 30, 12), |(w, h, z)| {
+            if (3..27).contains(&w) && (3..27).contains(&h) && (1..11).contains(&z) {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let label = CtLabel::fake(label, [1.0, 1.0, 2.0], 1, [0.0, 0.0, 1.0]);
+        let roi = label.center_roi_3d_fg(5.0, true, Foreground::LiverOnly);
+        assert!(roi.len() > 20);
+
+        // ROI 内的 HU 值交替为 50 和 60, 其余肝脏为 100 HU, 背景为 -1000 HU.
+        // 另有一个 1000 HU 的离群值和一个 NaN.
+        let mut scan = label
+            .data
+            .mapv(|l| if l == LITS_LIVER { 100.0 } else { -1000.0 });
+        for (i, &pos) in roi.iter().enumerate() {
+            scan[pos] = if i % 2 == 0 { 50.0 } else { 60.0 };
+        }
+        scan[roi[0]] = 1000.0;
+        scan[roi[1]] = f32::NAN;
+        let data = CtData3d {
+            scan: CtScan {
+                header: label.header.clone(),
+                data: scan,
+            },
+            label,
+        };
+
+        let est = data.estimate_liver_attenuation(5.0, true).unwrap();
+        assert_eq!(est.count as usize, roi.len() - 1);
+        // 离群值抬高了平均值, 但不影响中位数和截尾平均值.
+        assert!(est.mean > est.trimmed_mean + 3.0, "{est:?}");
+        assert!((50.0..=60.0).contains(&est.median), "{est:?}");
+        assert!((est.trimmed_mean - 55.0).abs() < 1.0, "{est:?}");
+        assert!(est.std > 5.0);
+
+        // 没有肝脏时无法估计.
+        let empty = CtData3d::fake(
+            Array3::zeros((8, 8, 4)),
+            Array3::zeros((8, 8, 4)),
+            [1.0; 3],
+            0,
+            [0.0; 3],
+        );
+        assert!(empty.estimate_liver_attenuation(5.0, true).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+//! 稀疏标注的层间插值.
+
+use super::slice::signed_edt;
+use crate::consts::gray::*;
+use crate::{CtLabel, NiftiHeaderAttr};
+use ndarray::{Array2, ArrayView2, Axis, Zip};
+
+impl CtLabel {
+    /// 对只标注了部分水平切片的稀疏标注, 为每个夹在两个已标注切片之间的未标注切片
+    /// 生成插值标注. `is_annotated(z)` 判断第 `z` 层是否已标注.
+    ///
+    /// 采用基于形状的插值 (shape-based interpolation): 对上下两个已标注切片的前景
+    /// 分别计算有符号欧几里得距离变换 (前景内部为到边界的正距离, 外部为负距离),
+    /// 按照与两者的 z 距离线性混合, 混合结果为正的像素即为插值前景. 因此形状会从
+    /// 一个切片平滑地 "变形" 到另一个切片, 而不是简单地取并集或交集.
+    ///
+    /// 肝脏 (含肿瘤) 与肿瘤分别插值: 插值肿瘤区域标为 `LITS_TUMOR`,
+    /// 其余插值前景标为 `LITS_LIVER`. 被插值的切片原有内容被完全覆盖;
+    /// 第一个已标注切片之前和最后一个已标注切片之后的切片保持不变.
+    pub fn interpolate_missing_slices(&mut self, is_annotated: impl Fn(usize) -> bool) {
+        let annotated: Vec<usize> = (0..self.len_z()).filter(|&z| is_annotated(z)).collect();
+        for pair in annotated.windows(2) {
+            let (lo, hi) = (pair[0], pair[1]);
+            if hi - lo < 2 {
+                continue;
+            }
+            let lo_slice = self.data.index_axis(Axis(0), lo);
+            let hi_slice = self.data.index_axis(Axis(0), hi);
+            let liver = SignedPair::new(lo_slice, hi_slice, is_liver_or_tumor);
+            let tumor = SignedPair::new(lo_slice, hi_slice, is_tumor);
+
+            for z in lo + 1..hi {
+                let t = (z - lo) as f64 / (hi - lo) as f64;
+                let liver = liver.blend(t);
+                let tumor = tumor.blend(t);
+                Zip::from(self.data.index_axis_mut(Axis(0), z))
+                    .and(&liver)
+                    .and(&tumor)
+                    .for_each(|p, &l, &t| {
+                        *p = if t > 0.0 {
+                            LITS_TUMOR
+                        } else if l > 0.0 {
+                            LITS_LIVER
+                        } else {
+                            LITS_BACKGROUND
+                        }
+                    });
+            }
+        }
+    }
+}
+
+/// 上下两个已标注切片中满足同一谓词的前景的有符号距离变换.
+struct SignedPair {
+    lo: Array2<f64>,
+    hi: Array2<f64>,
+}
+
+impl SignedPair {
+    fn new(lo: ArrayView2<u8>, hi: ArrayView2<u8>, pred: fn(u8) -> bool) -> Self {
+        Self {
+            lo: signed_edt(&lo.mapv(pred)),
+            hi: signed_edt(&hi.mapv(pred)),
+        }
+    }
+
+    /// 按 `(1 - t) * lo + t * hi` 线性混合.
+    fn blend(&self, t: f64) -> Array2<f64> {
+        Zip::from(&self.lo)
+            .and(&self.hi)
+            .map_collect(|&a, &b| (1.0 - t) * a + t * b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// 以 (h, w) = (16, 16) 为圆心, 半径为 `r` 的圆盘.
+    fn disk(r: f64) -> impl Fn(usize, usize) -> bool {
+        move |h, w| (h as f64 - 16.0).hypot(w as f64 - 16.0) <= r
+    }
+
+    #[test]
+    fn test_interpolate_missing_slices() {
+        // [w, h, z] = [33, 33, 5]: 第 0 层为半径 4 的圆, 第 4 层为半径 12 的圆,
+        // 中间三层未标注 (其中第 2 层有一些应被覆盖的杂质).
+        let mut data = Array3::<u8>::zeros((33, 33, 5));
+        for ((w, h, z), p) in data.indexed_iter_mut() {
+            let inside = match z {
+                0 => disk(4.0)(h, w),
+                4 => disk(12.0)(h, w),
+                2 => w == 0,
+                _ => false,
+            };
+            if inside {
+                *p = LITS_LIVER;
+            }
+        }
+        let mut label = CtLabel::fake(data, [1.0; 3], 0, [0.0; 3]);
+        let orig = label.clone();
+        label.interpolate_missing_slices(|z| z == 0 || z == 4);
+
+        // 已标注切片保持不变.
+        for z in [0, 4] {
+            assert_eq!(
+                label.data.index_axis(Axis(0), z),
+                orig.data.index_axis(Axis(0), z)
+            );
+        }
+        // 插值结果是半径依次增大的中间圆: 中间层近似为半径 8 的圆.
+        let area = |z: usize| {
+            label
+                .data
+                .index_axis(Axis(0), z)
+                .iter()
+                .filter(|&&p| p == LITS_LIVER)
+                .count()
+        };
+        assert!(area(0) < area(1) && area(1) < area(2) && area(2) < area(3) && area(3) < area(4));
+        for ((h, w), &p) in label.data.index_axis(Axis(0), 2).indexed_iter() {
+            if disk(7.0)(h, w) {
+                assert_eq!(p, LITS_LIVER, "{h} {w}");
+            } else if !disk(9.0)(h, w) {
+                assert_eq!(p, LITS_BACKGROUND, "{h} {w}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolate_missing_slices_tumor() {
+        // 上下两层都是含肿瘤的肝脏, 插值层也应当含有肿瘤.
+        let mut data = Array3::<u8>::zeros((33, 33, 3));
+        for ((w, h, z), p) in data.indexed_iter_mut() {
+            if z != 1 && disk(3.0)(h, w) {
+                *p = LITS_TUMOR;
+            } else if z != 1 && disk(10.0)(h, w) {
+                *p = LITS_LIVER;
+            }
+        }
+        let mut label = CtLabel::fake(data, [1.0; 3], 0, [0.0; 3]);
+        label.interpolate_missing_slices(|z| z != 1);
+        assert_eq!(
+            label.data.index_axis(Axis(0), 1),
+            label.data.index_axis(Axis(0), 0)
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! 肝脏表面的三角网格提取与导出.
+
+use super::{CtLabel, NiftiHeaderAttr};
+use crate::consts::Foreground;
+use ndarray::{s, Array3};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// 三角网格, 可以用 [`Mesh::save_obj`] 或 [`Mesh::save_stl`] 导出后在三维软件中查看.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    /// 顶点坐标 `[x, y, z]` (单位: 毫米), 依次对应宽, 高和 z 方向.
+    pub vertices: Vec<[f32; 3]>,
+
+    /// 三角面片, 每个元素为三个顶点在 `vertices` 中的下标.
+    /// 从外部看, 顶点按逆时针排列 (即法向量指向前景外部).
+    pub faces: Vec<[u32; 3]>,
+}
+
+/// 立方体的 8 个角点相对于左下角的偏移 `(dz, dh, dw)`, 第 `i` 个角点的偏移为
+/// `i` 的二进制位 `(bit2, bit1, bit0)`.
+const CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [0, 0, 1],
+    [0, 1, 0],
+    [0, 1, 1],
+    [1, 0, 0],
+    [1, 0, 1],
+    [1, 1, 0],
+    [1, 1, 1],
+];
+
+/// 沿主对角线 `0 -> 7` 把立方体分割为 6 个四面体 (Freudenthal 分割).
+/// 所有立方体的分割方式相同, 相邻立方体在公共面上的对角线一致, 因此网格没有裂缝.
+const TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 1, 3, 7],
+    [0, 1, 5, 7],
+    [0, 2, 3, 7],
+    [0, 2, 6, 7],
+    [0, 4, 5, 7],
+    [0, 4, 6, 7],
+];
+
+impl CtLabel {
+    /// 以 marching cubes 提取 `fg` 前景的表面三角网格, 是二维轮廓追踪在三维中的对应.
+    ///
+    /// 为了避免经典 marching cubes 查找表在歧义情形下产生的孔洞, 每个立方体都沿主对角线
+    /// 被分割为 6 个四面体分别处理 (即 marching tetrahedra). 前景指示函数的等值面取 0.5,
+    /// 因此所有顶点都位于相邻的前景与背景体素中心连线的中点. 体积以外视为背景,
+    /// 因此结果总是封闭 (watertight) 的.
+    ///
+    /// 顶点按 `pixdim` 换算为物理坐标 (单位: 毫米), 原点为第一个体素的中心,
+    /// 见 [`Mesh::vertices`]. 若不存在前景, 则返回空网格.
+    pub fn extract_surface_mesh(&self, fg: Foreground) -> Mesh {
+        let (z, h, w) = self.data.dim();
+        let [sz, sh, sw] = self.pix_dim();
+        // 四周补一层背景, 使触及体积边缘的前景也能得到封闭的表面.
+        let mut inside = Array3::from_elem((z + 2, h + 2, w + 2), false);
+        inside
+            .slice_mut(s![1..=z, 1..=h, 1..=w])
+            .zip_mut_with(&self.data, |v, &p| *v = fg.contains(p));
+
+        let mut mesh = Mesh::default();
+        // 以两个端点 (补边后的坐标) 为键, 共享同一条边上的顶点.
+        let mut edge_vertex: HashMap<([usize; 3], [usize; 3]), u32> = HashMap::new();
+        let mut vertex = |a: [usize; 3], b: [usize; 3], mesh: &mut Mesh| {
+            let key = if a < b { (a, b) } else { (b, a) };
+            *edge_vertex.entry(key).or_insert_with(|| {
+                let mid = |k: usize| (a[k] + b[k]) as f64 / 2.0 - 1.0;
+                mesh.vertices.push([
+                    (mid(2) * sw) as f32,
+                    (mid(1) * sh) as f32,
+                    (mid(0) * sz) as f32,
+                ]);
+                (mesh.vertices.len() - 1) as u32
+            })
+        };
+
+        for i in 0..=z {
+            for j in 0..=h {
+                for k in 0..=w {
+                    let corner = |c: usize| {
+                        let [di, dj, dk] = CORNERS[c];
+                        [i + di, j + dj, k + dk]
+                    };
+                    let flags: [bool; 8] = std::array::from_fn(|c| inside[corner(c)]);
+                    if flags.iter().all(|&f| f) || flags.iter().all(|&f| !f) {
+                        continue;
+                    }
+                    for tet in TETRAHEDRA {
+                        let (ins, outs): (Vec<usize>, Vec<usize>) =
+                            tet.iter().partition(|&&c| flags[c]);
+                        let (ins, outs) = (
+                            ins.iter().map(|&c| corner(c)).collect::<Vec<_>>(),
+                            outs.iter().map(|&c| corner(c)).collect::<Vec<_>>(),
+                        );
+                        let polygon = match (ins.len(), outs.len()) {
+                            (1, 3) => outs.iter().map(|&o| (ins[0], o)).collect(),
+                            (3, 1) => ins.iter().map(|&p| (p, outs[0])).collect(),
+                            (2, 2) => vec![
+                                (ins[0], outs[0]),
+                                (ins[0], outs[1]),
+                                (ins[1], outs[1]),
+                                (ins[1], outs[0]),
+                            ],
+                            _ => vec![],
+                        };
+                        let ids: Vec<u32> = polygon
+                            .iter()
+                            .map(|&(a, b)| vertex(a, b, &mut mesh))
+                            .collect();
+                        let outward = centroid(&outs, &ins);
+                        for t in 1..ids.len().saturating_sub(1) {
+                            mesh.push_face([ids[0], ids[t], ids[t + 1]], outward);
+                        }
+                    }
+                }
+            }
+        }
+        mesh
+    }
+}
+
+/// 从 `ins` 的重心指向 `outs` 的重心的向量 `[dz, dh, dw]` (补边后的网格坐标).
+fn centroid(outs: &[[usize; 3]], ins: &[[usize; 3]]) -> [f64; 3] {
+    let mean = |pts: &[[usize; 3]], k: usize| {
+        pts.iter().map(|p| p[k] as f64).sum::<f64>() / pts.len() as f64
+    };
+    std::array::from_fn(|k| mean(outs, k) - mean(ins, k))
+}
+
+impl Mesh {
+    /// 加入一个三角面片, 必要时翻转顶点顺序, 使其法向量与 `outward` 同向.
+    /// `outward` 以 `[dz, dh, dw]` 的网格坐标给出.
+    fn push_face(&mut self, mut face: [u32; 3], outward: [f64; 3]) {
+        let p = face.map(|i| self.vertices[i as usize].map(|v| v as f64));
+        let (u, v) = (sub(p[1], p[0]), sub(p[2], p[0]));
+        let normal = cross(u, v);
+        // 顶点坐标为 [x, y, z] = [w, h, z], 与网格坐标的分量顺序相反.
+        let dot = normal[0] * outward[2] + normal[1] * outward[1] + normal[2] * outward[0];
+        if dot < 0.0 {
+            face.swap(1, 2);
+        }
+        self.faces.push(face);
+    }
+
+    /// 以 Wavefront OBJ 文本格式保存网格.
+    pub fn save_obj<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_obj(&mut out)?;
+        out.flush()
+    }
+
+    /// 以二进制 STL 格式保存网格. 每个面片的法向量由顶点坐标计算.
+    pub fn save_stl<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_stl(&mut out)?;
+        out.flush()
+    }
+
+    fn write_obj(&self, out: &mut impl Write) -> std::io::Result<()> {
+        for [x, y, z] in self.vertices.iter() {
+            writeln!(out, "v {x} {y} {z}")?;
+        }
+        // OBJ 的顶点下标从 1 开始.
+        for [a, b, c] in self.faces.iter() {
+            writeln!(out, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_stl(&self, out: &mut impl Write) -> std::io::Result<()> {
+        out.write_all(&[0u8; 80])?;
+        out.write_all(&(self.faces.len() as u32).to_le_bytes())?;
+        for face in self.faces.iter() {
+            let p = face.map(|i| self.vertices[i as usize].map(|v| v as f64));
+            let n = cross(sub(p[1], p[0]), sub(p[2], p[0]));
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            let n = if len > 0.0 { n.map(|v| v / len) } else { n };
+            for v in std::iter::once(n).chain(p) {
+                for c in v {
+                    out.write_all(&(c as f32).to_le_bytes())?;
+                }
+            }
+            out.write_all(&[0u8; 2])?;
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::{LITS_BACKGROUND, LITS_LIVER, LITS_TUMOR};
+
+    #[test]
+    fn test_extract_surface_mesh() {
+        // [w, h, z] = [10, 8, 6], 体素间距 [0.8, 0.8, 2.5]. 长方体占据
+        // w: 2..=6, h: 0..=4 (触及体积边缘), z: 1..=4, 其中心有一个肿瘤体素.
+        let data = Array3::from_shape_fn((10, 8, 6), |(w, h, z)| {
+            match ((2..=6).contains(&w), h <= 4, (1..=4).contains(&z)) {
+                (true, true, true) if (w, h, z) == (4, 2, 2) => LITS_TUMOR,
+                (true, true, true) => LITS_LIVER,
+                _ => LITS_BACKGROUND,
+            }
+        });
+        let label = CtLabel::fake(data, [0.8, 0.8, 2.5], 1, [0.0, 0.0, 1.0]);
+
+        let mesh = label.extract_surface_mesh(Foreground::LiverAndTumor);
+        assert!(!mesh.faces.is_empty());
+
+        // 包围盒: 前景体素中心向外延伸半个体素.
+        let bound = |k: usize| {
+            let vs = mesh.vertices.iter().map(|v| v[k] as f64);
+            let min = vs.clone().fold(f64::INFINITY, f64::min);
+            let max = vs.fold(f64::NEG_INFINITY, f64::max);
+            (min, max)
+        };
+        let close =
+            |(a, b): (f64, f64), (c, d): (f64, f64)| (a - c).abs() < 1e-5 && (b - d).abs() < 1e-5;
+        assert!(close(bound(0), (1.5 * 0.8, 6.5 * 0.8)));
+        assert!(close(bound(1), (-0.5 * 0.8, 4.5 * 0.8)));
+        assert!(close(bound(2), (0.5 * 2.5, 4.5 * 2.5)));
+
+        // 封闭性: 每条有向边恰好出现一次, 且其反向边也恰好出现一次.
+        let mut edges = HashMap::new();
+        for f in mesh.faces.iter() {
+            for t in 0..3 {
+                *edges.entry((f[t], f[(t + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &n) in edges.iter() {
+            assert_eq!(n, 1);
+            assert_eq!(edges.get(&(b, a)), Some(&1));
+        }
+
+        // 法向量朝外: 由散度定理, 有向面片围成的体积等于长方体体积.
+        let volume: f64 = mesh
+            .faces
+            .iter()
+            .map(|f| {
+                let p = f.map(|i| mesh.vertices[i as usize].map(|v| v as f64));
+                let c = cross(p[1], p[2]);
+                (p[0][0] * c[0] + p[0][1] * c[1] + p[0][2] * c[2]) / 6.0
+            })
+            .sum();
+        let expected = (5.0 * 0.8) * (5.0 * 0.8) * (4.0 * 2.5);
+        assert!((volume - expected).abs() < 0.2 * expected, "{volume}");
+
+        // 只取肝脏时, 肿瘤体素形成内部空腔, 表面更多.
+        let hollow = label.extract_surface_mesh(Foreground::LiverOnly);
+        assert!(hollow.faces.len() > mesh.faces.len());
+
+        let mut obj = vec![];
+        mesh.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(
+            obj.lines().filter(|l| l.starts_with("v ")).count(),
+            mesh.vertices.len()
+        );
+        assert_eq!(
+            obj.lines().filter(|l| l.starts_with("f ")).count(),
+            mesh.faces.len()
+        );
+        let mut stl = vec![];
+        mesh.write_stl(&mut stl).unwrap();
+        assert_eq!(stl.len(), 84 + 50 * mesh.faces.len());
+
+        let empty = CtLabel::fake(Array3::zeros((4, 4, 4)), [1.0; 3], 1, [0.0, 0.0, 1.0]);
+        assert_eq!(
+            empty.extract_surface_mesh(Foreground::LiverOnly),
+            Mesh::default()
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! 以内存映射方式打开 nii 文件.
+//!
+//! 对于全身 CT 这样的大体积数据, [`CtScan::open`] 一次性把全部体素读入内存的开销很大.
+//! 内存映射只在访问时才由操作系统按页载入数据, 适合随机读取少数几张切片的场景.
+//!
+//! # 限制
+//!
+//! - 仅支持未压缩的单文件 nii 格式 (magic 为 `n+1`). gzip 压缩的 `.nii.gz`
+//!   文件无法映射, 你应先将其解压;
+//! - 文件的字节序必须与本机一致;
+//! - 映射期间文件不应被其他进程修改或截断, 否则读到的数据是未定义的.
+//!
+//! 所有视图的生命周期都与映射对象绑定. 映射对象被 drop 后, 视图随之失效,
+//! 这一点由借用检查器保证.
+
+use std::fs::File;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
+
+use memmap2::Mmap;
+use ndarray::{Array2, ArrayView3, Axis};
+use nifti::{Endianness, NiftiError, NiftiHeader, NiftiType};
+
+use super::{get_shape_from_header, BoxedHeader, CtLabel, CtScan, LabelSlice, NiftiHeaderAttr};
+
+/// gzip 文件的前两个字节.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 可以直接从映射内存中按原生字节序解释的体素类型.
+///
+/// 该 trait 是封闭的, 仅为 nifti 所支持的基本数值类型实现.
+pub trait MmapElement: Copy + sealed::Sealed {
+    /// 该类型在 nifti header 中对应的数据类型.
+    const DATA_TYPE: NiftiType;
+
+    /// 转换为 `f32`.
+    fn to_f32(self) -> f32;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_mmap_element {
+    ($($t: ty => $nt: ident),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $t {}
+
+            impl MmapElement for $t {
+                const DATA_TYPE: NiftiType = NiftiType::$nt;
+
+                #[inline]
+                fn to_f32(self) -> f32 {
+                    self as f32
+                }
+            }
+        )*
+    };
+}
+
+impl_mmap_element!(
+    u8 => Uint8,
+    i8 => Int8,
+    u16 => Uint16,
+    i16 => Int16,
+    u32 => Uint32,
+    i32 => Int32,
+    f32 => Float32,
+    f64 => Float64,
+);
+
+#[inline]
+fn invalid_input(msg: &str) -> NiftiError {
+    IoError::new(ErrorKind::InvalidInput, msg).into()
+}
+
+/// 映射文件并校验 header 及数据区长度, 返回 header 和映射对象.
+fn map_nii(path: &Path) -> nifti::Result<(BoxedHeader, Mmap)> {
+    let file = File::open(path)?;
+    // SAFETY: 映射是只读的. 文件在映射期间被外部修改的情形已在模块文档中说明.
+    let mmap = unsafe { Mmap::map(&file)? };
+    if mmap.starts_with(&GZIP_MAGIC) {
+        return Err(invalid_input("内存映射仅支持未压缩的 nii 文件"));
+    }
+    let header = Box::new(NiftiHeader::from_reader(&mmap[..])?);
+    if &header.magic != b"n+1\0" {
+        return Err(NiftiError::NoVolumeData);
+    }
+    if header.endianness != Endianness::native() {
+        return Err(invalid_input("内存映射要求文件字节序与本机一致"));
+    }
+
+    let elem = header.data_type()?.size_of();
+    let (z, h, w) = get_shape_from_header(&header);
+    let expected = z * h * w;
+    let offset = header.vox_offset as usize;
+    let got = mmap.len().saturating_sub(offset) / elem;
+    if got < expected {
+        return Err(NiftiError::IncompatibleLength(got, expected));
+    }
+    Ok((header, mmap))
+}
+
+/// 以内存映射方式打开的 3D CT 扫描, 由 [`CtScan::open_mmap`] 创建.
+///
+/// 与 [`CtScan`] 相同, 数据按照 (z, H, W) 的顺序访问.
+pub struct MmapCtScan {
+    header: BoxedHeader,
+    mmap: Mmap,
+}
+
+impl NiftiHeaderAttr for MmapCtScan {
+    #[inline]
+    fn header(&self) -> &NiftiHeader {
+        &self.header
+    }
+}
+
+impl MmapCtScan {
+    /// 数据区的原始字节.
+    #[inline]
+    fn raw_bytes(&self) -> &[u8] {
+        let offset = self.header.vox_offset as usize;
+        let len = self.size() * self.data_type().size_of();
+        &self.mmap[offset..offset + len]
+    }
+
+    /// 文件中体素的存储类型.
+    #[inline]
+    pub fn data_type(&self) -> NiftiType {
+        // 打开时已校验.
+        self.header.data_type().unwrap()
+    }
+
+    /// 获取未经 `scl_slope` / `scl_inter` 缩放的原始体素视图.
+    ///
+    /// 若 `T` 与文件存储类型不一致, 或数据区在内存中未按 `T` 对齐, 则返回 `None`.
+    pub fn raw_view<T: MmapElement>(&self) -> Option<ArrayView3<'_, T>> {
+        if self.data_type() != T::DATA_TYPE {
+            return None;
+        }
+        // SAFETY: `T` 是基本数值类型, 任意位模式均合法; 字节序已在打开时校验.
+        let (head, data, _) = unsafe { self.raw_bytes().align_to::<T>() };
+        if !head.is_empty() {
+            return None;
+        }
+        ArrayView3::from_shape(self.shape(), &data[..self.size()]).ok()
+    }
+
+    /// 读取第 `z` 张水平切片, 并按照 header 中的 `scl_slope` / `scl_inter`
+    /// 换算为 HU 值 (与 [`CtScan::open`] 的结果一致). 只有该切片所在的页会被载入.
+    ///
+    /// 若 `z` 越界, 或文件的存储类型不受支持, 则返回 `None`.
+    pub fn slice(&self, z: usize) -> Option<Array2<f32>> {
+        if z >= self.len_z() {
+            return None;
+        }
+        macro_rules! dispatch {
+            ($($t: ty),*) => {
+                match self.data_type() {
+                    $(<$t>::DATA_TYPE => self.slice_as::<$t>(z),)*
+                    _ => None,
+                }
+            };
+        }
+        dispatch!(u8, i8, u16, i16, u32, i32, f32, f64)
+    }
+
+    fn slice_as<T: MmapElement>(&self, z: usize) -> Option<Array2<f32>> {
+        let (slope, inter) = (self.header.scl_slope, self.header.scl_inter);
+        let view = self.raw_view::<T>()?;
+        let sli = view.index_axis(Axis(0), z);
+        Some(if slope == 0.0 {
+            sli.mapv(T::to_f32)
+        } else {
+            sli.mapv(|v| v.to_f32() * slope + inter)
+        })
+    }
+}
+
+/// 以内存映射方式打开的 3D CT 标注, 由 [`CtLabel::open_mmap`] 创建.
+///
+/// 与 [`CtLabel`] 相同, 数据按照 (z, H, W) 的顺序访问.
+pub struct MmapCtLabel {
+    header: BoxedHeader,
+    mmap: Mmap,
+}
+
+impl NiftiHeaderAttr for MmapCtLabel {
+    #[inline]
+    fn header(&self) -> &NiftiHeader {
+        &self.header
+    }
+}
+
+impl MmapCtLabel {
+    /// 获取全部体素的只读视图.
+    pub fn view(&self) -> ArrayView3<'_, u8> {
+        let offset = self.header.vox_offset as usize;
+        let data = &self.mmap[offset..offset + self.size()];
+        // 打开时已校验长度.
+        ArrayView3::from_shape(self.shape(), data).unwrap()
+    }
+
+    /// 获取第 `z` 张水平切片. 若 `z` 越界, 则返回 `None`.
+    pub fn slice_at(&self, z: usize) -> Option<LabelSlice<'_>> {
+        (z < self.len_z()).then(|| LabelSlice::new(self.view().index_axis_move(Axis(0), z)))
+    }
+}
+
+impl CtScan {
+    /// 以内存映射方式打开 nii 文件格式的 3D CT 扫描, 不会一次性读入全部数据.
+    /// 适用范围及限制见 [`crate::mmap`] 模块文档.
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> nifti::Result<MmapCtScan> {
+        let (header, mmap) = map_nii(path.as_ref())?;
+        Ok(MmapCtScan { header, mmap })
+    }
+}
+
+impl CtLabel {
+    /// 以内存映射方式打开 nii 文件格式的 3D CT 标注, 不会一次性读入全部数据.
+    /// 适用范围及限制见 [`crate::mmap`] 模块文档.
+    ///
+    /// 标注必须以 `u8` 类型存储, 否则返回 [`NiftiError::UnsupportedDataType`].
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> nifti::Result<MmapCtLabel> {
+        let (header, mmap) = map_nii(path.as_ref())?;
+        match header.data_type()? {
+            NiftiType::Uint8 => Ok(MmapCtLabel { header, mmap }),
+            t => Err(NiftiError::UnsupportedDataType(t)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::LITS_LIVER;
+    use ndarray::Array3;
+    use nifti::writer::WriterOptions;
+
+    #[test]
+    fn test_mmap_matches_open() {
+        let dir = std::env::temp_dir().join(format!("ct-berry-mmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // [w, h, z] = [7, 5, 4].
+        let raw = Array3::from_shape_fn((7, 5, 4), |(w, h, z)| {
+            (w as i16 - 3) * 100 + h as i16 * 10 + z as i16
+        });
+        let label = CtLabel::fake(raw.mapv(|v| (v > 0) as u8), [0.8, 0.8, 2.0], 1, [0.0; 3]);
+
+        let scan_path = dir.join("scan.nii");
+        let mut header = (*label.header).clone();
+        header.scl_slope = 0.5;
+        header.scl_inter = -10.0;
+        WriterOptions::new(&scan_path)
+            .reference_header(&header)
+            .write_nifti(&raw)
+            .unwrap();
+        let label_path = dir.join("label.nii");
+        WriterOptions::new(&label_path)
+            .reference_header(&label.header)
+            .write_nifti(&raw.mapv(|v| (v > 0) as u8 * LITS_LIVER))
+            .unwrap();
+
+        let scan = CtScan::open(&scan_path).unwrap();
+        let mapped = CtScan::open_mmap(&scan_path).unwrap();
+        assert_eq!(mapped.shape(), scan.shape());
+        assert_eq!(mapped.data_type(), NiftiType::Int16);
+        assert!(mapped.raw_view::<f32>().is_none());
+        for z in (0..scan.len_z()).rev() {
+            assert_eq!(mapped.slice(z).unwrap(), scan.data().index_axis(Axis(0), z));
+        }
+        assert!(mapped.slice(scan.len_z()).is_none());
+        // 数据区偏移 352 字节, 映射起点按页对齐, 因此 i16 视图一定可用.
+        let view = mapped.raw_view::<i16>().unwrap();
+        assert_eq!(view[(3, 4, 6)], raw[(6, 4, 3)]);
+
+        let opened = CtLabel::open(&label_path).unwrap();
+        let mapped = CtLabel::open_mmap(&label_path).unwrap();
+        assert_eq!(mapped.view(), opened.data());
+        assert_eq!(
+            mapped.slice_at(2).unwrap().array_view(),
+            opened.data().index_axis(Axis(0), 2)
+        );
+        assert!(mapped.slice_at(4).is_none());
+
+        // 非 u8 标注和压缩文件均被拒绝.
+        assert!(matches!(
+            CtLabel::open_mmap(&scan_path),
+            Err(NiftiError::UnsupportedDataType(NiftiType::Int16))
+        ));
+        let gz_path = dir.join("label.nii.gz");
+        WriterOptions::new(&gz_path)
+            .reference_header(&label.header)
+            .write_nifti(&raw.mapv(|v| (v > 0) as u8))
+            .unwrap();
+        assert!(CtLabel::open(&gz_path).is_ok());
+        assert!(matches!(
+            CtLabel::open_mmap(&gz_path),
+            Err(NiftiError::Io(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
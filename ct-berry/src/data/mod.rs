@@ -1,28 +1,45 @@
 use std::ops::{Index, IndexMut};
 use std::path::Path;
 
-use ndarray::{Array3, ArrayView, ArrayViewMut, Axis, Ix3};
-use nifti::{IntoNdArray, NiftiHeader, NiftiObject, ReaderOptions};
+use ndarray::{s, Array2, Array3, ArrayView, ArrayViewMut, Axis, Ix3};
+use nifti::{InMemNiftiVolume, IntoNdArray, NiftiHeader, NiftiObject, ReaderOptions};
 
 use crate::consts::gray::*;
+use crate::consts::Connectivity;
 use crate::{Idx2d, Idx3d, Predicate};
 
+mod blob;
+mod blur;
+mod component;
+mod hu_stats;
+mod interpolate;
+mod mesh;
+pub mod mmap;
 pub mod morph_3d;
+mod profile;
+mod projection;
+mod region_grow;
 pub mod sector;
+mod set_ops;
 pub mod slice;
 pub mod window;
 
 use sector::{InitLlsPatternError, LlsSectorPattern};
 
+pub use component::{ComponentStat, TumorBurden};
+pub use hu_stats::{HuStats, LiverAttenuation};
+pub use mesh::Mesh;
+pub use morph_3d::CenterTiebreak;
+
 pub use slice::{
-    CompactLabelSlice, ImgWriteRaw, ImgWriteVis, LabelSlice, LabelSliceMut, OwnedLabelSlice,
-    OwnedScanSlice, ScanSlice, ScanSliceMut,
+    CompactLabelSlice, CompressionBackend, ImgWriteRaw, ImgWriteVis, LabelSlice, LabelSliceMut,
+    OwnedLabelSlice, OwnedScanSlice, ScanSlice, ScanSliceMut,
 };
 
 #[cfg(feature = "plot")]
 pub use slice::ImgDisplay;
 
-pub use window::CtWindow;
+pub use window::{blend_windows, CtWindow};
 
 /// `NiftiHeader` 是栈上大对象, 移动该对象的开销很可观.
 /// 因此我们将其分配到堆上.
@@ -43,6 +60,116 @@ fn get_shape_from_header(h: &NiftiHeader) -> Idx3d {
     (z as usize, h as usize, w as usize)
 }
 
+/// `get_shape_from_header` 的逆操作. 将 (z, H, W) 写回 header 的 (W, H, z) 体素个数数组.
+#[inline]
+fn set_shape_to_header(header: &mut NiftiHeader, (z, h, w): Idx3d) {
+    header.dim = [3, w as u16, h as u16, z as u16, 1, 1, 1, 1];
+}
+
+/// 截取 `data` 在 `[min, max)` 范围内的子块, 并同步修改 `header` 中的形状信息.
+///
+/// 若范围越界或 `min` 在任一维度上大于 `max`, 则程序 panic.
+fn crop_with_header<T: Clone>(
+    header: &NiftiHeader,
+    data: &Array3<T>,
+    (z0, h0, w0): Idx3d,
+    (z1, h1, w1): Idx3d,
+) -> (BoxedHeader, Array3<T>) {
+    let (z, h, w) = data.dim();
+    assert!(z0 <= z1 && h0 <= h1 && w0 <= w1, "裁剪范围的下界大于上界");
+    assert!(z1 <= z && h1 <= h && w1 <= w, "裁剪范围越界");
+
+    let data = data.slice(s![z0..z1, h0..h1, w0..w1]).to_owned();
+    let mut header = Box::new(header.clone());
+    set_shape_to_header(&mut header, data.dim());
+    (header, data)
+}
+
+/// 在 `data` 每个维度的两侧各填充 `margin` (以 \[z, H, W\] 格式给出) 个值为 `value`
+/// 的体素, 并同步修改 `header` 中的形状信息.
+fn pad_with_header<T: Clone>(
+    header: &NiftiHeader,
+    data: &Array3<T>,
+    [mz, mh, mw]: [usize; 3],
+    value: T,
+) -> (BoxedHeader, Array3<T>) {
+    let (z, h, w) = data.dim();
+    let mut padded = Array3::from_elem((z + 2 * mz, h + 2 * mh, w + 2 * mw), value);
+    padded
+        .slice_mut(s![mz..mz + z, mh..mh + h, mw..mw + w])
+        .assign(data);
+    let mut header = Box::new(header.clone());
+    set_shape_to_header(&mut header, padded.dim());
+    (header, padded)
+}
+
+/// 翻转 `header` 中 w 方向 (NIfTI 的第一个体素轴) 的空间朝向, 使翻转后索引为 `w`
+/// 的体素与翻转前索引为 `2 * axis_w - w` 的体素位于同一物理位置.
+///
+/// sform 直接对第一列取反并平移. qform 的旋转矩阵只能是正常旋转, 因此把
+/// `R * diag(-1, 1, 1)` 写成 `(R * diag(-1, 1, -1)) * diag(1, 1, -1)`:
+/// 四元数右乘绕 y 轴旋转 180° 的四元数, 同时对 `qfac` 取反.
+fn flip_w_in_header(header: &mut NiftiHeader, axis_w: usize) {
+    let shift = 2.0 * axis_w as f32;
+
+    for row in [&mut header.srow_x, &mut header.srow_y, &mut header.srow_z] {
+        row[3] += shift * row[0];
+        row[0] = -row[0];
+    }
+
+    let (b, c, d) = (header.quatern_b, header.quatern_c, header.quatern_d);
+    let a = (1.0 - b * b - c * c - d * d).max(0.0).sqrt();
+    // 旋转矩阵的第一列, 乘以 w 方向的体素间距.
+    let pw = header.pixdim[1];
+    let col = [
+        (a * a + b * b - c * c - d * d) * pw,
+        2.0 * (b * c + a * d) * pw,
+        2.0 * (b * d - a * c) * pw,
+    ];
+    header.quatern_x += shift * col[0];
+    header.quatern_y += shift * col[1];
+    header.quatern_z += shift * col[2];
+    // (a, b, c, d) * (0, 0, 1, 0) = (-c, -d, a, b). NIfTI 要求 a >= 0.
+    let sign = if c > 0.0 { -1.0 } else { 1.0 };
+    (header.quatern_b, header.quatern_c, header.quatern_d) = (-sign * d, sign * a, sign * b);
+    let qfac = if header.pixdim[0] < 0.0 { -1.0 } else { 1.0 };
+    header.pixdim[0] = -qfac;
+}
+
+/// 将按照 \[w, h, z\] 存储的数据转换为标准内存布局的 \[z, h, w\] 格式.
+fn wh_z_to_standard_zhw<T: Clone>(data: Array3<T>) -> Array3<T> {
+    let data = data.permuted_axes([2, 1, 0]);
+    let data = if data.is_standard_layout() {
+        data
+    } else {
+        data.as_standard_layout().to_owned()
+    };
+    debug_assert!(data.is_standard_layout());
+    data
+}
+
+/// 为 `fake` 系列方法构造 header. `shape` 按 (z, H, W) 格式给出,
+/// 其余参数含义同 [`CtLabel::fake`].
+fn fake_header(
+    shape: Idx3d,
+    pix_dim: [f32; 3],
+    qform_code: i16,
+    quatern_bcd: [f32; 3],
+) -> BoxedHeader {
+    let mut header = Box::<NiftiHeader>::default();
+    set_shape_to_header(&mut header, shape);
+    let [_, pw, ph, pz, ..] = &mut header.pixdim;
+    let [w, h, z] = &pix_dim;
+    assert_eq!(w, h); // 目前仅支持水平方向各向同性的情况
+    (*pw, *ph, *pz) = (*w, *h, *z);
+    header.qform_code = qform_code;
+    let [qb, qc, qd] = &quatern_bcd;
+    (header.quatern_b, header.quatern_c, header.quatern_d) = (*qb, *qc, *qd);
+    // header.intent_name.take(4).
+    header.intent_name[..4].copy_from_slice(b"fake");
+    header
+}
+
 /// 3D CT nii 文件 header 的共用属性和部分通用操作.
 pub trait NiftiHeaderAttr {
     /// 获取 header 部分.
@@ -150,6 +277,21 @@ pub trait NiftiHeaderAttr {
     fn slice_pixel(&self) -> f64 {
         self.pix_dim().iter().skip(1).product()
     }
+
+    /// 获取整个数据在三个维度上的实际尺寸, 以毫米为单位, 按 \[z, H, W\] 格式给出.
+    #[inline]
+    fn physical_size_mm(&self) -> [f64; 3] {
+        let (z, h, w) = self.shape();
+        let [zm, hm, wm] = self.pix_dim();
+        [z as f64 * zm, h as f64 * hm, w as f64 * wm]
+    }
+
+    /// 获取数据的中心体素索引. 对于偶数长度的维度, 取靠后的那个.
+    #[inline]
+    fn center_index(&self) -> Idx3d {
+        let (z, h, w) = self.shape();
+        (z / 2, h / 2, w / 2)
+    }
 }
 
 impl NiftiHeaderAttr for CtScan {
@@ -178,16 +320,36 @@ impl IndexMut<Idx3d> for CtScan {
 impl CtScan {
     /// 打开 nii 文件格式的 3D CT 扫描. `path` 为 nii 文件的本地路径.
     /// 如果打开成功, 则返回 `Ok(Self)`, 否则返回 `Err`.
+    ///
+    /// 当 header 中的 `scl_slope` 不为 0 时, 读入的存储值 `raw` 会被换算为
+    /// `scl_slope * raw + scl_inter`, 即得到 HU 值; `scl_slope` 为 0 表示不缩放,
+    /// 此时保留存储值. 若需要未经换算的存储值, 请使用 [`CtScan::open_raw`].
     pub fn open<P: AsRef<Path>>(path: P) -> nifti::Result<Self> {
-        let obj = ReaderOptions::new().read_file(path.as_ref())?;
+        Self::open_with(path.as_ref(), true)
+    }
+
+    /// 同 [`CtScan::open`], 但总是保留文件中的存储值, 忽略 `scl_slope` 和 `scl_inter`.
+    /// 返回的 header 与文件一致 (仍然包含缩放参数).
+    pub fn open_raw<P: AsRef<Path>>(path: P) -> nifti::Result<Self> {
+        Self::open_with(path.as_ref(), false)
+    }
+
+    fn open_with(path: &Path, rescale: bool) -> nifti::Result<Self> {
+        let obj = ReaderOptions::new().read_file(path)?;
         let header = Box::new(obj.header().clone());
+        let mut volume = obj.into_volume();
+        if !rescale {
+            // `scl_slope` 为 0 时 nifti 不做缩放.
+            let unscaled = NiftiHeader {
+                scl_slope: 0.0,
+                ..(*header).clone()
+            };
+            volume = InMemNiftiVolume::from_raw_data(&unscaled, volume.into_raw_data())?;
+        }
 
         // [W, H, z] -> [z, H, W].
         // hint: 原第一维向下增长, 原第二维向右增长.
-        let data = obj
-            .into_volume()
-            .into_ndarray()?
-            .permuted_axes([2, 1, 0].as_slice());
+        let data = volume.into_ndarray()?.permuted_axes([2, 1, 0].as_slice());
 
         // The nature of nifti data field layout.
         debug_assert!(data.is_standard_layout());
@@ -200,8 +362,29 @@ impl CtScan {
         Ok(Self { header, data })
     }
 
+    /// 根据裸扫描数据和部分元信息直接创建 `CtScan` 实体. 参数含义同 [`CtLabel::fake`],
+    /// 其中 `data` 同样按照 \[w, h, z\] 格式存储.
+    ///
+    /// # 注意
+    ///
+    /// 该方法可能会创建不一致的实体, 因此你应仅将其用于实验目的.
+    pub fn fake(
+        data: Array3<f32>,
+        pix_dim: [f32; 3],
+        qform_code: i16,
+        quatern_bcd: [f32; 3],
+    ) -> Self {
+        let data = wh_z_to_standard_zhw(data);
+        let header = fake_header(data.dim(), pix_dim, qform_code, quatern_bcd);
+        Self { header, data }
+    }
+
     /// 计算由 `it` 给出的所有索引对应的 CT HU 值的平均值.
     ///
+    /// 该函数不过滤非有限值: 只要任一体素为 NaN (或同时含有 +inf 和 -inf),
+    /// 结果即为 NaN; 含有单一符号的 inf 时结果为该 inf. `it` 为空时结果同样为 NaN.
+    /// 可先调用 [`CtScan::replace_nonfinite`] 清洗数据, 或改用 [`CtScan::hu_stats`].
+    ///
     /// 如果存在越界索引, 则程序 panic.
     pub fn mean_hu<I: IntoIterator<Item = Idx3d>>(&self, it: I) -> f64 {
         let mut count = 0u64;
@@ -214,6 +397,7 @@ impl CtScan {
     }
 
     /// 计算第 `z_index` 个水平切片上的、由 `it` 给出的所有索引对应的 CT HU 值的平均值.
+    /// 对非有限值的处理同 [`CtScan::mean_hu`].
     ///
     /// 如果 `z_index` 或 `it` 中的二维索引越界, 则程序 panic.
     pub fn mean_hu_2d<I: IntoIterator<Item = Idx2d>>(&self, it: I, z_index: usize) -> f64 {
@@ -243,6 +427,15 @@ impl CtScan {
         ScanSliceMut::new(self.data.index_axis_mut(Axis(0), z_index))
     }
 
+    /// 为由本扫描派生的标注 (如阈值分割结果) 构建 header:
+    /// 复制扫描的 header, 仅把数据类型改为 `u8`.
+    fn derived_label_header(&self) -> BoxedHeader {
+        let mut header = self.header.clone();
+        header.datatype = nifti::NiftiType::Uint8 as i16;
+        header.bitpix = 8;
+        header
+    }
+
     /// 获取能按升序迭代 3D 扫描水平不可变切片的迭代器.
     #[inline]
     pub fn slice_iter(&self) -> impl ExactSizeIterator<Item = ScanSlice> {
@@ -255,6 +448,19 @@ impl CtScan {
         self.data.axis_iter_mut(Axis(0)).map(ScanSliceMut::new)
     }
 
+    /// 获取能按升序迭代 3D 扫描水平切片的迭代器, 每张切片在迭代时才按 `window`
+    /// 转换为灰度图像, 因此不需要一次性生成整个 `u8` 体数据.
+    ///
+    /// 每个像素的值为 `window.eval(hu)`; 非有限的 HU 值 (如 NaN) 被转换为 0.
+    pub fn windowed_slice_iter(
+        &self,
+        window: CtWindow,
+    ) -> impl ExactSizeIterator<Item = Array2<u8>> + '_ {
+        self.data
+            .axis_iter(Axis(0))
+            .map(move |sli| sli.mapv(|hu| window.eval(hu).unwrap_or(u8::MIN)))
+    }
+
     /// 获得数据的一份不可变 shallow copy.
     #[inline]
     pub fn data(&self) -> ArrayView<'_, f32, Ix3> {
@@ -266,6 +472,64 @@ impl CtScan {
     pub fn data_mut(&mut self) -> ArrayViewMut<'_, f32, Ix3> {
         self.data.view_mut()
     }
+
+    /// 导出为 `i16` 整数体积, 便于紧凑存储或交给只接受整数 HU 的工具.
+    ///
+    /// 返回 `(volume, scl_slope, scl_inter)`, 其中 `volume` 按 (z, H, W) 格式存储.
+    /// 原 HU 值可按 nifti 的约定 `hu = scl_slope * v + scl_inter` 重建:
+    ///
+    /// - 若所有有限值都是 `i16` 范围内的整数 (CT 扫描的常见情况), 则返回 `(1, 0)`,
+    ///   导出是无损的;
+    /// - 否则把有限值的范围 `[min, max]` 线性映射到整个 `i16` 范围,
+    ///   使量化步长 `scl_slope` 尽可能小, 重建误差不超过半个步长.
+    ///
+    /// 非有限值无法表示: `+inf` 和 `-inf` 分别被截断为 `i16` 的最大值和最小值,
+    /// NaN 的存储值为 0.
+    pub fn to_i16_volume(&self) -> (Array3<i16>, f32, f32) {
+        let (lo, hi) = self
+            .data
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+                (lo.min(v), hi.max(v))
+            });
+        let is_integral = self
+            .data
+            .iter()
+            .filter(|v| v.is_finite())
+            .all(|&v| v.fract() == 0.0 && (i16::MIN as f32..=i16::MAX as f32).contains(&v));
+
+        let (slope, inter) = if lo > hi || is_integral {
+            (1.0, 0.0)
+        } else if lo == hi {
+            (1.0, lo as f64)
+        } else {
+            let slope = (hi as f64 - lo as f64) / (i16::MAX as f64 - i16::MIN as f64);
+            (slope, lo as f64 - slope * i16::MIN as f64)
+        };
+        // `as` 在浮点数转换为整数时饱和, 且把 NaN 转换为 0.
+        let volume = self
+            .data
+            .mapv(|v| ((v as f64 - inter) / slope).round() as i16);
+        (volume, slope as f32, inter as f32)
+    }
+
+    /// 截取 `[min, max)` 范围内 (以 (z, H, W) 格式给出) 的子扫描.
+    /// header 中的形状信息会同步更新, 其余元信息 (如体素间距) 保持不变.
+    ///
+    /// 若范围越界或 `min` 在任一维度上大于 `max`, 则程序 panic.
+    pub fn crop(&self, min: Idx3d, max: Idx3d) -> CtScan {
+        let (header, data) = crop_with_header(&self.header, &self.data, min, max);
+        Self { header, data }
+    }
+
+    /// 在每个维度的两侧各填充 `margin` (以 (z, H, W) 格式给出) 层 HU 值为 `value`
+    /// 的体素, 原数据位于新扫描的 `margin` 偏移处. header 中的形状信息会同步更新,
+    /// 其余元信息 (如体素间距) 保持不变.
+    pub fn pad(&self, margin: [usize; 3], value: f32) -> CtScan {
+        let (header, data) = pad_with_header(&self.header, &self.data, margin, value);
+        Self { header, data }
+    }
 }
 
 /// nii 格式 3D CT 标注, 包括 header 和真值标签. 标签值以 `u8` 保存.
@@ -275,6 +539,23 @@ pub struct CtLabel {
     data: Array3<u8>,
 }
 
+/// [`CtLabel::try_fake`] 的参数校验错误. 维度均以 \[w, h, z\] 格式给出.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FakeError {
+    /// 某个维度的长度为 0. `(维度索引)`
+    EmptyDim(usize),
+
+    /// 某个维度的体素间距不是正的有限数. `(维度索引, 体素间距)`
+    InvalidPixDim(usize, f32),
+
+    /// 水平方向 (w, h) 的体素间距不相等. 目前仅支持水平方向各向同性的情况.
+    /// `(w 方向体素间距, h 方向体素间距)`
+    AnisotropicPixDim(f32, f32),
+
+    /// 体素值不是 0, 1 或 2. `(体素位置, 体素值)`
+    InvalidValue(Idx3d, u8),
+}
+
 impl NiftiHeaderAttr for CtLabel {
     #[inline]
     fn header(&self) -> &NiftiHeader {
@@ -324,7 +605,7 @@ impl CtLabel {
     }
 
     /// 根据裸标签数据和部分元信息直接创建 `CtLabel` 实体.
-    /// 目前, 如果参数数据不符合规定则返回 `None`.
+    /// 该方法不做任何检查; 如需校验参数, 请使用 [`CtLabel::try_fake`].
     ///
     /// # 参数
     ///
@@ -341,28 +622,45 @@ impl CtLabel {
         qform_code: i16,
         quatern_bcd: [f32; 3],
     ) -> Self {
-        let data = data.permuted_axes([2, 1, 0]);
-        let data = if data.is_standard_layout() {
-            data
-        } else {
-            data.as_standard_layout().to_owned()
-        };
-        debug_assert!(data.is_standard_layout());
-
-        let mut header = Box::<NiftiHeader>::default();
-        let [_, pw, ph, pz, ..] = &mut header.pixdim;
-        let [w, h, z] = &pix_dim;
-        assert_eq!(w, h); // 目前仅支持水平方向各向同性的情况
-        (*pw, *ph, *pz) = (*w, *h, *z);
-        header.qform_code = qform_code;
-        let [qb, qc, qd] = &quatern_bcd;
-        (header.quatern_b, header.quatern_c, header.quatern_d) = (*qb, *qc, *qd);
-        // header.intent_name.take(4).
-        header.intent_name[..4].copy_from_slice(b"fake");
-
+        let data = wh_z_to_standard_zhw(data);
+        let header = fake_header(data.dim(), pix_dim, qform_code, quatern_bcd);
         Self { header, data }
     }
 
+    /// 带参数校验的 [`CtLabel::fake`]. 按以下顺序检查, 返回遇到的第一个错误:
+    ///
+    /// 1. `data` 的每个维度长度非 0, 否则返回 [`FakeError::EmptyDim`];
+    /// 2. `pix_dim` 的每个分量都是正的有限数, 否则返回 [`FakeError::InvalidPixDim`];
+    ///    且 w 和 h 方向的分量相等, 否则返回 [`FakeError::AnisotropicPixDim`];
+    /// 3. `data` 的体素值均为 0, 1 或 2, 否则返回 [`FakeError::InvalidValue`],
+    ///    其中位置为第一个 (按 \[w, h, z\] 行优先序) 非法体素的位置.
+    ///
+    /// 参数含义同 [`CtLabel::fake`].
+    pub fn try_fake(
+        data: Array3<u8>,
+        pix_dim: [f32; 3],
+        qform_code: i16,
+        quatern_bcd: [f32; 3],
+    ) -> Result<Self, FakeError> {
+        let (w, h, z) = data.dim();
+        if let Some(axis) = [w, h, z].iter().position(|&len| len == 0) {
+            return Err(FakeError::EmptyDim(axis));
+        }
+        if let Some(axis) = pix_dim.iter().position(|&d| !(d.is_finite() && d > 0.0)) {
+            return Err(FakeError::InvalidPixDim(axis, pix_dim[axis]));
+        }
+        if pix_dim[0] != pix_dim[1] {
+            return Err(FakeError::AnisotropicPixDim(pix_dim[0], pix_dim[1]));
+        }
+        if let Some((pos, &v)) = data
+            .indexed_iter()
+            .find(|(_, &v)| !matches!(v, LITS_BACKGROUND | LITS_LIVER | LITS_TUMOR))
+        {
+            return Err(FakeError::InvalidValue(pos, v));
+        }
+        Ok(Self::fake(data, pix_dim, qform_code, quatern_bcd))
+    }
+
     /// 直接创建数据.
     ///
     /// # 注意
@@ -375,19 +673,76 @@ impl CtLabel {
     ///   训练集中已有的 label header 加载.
     #[inline]
     pub fn fake_with_header(header: &NiftiHeader, data: Array3<u8>) -> Self {
-        let data = data.permuted_axes([2, 1, 0]);
-        let data = if data.is_standard_layout() {
-            data
-        } else {
-            data.as_standard_layout().to_owned()
-        };
-        debug_assert!(data.is_standard_layout());
+        let data = wh_z_to_standard_zhw(data);
 
         let mut header = Box::new(header.clone());
         header.intent_name[..4].copy_from_slice(b"fake");
         Self { header, data }
     }
 
+    /// 截取 `[min, max)` 范围内 (以 (z, H, W) 格式给出) 的子标注.
+    /// header 中的形状信息会同步更新, 其余元信息 (如体素间距) 保持不变.
+    ///
+    /// 若范围越界或 `min` 在任一维度上大于 `max`, 则程序 panic.
+    pub fn crop(&self, min: Idx3d, max: Idx3d) -> CtLabel {
+        let (header, data) = crop_with_header(&self.header, &self.data, min, max);
+        Self { header, data }
+    }
+
+    /// 在每个维度的两侧各填充 `margin` (以 (z, H, W) 格式给出) 层值为 `value`
+    /// 的体素, 原数据位于新标注的 `margin` 偏移处. header 中的形状信息会同步更新,
+    /// 其余元信息 (如体素间距) 保持不变.
+    ///
+    /// 以 `LITS_BACKGROUND` 填充可以保证前景不接触数据边界, 从而满足
+    /// [`Self::fill_background_hollow`] 等方法对边界的要求.
+    pub fn pad(&self, margin: [usize; 3], value: u8) -> CtLabel {
+        let (header, data) = pad_with_header(&self.header, &self.data, margin, value);
+        Self { header, data }
+    }
+
+    /// 以宽度索引 `axis_w` 所在的矢状面为对称面镜像标注: 新标注在 `(z, h, w)` 处的值
+    /// 为原标注在 `(z, h, 2 * axis_w - w)` 处的值. 镜像后落在图像以外的部分被丢弃,
+    /// 没有对应来源的体素为背景. 对称面位于图像中央 (`axis_w == (W - 1) / 2`,
+    /// 宽度为奇数) 时, 镜像是可逆的.
+    ///
+    /// header 中 w 方向的空间朝向 (qform 和 sform) 同步翻转, 使每个体素的物理位置
+    /// 与其数据来源一致. 因此由 header 推导出的方向信息 (如 LLS 扇区模式)
+    /// 也随之左右相反, 与镜像后的解剖结构相符.
+    ///
+    /// 若 `axis_w` 越界, 则程序 panic.
+    pub fn mirror_about_width(&self, axis_w: usize) -> CtLabel {
+        let (_, _, w) = self.shape();
+        assert!(axis_w < w, "对称面索引 {axis_w} 越界");
+        let mut data = Array3::<u8>::zeros(self.data.raw_dim());
+        for dst_w in 0..w {
+            // 源索引 2 * axis_w - dst_w 必须位于 [0, w) 内.
+            let Some(src_w) = (2 * axis_w).checked_sub(dst_w).filter(|&x| x < w) else {
+                continue;
+            };
+            data.index_axis_mut(Axis(2), dst_w)
+                .assign(&self.data.index_axis(Axis(2), src_w));
+        }
+        let mut header = self.header.clone();
+        flip_w_in_header(&mut header, axis_w);
+        Self { header, data }
+    }
+
+    /// 计算所有满足 `pred` 的体素的包围盒 `[min, max)`, 以 (z, H, W) 格式给出.
+    /// 若不存在这样的体素, 则返回 `None`.
+    pub fn bounding_box(&self, pred: Predicate) -> Option<(Idx3d, Idx3d)> {
+        let mut it = self
+            .data
+            .indexed_iter()
+            .filter_map(|(pos, &v)| pred(v).then_some(pos));
+        let (z, h, w) = it.next()?;
+        let (mut min, mut max) = ((z, h, w), (z, h, w));
+        for (z, h, w) in it {
+            min = (min.0.min(z), min.1.min(h), min.2.min(w));
+            max = (max.0.max(z), max.1.max(h), max.2.max(w));
+        }
+        Some((min, (max.0 + 1, max.1 + 1, max.2 + 1)))
+    }
+
     /// 判断该结构是否是由 `fake_*` 方法手动拼接的.
     pub fn is_faked(&self) -> bool {
         self.header.intent_name.starts_with(b"fake")
@@ -439,6 +794,23 @@ impl CtLabel {
         self.data.iter().filter(|p| **p == label).count()
     }
 
+    /// 获取 3D 标注中满足 `pred` 的体素个数. 是 [`Self::count`] 的推广.
+    ///
+    /// 启用 `rayon` 特性时, 各水平切片并行计数后再求和; 否则串行计数.
+    pub fn count_where(&self, pred: impl Fn(u8) -> bool + Sync) -> usize {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "rayon")] {
+                self.data
+                    .axis_iter(Axis(0))
+                    .into_par_iter()
+                    .map(|sli| sli.iter().filter(|&&p| pred(p)).count())
+                    .sum()
+            } else {
+                self.data.iter().filter(|&&p| pred(p)).count()
+            }
+        }
+    }
+
     /// 获取 CT 标签的基本统计信息.
     ///
     /// 统计信息格式为: \[背景像素数, 肝脏像素数, 肿瘤像素数\].
@@ -492,6 +864,34 @@ impl CtLabel {
         self.filter_pos(|p| matches!(p, LITS_TUMOR))
     }
 
+    /// 按 z 升序统计每个水平切片中满足 `pred` 的体素个数.
+    ///
+    /// 可用于查找肝脏横截面最大的切片, 或肝脏在 z 方向的范围.
+    pub fn z_profile(&self, pred: Predicate) -> Vec<usize> {
+        self.slice_iter()
+            .map(|sli| sli.iter().filter(|&&p| pred(p)).count())
+            .collect()
+    }
+
+    /// 获取所有直接接触肝脏表面 (包膜) 的肿瘤体素坐标.
+    ///
+    /// 若肿瘤体素前后上下左右六个邻居中至少有一个是背景或越界, 则认为其接触表面.
+    pub fn tumor_surface_voxels(&self) -> Vec<Idx3d> {
+        self.tumor_pos()
+            .into_iter()
+            .filter(|&pos| {
+                let nbs = self.diamond_neighbours(pos);
+                nbs.len() < 6 || nbs.into_iter().any(|p| self[p] == LITS_BACKGROUND)
+            })
+            .collect()
+    }
+
+    /// 统计直接接触肝脏表面 (包膜) 的肿瘤体素个数. 见 [`CtLabel::tumor_surface_voxels`].
+    #[inline]
+    pub fn tumor_surface_contact(&self) -> usize {
+        self.tumor_surface_voxels().len()
+    }
+
     /// 将三维标签中的背景空洞
     /// (即钻石-连通背景区域中面积不是最大的那些)
     /// 填充为肝脏像素.
@@ -510,6 +910,40 @@ impl CtLabel {
         non_trivial
     }
 
+    /// 同 `fill_background_hollow`, 但在每个水平切片上按照 `conn` 规则划分背景连通区域.
+    /// 与 `fill_background_hollow` 相同, 算法运行前 **会强制将六个表面设置为背景**.
+    ///
+    /// 连通规则的影响见 [`LabelSliceMut::fill_holes_2d`].
+    pub fn fill_holes_2d(&mut self, conn: Connectivity) -> bool {
+        self.make_background_surface6();
+        let mut non_trivial = false;
+        self.slice_iter_mut()
+            .for_each(|mut s| non_trivial |= s.fill_holes_2d(conn));
+        non_trivial
+    }
+
+    /// 判断是否存在位于 3D 图像六个表面上的前景 (肝脏或肿瘤) 体素.
+    ///
+    /// 若返回 `true`, 说明肝脏可能被视野截断, 该数据不适合做完整的表面分析.
+    pub fn liver_touches_border(&self) -> bool {
+        self.border_foreground().next().is_some()
+    }
+
+    /// 统计位于 3D 图像六个表面上的前景 (肝脏或肿瘤) 体素个数.
+    /// 位于棱或顶点上的体素只计一次.
+    pub fn border_contact_voxels(&self) -> usize {
+        self.border_foreground().count()
+    }
+
+    /// 迭代位于六个表面上的前景体素.
+    fn border_foreground(&self) -> impl Iterator<Item = Idx3d> + '_ {
+        let (z, h, w) = self.shape();
+        self.data.indexed_iter().filter_map(move |((i, j, k), &p)| {
+            let on_border = i == 0 || j == 0 || k == 0 || i + 1 == z || j + 1 == h || k + 1 == w;
+            (on_border && is_liver_or_tumor(p)).then_some((i, j, k))
+        })
+    }
+
     /// 将 3D 图像的六个表面矩形填充为背景.
     fn make_background_surface6(&mut self) {
         debug_assert_ne!(self.size(), 0);
@@ -693,6 +1127,31 @@ impl CtData3d {
         Ok(Self { scan, label })
     }
 
+    /// 根据裸扫描数据, 裸标签数据和部分元信息同时创建 `CtScan` 和 `CtLabel`,
+    /// 两者的 header 完全一致. 参数含义同 [`CtLabel::fake`],
+    /// 其中两份数据均按照 \[w, h, z\] 格式存储.
+    ///
+    /// 若两份数据形状不一致, 则程序 panic.
+    ///
+    /// # 注意
+    ///
+    /// 该方法可能会创建不一致的实体, 因此你应仅将其用于实验目的.
+    pub fn fake(
+        scan_data: Array3<f32>,
+        label_data: Array3<u8>,
+        pix_dim: [f32; 3],
+        qform_code: i16,
+        quatern_bcd: [f32; 3],
+    ) -> Self {
+        assert_eq!(scan_data.dim(), label_data.dim(), "CT 扫描和标注形状不一致");
+        let label = CtLabel::fake(label_data, pix_dim, qform_code, quatern_bcd);
+        let scan = CtScan {
+            header: label.header.clone(),
+            data: wh_z_to_standard_zhw(scan_data),
+        };
+        Self { scan, label }
+    }
+
     /// 获取水平切片个数.
     #[inline]
     pub fn len_z(&self) -> usize {
@@ -731,8 +1190,703 @@ impl CtData3d {
         self.scan.slice_iter_mut().zip(self.label.slice_iter_mut())
     }
 
+    /// 获取能按升序迭代 3D 水平 (z 索引, 扫描, 标注) 不可变切片的迭代器.
+    #[inline]
+    pub fn indexed_slice_iter(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (usize, ScanSlice<'_>, LabelSlice<'_>)> {
+        (0..self.len_z())
+            .zip(self.slice_iter())
+            .map(|(z, (scan, label))| (z, scan, label))
+    }
+
+    /// 获取能按升序迭代 3D 水平 (z 索引, 扫描, 标注) 可变切片的迭代器.
+    #[inline]
+    pub fn indexed_slice_iter_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<Item = (usize, ScanSliceMut<'_>, LabelSliceMut<'_>)> {
+        (0..self.len_z())
+            .zip(self.slice_iter_mut())
+            .map(|(z, (scan, label))| (z, scan, label))
+    }
+
+    /// 获取能按升序迭代含有肝脏或肿瘤体素的 (z 索引, 扫描, 标注) 不可变切片的迭代器.
+    /// 不含任何前景的切片被跳过, 判断时遇到第一个前景像素即返回.
+    pub fn iter_liver_slices(
+        &self,
+    ) -> impl Iterator<Item = (usize, ScanSlice<'_>, LabelSlice<'_>)> {
+        self.indexed_slice_iter()
+            .filter(|(_, _, label)| label.iter().any(|c| is_liver_or_tumor(*c)))
+    }
+
     /// 获取能按行优先序迭代 3D (扫描, 标注) 像素的迭代器.
     pub fn iter(&self) -> impl ExactSizeIterator<Item = (&f32, &u8)> {
         self.scan.data.iter().zip(self.label.data.iter())
     }
+
+    /// 按照肝脏 (含肿瘤) 的包围盒同时裁剪扫描和标注, 以缩小后续处理的数据规模.
+    /// 包围盒在每个方向上向外扩展 `margin` 个体素, 并截断在数据边界内.
+    ///
+    /// 若标注中不存在肝脏体素, 则返回原数据的完整拷贝.
+    pub fn crop_to_liver(&self, margin: usize) -> CtData3d {
+        let (z, h, w) = self.label.shape();
+        let Some(((z0, h0, w0), (z1, h1, w1))) = self.label.bounding_box(is_liver_or_tumor) else {
+            return self.clone();
+        };
+        let min = (
+            z0.saturating_sub(margin),
+            h0.saturating_sub(margin),
+            w0.saturating_sub(margin),
+        );
+        let max = (
+            (z1 + margin).min(z),
+            (h1 + margin).min(h),
+            (w1 + margin).min(w),
+        );
+        Self {
+            scan: self.scan.crop(min, max),
+            label: self.label.crop(min, max),
+        }
+    }
+
+    /// 沿 z 轴把数据切分为若干段连续的子数据, 每段 (最后一段除外) 包含 `chunk`
+    /// 张水平切片, 最后一段可能较少. 各段按 z 升序排列, 其 header 中的形状信息已更新,
+    /// 其余元信息与 [`CtScan::crop`] 的约定相同.
+    ///
+    /// 可用于分块并行处理或内存受限的场景. 若 `chunk` 为 0, 则程序 panic.
+    pub fn split_z(&self, chunk: usize) -> Vec<CtData3d> {
+        assert_ne!(chunk, 0, "分块大小不能为 0");
+        let (z, h, w) = self.label.shape();
+        (0..z)
+            .step_by(chunk)
+            .map(|z0| {
+                let (min, max) = ((z0, 0, 0), ((z0 + chunk).min(z), h, w));
+                Self {
+                    scan: self.scan.crop(min, max),
+                    label: self.label.crop(min, max),
+                }
+            })
+            .collect()
+    }
+
+    /// 若扫描与标注的网格 (形状或体素间距) 不一致, 则以最近邻插值把标注重采样到
+    /// 扫描的网格上, 使两者对齐, 从而可以安全地使用 [`Self::iter`] 等按体素对齐的方法.
+    /// 返回是否进行了重采样.
+    ///
+    /// 假设两者的原点相同, 即索引 `(0, 0, 0)` 的体素中心重合. 扫描体素的物理位置
+    /// 对应到标注网格以外时为背景. 重采样后标注的 header 由扫描的 header 派生.
+    ///
+    /// 若网格不一致, 但两者的方向 (`qform_code` 与四元数) 不同, 则无法重采样:
+    /// 标注保持不变并返回 `false`. 因此返回 `false` 时两者未必对齐,
+    /// 调用者需要自行比较形状和体素间距.
+    pub fn harmonize(&mut self) -> bool {
+        let (scan, label) = (&self.scan, &self.label);
+        if scan.shape() == label.shape() && scan.pix_dim() == label.pix_dim() {
+            return false;
+        }
+        let orientation = |h: &NiftiHeader| (h.qform_code, h.quatern_b, h.quatern_c, h.quatern_d);
+        if orientation(&scan.header) != orientation(&label.header) {
+            return false;
+        }
+
+        let (scan_dim, label_dim) = (scan.pix_dim(), label.pix_dim());
+        let (z, h, w) = label.shape();
+        let len = [z, h, w];
+        let nearest = |i: usize, axis: usize| {
+            let j = (i as f64 * scan_dim[axis] / label_dim[axis]).round() as usize;
+            (j < len[axis]).then_some(j)
+        };
+        let data = Array3::from_shape_fn(scan.shape(), |(i, j, k)| {
+            match (nearest(i, 0), nearest(j, 1), nearest(k, 2)) {
+                (Some(i), Some(j), Some(k)) => label.data[(i, j, k)],
+                _ => LITS_BACKGROUND,
+            }
+        });
+        self.label = CtLabel {
+            header: scan.derived_label_header(),
+            data,
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::sector::Orientation;
+
+    #[test]
+    fn test_try_fake() {
+        let raw = Array3::from_shape_fn((4, 3, 2), |(w, h, z)| ((w + h + z) % 3) as u8);
+        let label = CtLabel::try_fake(raw.clone(), [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]).unwrap();
+        let expected = CtLabel::fake(raw.clone(), [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]);
+        assert_eq!(label.data, expected.data);
+        assert_eq!(label.shape(), (2, 3, 4));
+        assert_eq!(label.pix_dim(), expected.pix_dim());
+
+        // 空维度.
+        let err = CtLabel::try_fake(Array3::zeros((4, 0, 2)), [1.0; 3], 1, [0.0; 3]);
+        assert_eq!(err.unwrap_err(), FakeError::EmptyDim(1));
+
+        // 非法体素间距: 0, 负数和 NaN.
+        for (pix_dim, axis) in [
+            ([0.0, 1.0, 1.0], 0),
+            ([1.0, -0.5, 1.0], 1),
+            ([1.0, 1.0, f32::NAN], 2),
+        ] {
+            let err = CtLabel::try_fake(raw.clone(), pix_dim, 1, [0.0; 3]).unwrap_err();
+            assert!(
+                matches!(err, FakeError::InvalidPixDim(a, _) if a == axis),
+                "{err:?}"
+            );
+        }
+
+        // 非法体素值: 报告第一个非法体素.
+        let mut bad = raw.clone();
+        bad[(3, 1, 0)] = 255;
+        bad[(3, 2, 1)] = 3;
+        let err = CtLabel::try_fake(bad, [1.0; 3], 1, [0.0; 3]).unwrap_err();
+        assert_eq!(err, FakeError::InvalidValue((3, 1, 0), 255));
+
+        // 空维度优先于其他错误.
+        let err = CtLabel::try_fake(Array3::zeros((0, 1, 1)), [-1.0; 3], 1, [0.0; 3]);
+        assert_eq!(err.unwrap_err(), FakeError::EmptyDim(0));
+    }
+
+    #[test]
+    fn test_crop_to_liver() {
+        // [w, h, z] = [8, 8, 6].
+        let mut raw = Array3::<u8>::zeros((8, 8, 6));
+        raw[[3, 2, 1]] = LITS_LIVER;
+        raw[[4, 5, 2]] = LITS_TUMOR;
+        let label = CtLabel::fake(raw, [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]);
+        assert_eq!(label.shape(), (6, 8, 8));
+
+        let mut data = Array3::<f32>::zeros(label.data.dim());
+        data[(1, 2, 3)] = 60.0;
+        let scan = CtScan {
+            header: label.header.clone(),
+            data,
+        };
+        let ct = CtData3d { scan, label };
+
+        let cropped = ct.crop_to_liver(1);
+        assert_eq!(cropped.scan.shape(), cropped.label.shape());
+        assert_eq!(cropped.label.shape(), (4, 6, 4));
+        assert_eq!(cropped.scan.data().dim(), cropped.label.data().dim());
+        assert_eq!(cropped.label.pix_dim(), ct.label.pix_dim());
+
+        // 原 (1, 2, 3) 处的体素在裁剪后位于 (1, 2, 3) - (0, 1, 2).
+        assert_eq!(cropped.label[(1, 1, 1)], LITS_LIVER);
+        assert_eq!(cropped.scan[(1, 1, 1)], 60.0);
+        assert_eq!(cropped.label[(2, 4, 2)], LITS_TUMOR);
+        assert_eq!(cropped.label.count(LITS_LIVER), 1);
+
+        // 足够大的 margin 会被截断在数据边界内.
+        assert_eq!(ct.crop_to_liver(100).label.shape(), ct.label.shape());
+    }
+
+    #[test]
+    fn test_iter_liver_slices() {
+        // [w, h, z] = [4, 3, 6], 只有 z = 1 (肝脏), 3 (肿瘤), 4 (两者) 含有前景.
+        let mut raw = Array3::<u8>::zeros((4, 3, 6));
+        raw[[0, 0, 1]] = LITS_LIVER;
+        raw[[3, 2, 3]] = LITS_TUMOR;
+        raw[[1, 1, 4]] = LITS_LIVER;
+        raw[[2, 1, 4]] = LITS_TUMOR;
+        let label = CtLabel::fake(raw, [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]);
+        let scan = CtScan {
+            header: label.header.clone(),
+            data: Array3::from_shape_fn(label.data.dim(), |(z, _, _)| z as f32),
+        };
+        let ct = CtData3d { scan, label };
+
+        let yielded: Vec<_> = ct.iter_liver_slices().collect();
+        assert_eq!(
+            yielded.iter().map(|(z, _, _)| *z).collect::<Vec<_>>(),
+            [1, 3, 4]
+        );
+        for (z, scan, label) in yielded {
+            assert!(scan.iter().all(|&v| v == z as f32));
+            assert!(label.has_liver() || label.has_tumor());
+        }
+
+        let empty = CtData3d {
+            scan: ct.scan.clone(),
+            label: CtLabel::fake(
+                Array3::zeros((4, 3, 6)),
+                [0.7, 0.7, 2.5],
+                1,
+                [0.0, 0.0, 1.0],
+            ),
+        };
+        assert_eq!(empty.iter_liver_slices().count(), 0);
+    }
+
+    #[test]
+    fn test_split_z() {
+        // [w, h, z] = [5, 4, 7].
+        let raw = Array3::from_shape_fn((5, 4, 7), |(w, h, z)| ((w * h + z) % 3) as u8);
+        let label = CtLabel::fake(raw, [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]);
+        let scan = CtScan {
+            header: label.header.clone(),
+            data: Array3::from_shape_fn(label.data.dim(), |(z, h, w)| {
+                (z * 100 + h * 10 + w) as f32
+            }),
+        };
+        let ct = CtData3d { scan, label };
+
+        let chunks = ct.split_z(3);
+        assert_eq!(
+            chunks.iter().map(CtData3d::len_z).collect::<Vec<_>>(),
+            [3, 3, 1]
+        );
+        for c in chunks.iter() {
+            assert_eq!(c.scan.shape(), c.scan.data.dim());
+            assert_eq!(c.label.shape(), c.label.data.dim());
+            assert_eq!(c.label.header.dim[3] as usize, c.len_z());
+            assert_eq!(c.label.pix_dim(), ct.label.pix_dim());
+        }
+
+        // 沿 z 轴重新拼接后与原数据一致.
+        let scans: Vec<_> = chunks.iter().map(|c| c.scan.data.view()).collect();
+        let labels: Vec<_> = chunks.iter().map(|c| c.label.data.view()).collect();
+        assert_eq!(ndarray::concatenate(Axis(0), &scans).unwrap(), ct.scan.data);
+        assert_eq!(
+            ndarray::concatenate(Axis(0), &labels).unwrap(),
+            ct.label.data
+        );
+
+        assert_eq!(ct.split_z(7).len(), 1);
+        assert_eq!(ct.split_z(100)[0].label.data, ct.label.data);
+    }
+
+    #[test]
+    fn test_open_rescale() {
+        use nifti::writer::WriterOptions;
+
+        let dir = std::env::temp_dir().join(format!("ct-berry-rescale-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scan.nii");
+
+        // [w, h, z] = [4, 3, 2], 存储值为 0..24.
+        let raw = Array3::from_shape_fn((4, 3, 2), |(w, h, z)| (w * 6 + h * 2 + z) as i16);
+        let label = CtLabel::fake(Array3::zeros((4, 3, 2)), [1.0; 3], 1, [0.0; 3]);
+        WriterOptions::new(&path)
+            .reference_header(&label.header)
+            .write_nifti(&raw)
+            .unwrap();
+        // 写入时 nifti 总是把缩放参数设为 (1, 0), 因此直接修改文件中
+        // `scl_slope` (偏移 112) 和 `scl_inter` (偏移 116) 两个字段.
+        let set_scale = |slope: f32, inter: f32| {
+            let mut bytes = std::fs::read(&path).unwrap();
+            bytes[112..116].copy_from_slice(&slope.to_le_bytes());
+            bytes[116..120].copy_from_slice(&inter.to_le_bytes());
+            std::fs::write(&path, bytes).unwrap();
+        };
+        let expected = |f: fn(f32) -> f32| wh_z_to_standard_zhw(raw.mapv(|v| f(v as f32)));
+
+        set_scale(2.0, -1024.0);
+        let scan = CtScan::open(&path).unwrap();
+        assert_eq!(scan.header.scl_slope, 2.0);
+        assert_eq!(scan.data, expected(|v| 2.0 * v - 1024.0));
+        let stored = CtScan::open_raw(&path).unwrap();
+        assert_eq!(stored.header.scl_inter, -1024.0);
+        assert_eq!(stored.data, expected(|v| v));
+
+        // `scl_slope` 为 0 表示不缩放.
+        set_scale(0.0, -1024.0);
+        assert_eq!(CtScan::open(&path).unwrap().data, expected(|v| v));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_to_i16_volume() {
+        // 整数 HU: 无损导出.
+        let raw =
+            Array3::from_shape_fn((4, 3, 2), |(w, h, z)| (w * 300 + h * 7 + z) as f32 - 1024.0);
+        let scan = CtScan::fake(raw, [1.0; 3], 0, [0.0; 3]);
+        let (volume, slope, inter) = scan.to_i16_volume();
+        assert_eq!((slope, inter), (1.0, 0.0));
+        assert_eq!(volume.dim(), scan.shape());
+        assert_eq!(volume.mapv(|v| v as f32), scan.data);
+
+        // 非整数 HU: 重建误差不超过半个量化步长.
+        let raw = Array3::from_shape_fn((8, 8, 3), |(w, h, z)| {
+            -1000.0 + 2500.0 * ((w * 64 + h * 3 + z) as f32 * 0.618_034).fract()
+        });
+        let mut scan = CtScan::fake(raw, [1.0; 3], 0, [0.0; 3]);
+        scan.data[(0, 0, 0)] = -1000.5;
+        scan.data[(0, 0, 1)] = 1500.25;
+        let (volume, slope, inter) = scan.to_i16_volume();
+        assert!(slope > 0.0 && slope < 0.04, "{slope}");
+        for (&q, &hu) in volume.iter().zip(scan.data.iter()) {
+            let restored = slope as f64 * q as f64 + inter as f64;
+            assert!(
+                (restored - hu as f64).abs() <= slope as f64 / 2.0 + 1e-4,
+                "{hu}"
+            );
+        }
+        // 量化充分利用了整个 `i16` 范围.
+        assert_eq!(volume[(0, 0, 0)], i16::MIN);
+        assert_eq!(volume[(0, 0, 1)], i16::MAX);
+
+        // 非有限值与常数体积.
+        let mut scan = CtScan::fake(Array3::from_elem((2, 2, 1), 0.5), [1.0; 3], 0, [0.0; 3]);
+        scan.data[(0, 0, 0)] = f32::NAN;
+        scan.data[(0, 0, 1)] = f32::INFINITY;
+        let (volume, slope, inter) = scan.to_i16_volume();
+        assert_eq!((slope, inter), (1.0, 0.5));
+        assert_eq!(volume.as_slice().unwrap(), &[0, i16::MAX, 0, 0]);
+    }
+
+    #[test]
+    fn test_pad() {
+        // [w, h, z] = [4, 3, 2].
+        let raw = Array3::from_shape_fn((4, 3, 2), |(w, h, z)| ((w + h + z) % 3) as u8);
+        let label = CtLabel::fake(raw, [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]);
+
+        let padded = label.pad([1, 2, 3], LITS_BACKGROUND);
+        assert_eq!(padded.shape(), (4, 7, 10));
+        assert_eq!(padded.data.dim(), padded.shape());
+        assert_eq!(padded.pix_dim(), label.pix_dim());
+        assert_eq!(padded.data.slice(s![1..3, 2..5, 3..7]), label.data.view());
+        assert_eq!(padded.count(LITS_LIVER), label.count(LITS_LIVER));
+        assert_eq!(padded.crop((1, 2, 3), (3, 5, 7)).data, label.data);
+        assert_eq!(label.pad([0; 3], LITS_TUMOR).data, label.data);
+
+        let scan = CtScan {
+            header: label.header.clone(),
+            data: label.data.mapv(|v| v as f32 * 100.0),
+        };
+        let padded = scan.pad([2, 0, 1], -1000.0);
+        assert_eq!(padded.shape(), (6, 3, 6));
+        assert_eq!(padded[(0, 0, 0)], -1000.0);
+        assert_eq!(padded[(5, 2, 5)], -1000.0);
+        assert_eq!(padded[(3, 1, 2)], scan[(1, 1, 1)]);
+        assert_eq!(
+            padded.data.iter().filter(|&&v| v == -1000.0).count(),
+            padded.size() - scan.size()
+        );
+    }
+
+    #[test]
+    fn test_indexed_slice_iter() {
+        let raw = Array3::from_shape_fn((4, 4, 5), |(_, _, z)| (z % 3) as u8);
+        let label = CtLabel::fake(raw, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+        let data = Array3::from_shape_fn(label.data.dim(), |(z, _, _)| z as f32);
+        let scan = CtScan {
+            header: label.header.clone(),
+            data,
+        };
+        let mut ct = CtData3d { scan, label };
+
+        let it = ct.indexed_slice_iter();
+        assert_eq!(it.len(), 5);
+        let mut indices = Vec::new();
+        for (z, scan, label) in it {
+            let (s, l) = ct.slice_at(z);
+            assert_eq!(scan.data(), s.data());
+            assert_eq!(label.array_view(), l.array_view());
+            assert_eq!(scan[(0, 0)], z as f32);
+            indices.push(z);
+        }
+        assert_eq!(indices, (0..5).collect::<Vec<_>>());
+
+        let it = ct.indexed_slice_iter_mut();
+        assert_eq!(it.len(), 5);
+        for (z, mut scan, _) in it {
+            scan[(1, 1)] = -(z as f32);
+        }
+        assert!((0..5).all(|z| ct.scan[(z, 1, 1)] == -(z as f32)));
+    }
+
+    #[test]
+    fn test_fake_ct_data() {
+        // [w, h, z] = [4, 4, 3].
+        let scan = Array3::from_shape_fn((4, 4, 3), |(w, h, z)| (z * 100 + h * 10 + w) as f32);
+        let mut label = Array3::<u8>::zeros((4, 4, 3));
+        label[[1, 2, 0]] = LITS_LIVER;
+        let ct = CtData3d::fake(scan, label, [0.8, 0.8, 5.0], 1, [0.0, 0.0, 1.0]);
+
+        assert_eq!(ct.scan.shape(), (3, 4, 4));
+        assert_eq!(ct.scan.shape(), ct.label.shape());
+        assert_eq!(ct.scan.data().dim(), ct.label.data().dim());
+        assert_eq!(ct.scan.pix_dim(), ct.label.pix_dim());
+        assert_eq!(ct.scan.header(), ct.label.header());
+        assert!(ct.label.is_faked());
+
+        // (z, h, w) 处的扫描值为 z * 100 + h * 10 + w.
+        let (scan, label) = ct.slice_at(0);
+        assert_eq!(label[(2, 1)], LITS_LIVER);
+        assert_eq!(scan[(2, 1)], 21.0);
+        assert_eq!(ct.slice_at(2).0[(3, 1)], 231.0);
+    }
+
+    #[test]
+    fn test_count_where() {
+        // [w, h, z] = [7, 6, 5].
+        let raw = Array3::from_shape_fn((7, 6, 5), |(w, h, z)| ((w * 3 + h * 5 + z) % 4) as u8);
+        let label = CtLabel::fake(raw, [1.0; 3], 1, [0.0; 3]);
+
+        let fg = label.count_where(is_liver_or_tumor);
+        assert_eq!(fg, label.count(LITS_LIVER) + label.count(LITS_TUMOR));
+        // 与串行计数一致.
+        assert_eq!(
+            fg,
+            label.data.iter().filter(|&&p| is_liver_or_tumor(p)).count()
+        );
+        assert_eq!(label.count_where(|p| p == 3), label.count(3));
+        assert_eq!(label.count_where(|_| true), label.size());
+        assert_eq!(label.count_where(|_| false), 0);
+    }
+
+    #[test]
+    fn test_harmonize() {
+        // 扫描: [w, h, z] = [20, 20, 6], 体素间距 [1, 1, 2].
+        let scan = CtScan::fake(Array3::zeros((20, 20, 6)), [1.0, 1.0, 2.0], 1, [0.0; 3]);
+        // 标注来自更粗的网格: [w, h, z] = [9, 9, 6], 体素间距 [2, 2, 2].
+        let raw = Array3::from_shape_fn((9, 9, 6), |(w, h, z)| ((w + 2 * h + z) % 3) as u8);
+        let label = CtLabel::fake(raw, [2.0, 2.0, 2.0], 1, [0.0; 3]);
+        let coarse = label.data.clone();
+        let mut ct = CtData3d { scan, label };
+
+        assert!(ct.harmonize());
+        assert_eq!(ct.label.shape(), ct.scan.shape());
+        assert_eq!(ct.label.pix_dim(), ct.scan.pix_dim());
+        for ((z, h, w), &p) in ct.label.data.indexed_iter() {
+            // 最近邻: 扫描的 (h, w) 对应标注的 (h / 2, w / 2), 四舍五入 (0.5 向远离 0 的方向舍入),
+            // 超出标注网格的部分为背景.
+            let (lh, lw) = (
+                (h as f64 / 2.0).round() as usize,
+                (w as f64 / 2.0).round() as usize,
+            );
+            let expected = if lh < 9 && lw < 9 {
+                coarse[(z, lh, lw)]
+            } else {
+                LITS_BACKGROUND
+            };
+            assert_eq!(p, expected, "{z}, {h}, {w}");
+        }
+        assert_eq!(ct.iter().count(), ct.scan.size());
+
+        // 已经对齐时不做任何事.
+        let before = ct.label.data.clone();
+        assert!(!ct.harmonize());
+        assert_eq!(ct.label.data, before);
+    }
+
+    #[test]
+    fn test_harmonize_orientation_mismatch() {
+        let scan = CtScan::fake(Array3::zeros((4, 4, 2)), [1.0; 3], 1, [0.0; 3]);
+        let label = CtLabel::fake(Array3::ones((2, 2, 2)), [2.0, 2.0, 1.0], 1, [0.0, 0.0, 1.0]);
+        let mut ct = CtData3d { scan, label };
+        let before = ct.label.clone();
+
+        // 方向不同时不重采样, 标注保持不变.
+        assert!(!ct.harmonize());
+        assert_eq!(ct.label.data, before.data);
+        assert_eq!(ct.label.header, before.header);
+        assert_ne!(ct.label.shape(), ct.scan.shape());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fake_ct_data_shape_mismatch() {
+        let _ = CtData3d::fake(
+            Array3::zeros((4, 4, 3)),
+            Array3::zeros((4, 4, 2)),
+            [1.0, 1.0, 1.0],
+            1,
+            [0.0, 0.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn test_liver_touches_border() {
+        // [w, h, z] = [6, 6, 4]. 肝脏位于内部.
+        let mut raw = Array3::<u8>::zeros((6, 6, 4));
+        raw.slice_mut(s![1..5, 1..5, 1..3]).fill(LITS_LIVER);
+        let label = CtLabel::fake(raw.clone(), [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+        assert!(!label.liver_touches_border());
+        assert_eq!(label.border_contact_voxels(), 0);
+
+        // 肝脏延伸到顶面 (z 最大的切片), 其中一个体素同时位于 w 方向的侧面.
+        raw.slice_mut(s![1..5, 1..5, 3]).fill(LITS_LIVER);
+        raw[[5, 2, 3]] = LITS_TUMOR;
+        let label = CtLabel::fake(raw, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+        assert!(label.liver_touches_border());
+        assert_eq!(label.border_contact_voxels(), 17);
+    }
+
+    #[test]
+    fn test_z_profile() {
+        // [w, h, z] = [6, 6, 5]. 第 z 层有 z * z 个前景体素.
+        let mut raw = Array3::<u8>::zeros((6, 6, 5));
+        for z in 0..5 {
+            raw.slice_mut(s![..z, ..z, z]).fill(LITS_LIVER);
+        }
+        raw[[0, 0, 4]] = LITS_TUMOR;
+        let label = CtLabel::fake(raw, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+
+        assert_eq!(label.z_profile(is_liver_or_tumor), vec![0, 1, 4, 9, 16]);
+        assert_eq!(label.z_profile(|p| p == LITS_LIVER), vec![0, 1, 4, 9, 15]);
+        assert_eq!(label.z_profile(|p| p == LITS_BACKGROUND)[1], 35);
+    }
+
+    #[test]
+    fn test_tumor_surface_contact() {
+        // [w, h, z] = [7, 7, 5]. 肝脏为 [1, 6) ^ 3 的立方体 (z 方向为 [0, 5)).
+        let mut raw = Array3::<u8>::zeros((7, 7, 5));
+        raw.slice_mut(s![1..6, 1..6, 0..5]).fill(LITS_LIVER);
+        // 内部肿瘤: 被肝脏完全包裹.
+        raw[[3, 3, 2]] = LITS_TUMOR;
+        // 表面肿瘤: 与背景相邻.
+        raw[[1, 3, 2]] = LITS_TUMOR;
+        // 表面肿瘤: 位于 z 方向数据边界上.
+        raw[[3, 3, 0]] = LITS_TUMOR;
+        let label = CtLabel::fake(raw, [1.0, 1.0, 1.0], 1, [0.0, 0.0, 1.0]);
+
+        let mut voxels = label.tumor_surface_voxels();
+        voxels.sort_unstable();
+        assert_eq!(voxels, vec![(0, 3, 3), (2, 3, 1)]);
+        assert_eq!(label.tumor_surface_contact(), 2);
+        assert_eq!(label.count(LITS_TUMOR), 3);
+    }
+
+    #[test]
+    fn test_physical_size() {
+        let label = CtLabel::fake(
+            Array3::zeros((512, 512, 75)),
+            [0.5, 0.5, 2.0],
+            1,
+            [0.0, 0.0, 1.0],
+        );
+        assert_eq!(label.shape(), (75, 512, 512));
+        assert_eq!(label.physical_size_mm(), [150.0, 256.0, 256.0]);
+        assert_eq!(label.center_index(), (37, 256, 256));
+
+        let label = label.crop((0, 0, 0), (1, 3, 3));
+        assert_eq!(label.physical_size_mm(), [2.0, 1.5, 1.5]);
+        assert_eq!(label.center_index(), (0, 1, 1));
+    }
+
+    #[test]
+    fn test_windowed_slice_iter() {
+        let mut raw = Array3::from_shape_fn((6, 5, 4), |(w, h, z)| {
+            (w as f32 * 17.0 + h as f32 * 31.0 + z as f32 * 7.0) - 60.0
+        });
+        raw[(0, 0, 1)] = f32::NAN;
+        let scan = CtScan::fake(raw, [1.0, 1.0, 1.0], 0, [0.0; 3]);
+        let window = CtWindow::new(40.0, 100.0).unwrap();
+
+        let iter = scan.windowed_slice_iter(window);
+        assert_eq!(iter.len(), scan.len_z());
+        for (z, windowed) in iter.enumerate() {
+            let sli = scan.slice_at(z);
+            assert_eq!(windowed.dim(), sli.shape());
+            for (pos, &hu) in sli.indexed_iter() {
+                assert_eq!(windowed[pos], window.eval(hu).unwrap_or(0));
+            }
+        }
+        assert_eq!(scan.windowed_slice_iter(window).nth(1).unwrap()[(0, 0)], 0);
+    }
+
+    #[test]
+    fn test_mirror_about_width() {
+        // [w, h, z] = [21, 8, 3], 关于 w = 10 对称.
+        let raw = Array3::from_shape_fn((21, 8, 3), |(w, h, _)| {
+            (w.abs_diff(10) + h < 9) as u8 * LITS_LIVER
+        });
+        let label = CtLabel::fake(raw, [0.7, 0.7, 2.5], 1, [0.0, 0.0, 1.0]);
+        let mirrored = label.mirror_about_width(10);
+        assert_eq!(mirrored.data, label.data);
+        assert_eq!(mirrored.shape(), label.shape());
+        assert_eq!(mirrored.pix_dim(), label.pix_dim());
+
+        // 标记的体素被映射到对称位置, 两次镜像还原.
+        let mut marked = label.clone();
+        marked[(1, 2, 3)] = LITS_TUMOR;
+        let mirrored = marked.mirror_about_width(10);
+        assert_eq!(mirrored[(1, 2, 17)], LITS_TUMOR);
+        assert_eq!(mirrored.count(LITS_TUMOR), 1);
+        assert_eq!(mirrored.mirror_about_width(10).data, marked.data);
+
+        // 偏离中央的对称面: 超出图像的部分被丢弃.
+        let shifted = marked.mirror_about_width(2);
+        assert_eq!(shifted[(1, 2, 1)], LITS_TUMOR);
+        assert!((5..21).all(|w| shifted[(0, 0, w)] == LITS_BACKGROUND));
+        assert_eq!(shifted[(0, 0, 4)], marked[(0, 0, 0)]);
+    }
+
+    /// 按 qform (四元数) 和 sform 分别计算体素 `[i, j, k]` 的物理坐标.
+    fn world(h: &NiftiHeader, [i, j, k]: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+        let (b, c, d) = (h.quatern_b, h.quatern_c, h.quatern_d);
+        let a = (1.0 - b * b - c * c - d * d).max(0.0).sqrt();
+        let r = [
+            [
+                a * a + b * b - c * c - d * d,
+                2.0 * (b * c - a * d),
+                2.0 * (b * d + a * c),
+            ],
+            [
+                2.0 * (b * c + a * d),
+                a * a + c * c - b * b - d * d,
+                2.0 * (c * d - a * b),
+            ],
+            [
+                2.0 * (b * d - a * c),
+                2.0 * (c * d + a * b),
+                a * a + d * d - c * c - b * b,
+            ],
+        ];
+        let qfac = if h.pixdim[0] < 0.0 { -1.0 } else { 1.0 };
+        let v = [i * h.pixdim[1], j * h.pixdim[2], k * h.pixdim[3] * qfac];
+        let offset = [h.quatern_x, h.quatern_y, h.quatern_z];
+        let q =
+            std::array::from_fn(|n| offset[n] + r[n][0] * v[0] + r[n][1] * v[1] + r[n][2] * v[2]);
+        let s =
+            [h.srow_x, h.srow_y, h.srow_z].map(|row| row[0] * i + row[1] * j + row[2] * k + row[3]);
+        (q, s)
+    }
+
+    #[test]
+    fn test_mirror_about_width_header() {
+        let raw = Array3::from_shape_fn((21, 8, 3), |(w, _, _)| (w < 5) as u8 * LITS_LIVER);
+        for bcd in [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0],
+            [0.5, 0.5, 0.5],
+        ] {
+            let mut label = CtLabel::fake(raw.clone(), [0.7, 0.7, 2.5], 2, bcd);
+            label.header.quatern_x = 10.0;
+            label.header.quatern_y = -20.0;
+            label.header.quatern_z = 30.0;
+            label.header.srow_x = [-0.7, 0.0, 0.0, 10.0];
+            label.header.srow_y = [0.0, 0.7, 0.0, -20.0];
+            label.header.srow_z = [0.0, 0.0, 2.5, 30.0];
+
+            for axis in [10, 3] {
+                let mirrored = label.mirror_about_width(axis);
+                for (i, j, k) in [(0, 0, 0), (4, 7, 2), (13, 2, 1)] {
+                    let src = (2 * axis) as f32 - i as f32;
+                    let (q0, s0) = world(&label.header, [src, j as f32, k as f32]);
+                    let (q1, s1) = world(&mirrored.header, [i as f32, j as f32, k as f32]);
+                    for n in 0..3 {
+                        assert!((q0[n] - q1[n]).abs() < 1e-3, "{bcd:?} {q0:?} {q1:?}");
+                        assert!((s0[n] - s1[n]).abs() < 1e-3, "{bcd:?} {s0:?} {s1:?}");
+                    }
+                }
+            }
+        }
+
+        // 镜像后 LLS 扇区模式的时针方向相反.
+        let label = CtLabel::fake(raw, [0.7, 0.7, 2.5], 2, [0.0, 0.0, 0.0]);
+        let before = label.lls_sector_pattern().unwrap();
+        let after = label.mirror_about_width(10).lls_sector_pattern().unwrap();
+        assert!(matches!(before.orientation(), Orientation::Clockwise));
+        assert!(matches!(after.orientation(), Orientation::CounterClockwise));
+    }
 }
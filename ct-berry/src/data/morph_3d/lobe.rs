@@ -0,0 +1,185 @@
+//! 基于三维距离变换分水岭的肝叶划分.
+
+use crate::consts::gray::*;
+use crate::data::slice::squared_edt_3d;
+use crate::{CtLabel, Idx3d, NiftiHeaderAttr};
+use ndarray::Array3;
+use ordered_float::NotNan;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+impl CtLabel {
+    /// 以三维距离变换分水岭近似地把肝脏 (含肿瘤) 划分为至多 `n_lobes` 个肝叶.
+    ///
+    /// 算法先按照体素的物理间距计算每个前景体素到背景的欧几里得距离
+    /// (体积以外视为背景), 再取距离变换中最强的 `n_lobes` 个局部极大值作为种子.
+    /// 为了避免同一个 "叶尖" 内的多个极大值被重复选取, 落在已选种子内切球内的候选被跳过.
+    /// 最后把距离的相反数作为地形, 从所有种子出发按照 6-邻域进行优先级泛洪,
+    /// 各个种子的 "水域" 在最窄处 (腰部) 相遇.
+    ///
+    /// 返回的标注与 `self` 共享 header, 每个前景体素的值为其所属肝叶的编号
+    /// (`1..=n_lobes`, 按种子强度降序编号), 背景以及不与任何种子连通的前景体素为 0.
+    /// 若局部极大值不足 `n_lobes` 个, 则实际的肝叶个数更少.
+    ///
+    /// 若 `n_lobes` 为 0 或大于 255, 则程序 panic.
+    pub fn split_lobes_3d(&self, n_lobes: usize) -> CtLabel {
+        assert!(
+            (1..=u8::MAX as usize).contains(&n_lobes),
+            "非法的肝叶个数 {n_lobes}"
+        );
+        let mask = self.data.mapv(is_liver_or_tumor);
+        let spacing = self.pix_dim();
+        let dist = squared_edt_3d(&mask, spacing);
+
+        let seeds = lobe_seeds(&dist, spacing, n_lobes);
+        let data = flood_3d(&dist, &seeds);
+        CtLabel {
+            header: self.header.clone(),
+            data,
+        }
+    }
+}
+
+/// 在平方距离变换 `dist` 中按强度降序选取至多 `n` 个 26-邻域局部极大值作为种子.
+/// 落在已选种子内切球内的候选被跳过. 强度相同时索引较小者优先.
+fn lobe_seeds(dist: &Array3<f64>, spacing: [f64; 3], n: usize) -> Vec<Idx3d> {
+    let (z, h, w) = dist.dim();
+    let is_local_max = |(i, j, k): Idx3d| {
+        let d = dist[(i, j, k)];
+        for di in -1isize..=1 {
+            for dj in -1isize..=1 {
+                for dk in -1isize..=1 {
+                    let (Some(ni), Some(nj), Some(nk)) = (
+                        i.checked_add_signed(di).filter(|&v| v < z),
+                        j.checked_add_signed(dj).filter(|&v| v < h),
+                        k.checked_add_signed(dk).filter(|&v| v < w),
+                    ) else {
+                        continue;
+                    };
+                    if dist[(ni, nj, nk)] > d {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    };
+
+    let mut candidates: Vec<(Idx3d, f64)> = dist
+        .indexed_iter()
+        .filter(|&(pos, &d)| d > 0.0 && is_local_max(pos))
+        .map(|(pos, &d)| (pos, d))
+        .collect();
+    // 稳定排序, 强度相同时保留行优先的索引顺序.
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let squared = |a: Idx3d, b: Idx3d| {
+        [(a.0, b.0), (a.1, b.1), (a.2, b.2)]
+            .iter()
+            .zip(spacing)
+            .map(|(&(p, q), s)| ((p as f64 - q as f64) * s).powi(2))
+            .sum::<f64>()
+    };
+    let mut seeds: Vec<Idx3d> = Vec::with_capacity(n);
+    for (pos, _) in candidates {
+        if seeds.len() == n {
+            break;
+        }
+        if seeds.iter().all(|&s| squared(pos, s) >= dist[s]) {
+            seeds.push(pos);
+        }
+    }
+    seeds
+}
+
+/// 以 `seeds` (第 `i` 个种子的标签为 `i + 1`) 为起点, 在 `dist > 0` 的区域上按照
+/// 距离从高到低进行 6-邻域的优先级泛洪. 同一高度的体素按照先进先出的顺序处理,
+/// 因此结果是确定的.
+fn flood_3d(dist: &Array3<f64>, seeds: &[Idx3d]) -> Array3<u8> {
+    let (z, h, w) = dist.dim();
+    let mut out = Array3::<u8>::zeros((z, h, w));
+
+    // (距离, 入队顺序, 位置, 标签). 距离大者优先, 同距离先入队者优先.
+    let mut heap = BinaryHeap::new();
+    let mut order = 0u64;
+    let key = |pos: Idx3d| NotNan::new(dist[pos]).unwrap();
+    for (i, &pos) in seeds.iter().enumerate() {
+        heap.push((key(pos), Reverse(order), pos, i as u8 + 1));
+        order += 1;
+    }
+    while let Some((_, _, pos, label)) = heap.pop() {
+        if out[pos] != 0 {
+            continue;
+        }
+        out[pos] = label;
+        let (i, j, k) = pos;
+        let neighbours = [
+            (i.wrapping_sub(1), j, k),
+            (i + 1, j, k),
+            (i, j.wrapping_sub(1), k),
+            (i, j + 1, k),
+            (i, j, k.wrapping_sub(1)),
+            (i, j, k + 1),
+        ];
+        for n in neighbours {
+            if n.0 < z && n.1 < h && n.2 < w && dist[n] > 0.0 && out[n] == 0 {
+                heap.push((key(n), Reverse(order), n, label));
+                order += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// \[w, h, z\] = \[48, 21, 21\] 的哑铃: 两个半径为 8 的球 (球心 w = 10, 37)
+    /// 由一根半径为 3 的圆柱沿宽度方向相连.
+    fn dumbbell() -> CtLabel {
+        let data = Array3::from_shape_fn((48, 21, 21), |(w, h, z)| {
+            let (w, h, z) = (w as f64, h as f64 - 10.0, z as f64 - 10.0);
+            let ball = |c: f64| ((w - c).powi(2) + h * h + z * z).sqrt() <= 8.0;
+            let bar = (10.0..=37.0).contains(&w) && h.hypot(z) <= 3.0;
+            if ball(10.0) || ball(37.0) || bar {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        CtLabel::fake(data, [1.0; 3], 0, [0.0; 3])
+    }
+
+    #[test]
+    fn test_split_lobes_3d_dumbbell() {
+        let label = dumbbell();
+        let lobes = label.split_lobes_3d(2);
+        assert_eq!(lobes.shape(), label.shape());
+
+        for (pos, &p) in label.data.indexed_iter() {
+            let l = lobes.data[pos];
+            if !is_liver(p) {
+                assert_eq!(l, 0);
+                continue;
+            }
+            // 两个球分别属于两个肝叶, 分界位于圆柱中段 (腰部).
+            let w = pos.2;
+            if w <= 20 {
+                assert_eq!(l, 1, "{pos:?}");
+            } else if w >= 27 {
+                assert_eq!(l, 2, "{pos:?}");
+            } else {
+                assert!(l == 1 || l == 2);
+            }
+        }
+        // 分界在腰部附近: 两侧的体素个数接近.
+        let cnt = |v: u8| lobes.data.iter().filter(|&&l| l == v).count();
+        let (a, b) = (cnt(1), cnt(2));
+        assert!(a.abs_diff(b) * 20 < a + b, "{a} vs {b}");
+
+        // 只要求一个肝叶时全部前景都属于它.
+        let single = label.split_lobes_3d(1);
+        assert_eq!(single.data, label.data.mapv(|p| is_liver(p) as u8));
+    }
+}
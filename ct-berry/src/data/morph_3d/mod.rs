@@ -2,12 +2,15 @@
 
 use self::phantom::PhantomMemento;
 use self::roi::RoiGenerator;
-use crate::consts::{gray::*, ElemType};
+use crate::consts::{gray::*, ElemType, Foreground};
 use crate::sector::{AxisDirection, Orientation};
 use crate::{CtLabel, Idx2d, Idx2dU16, Idx3d, Idx3dU16, NiftiHeaderAttr};
+use ndarray::Array3;
 use std::cmp::Ordering;
 use std::ops::AddAssign;
 
+mod curvature;
+mod lobe;
 mod phantom;
 
 mod roi;
@@ -88,18 +91,57 @@ impl AddAssign<UnitVec> for Idx2d {
     }
 }
 
+/// 形态学腐蚀到最后一轮时, 从剩余的候选体素中选取中心的规则.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum CenterTiebreak {
+    /// 选取 (z, H, W) 字典序最小的索引. 这是 [`CtLabel::center`] 的行为.
+    #[default]
+    MinIndex,
+
+    /// 选取最接近候选体素质心 (体素坐标意义下) 的索引.
+    /// 若有多个索引与质心距离相同, 则选取其中字典序最小的.
+    Centroid,
+
+    /// 选取 (z, H, W) 字典序最大的索引.
+    MaxIndex,
+}
+
+impl CenterTiebreak {
+    /// 按照规则从非空的 `candidates` 中选取一个索引. 结果与 `candidates` 的顺序无关.
+    fn pick(&self, candidates: &[Idx3d]) -> Idx3d {
+        debug_assert!(!candidates.is_empty());
+        match self {
+            CenterTiebreak::MinIndex => *candidates.iter().min().unwrap(),
+            CenterTiebreak::MaxIndex => *candidates.iter().max().unwrap(),
+            CenterTiebreak::Centroid => {
+                let n = candidates.len() as f64;
+                let (sz, sh, sw) = candidates.iter().fold((0.0, 0.0, 0.0), |acc, &(z, h, w)| {
+                    (acc.0 + z as f64, acc.1 + h as f64, acc.2 + w as f64)
+                });
+                let (cz, ch, cw) = (sz / n, sh / n, sw / n);
+                let dist = |&(z, h, w): &Idx3d| {
+                    (z as f64 - cz).powi(2) + (h as f64 - ch).powi(2) + (w as f64 - cw).powi(2)
+                };
+                *candidates
+                    .iter()
+                    .min_by(|a, b| dist(a).total_cmp(&dist(b)).then(a.cmp(b)))
+                    .unwrap()
+            }
+        }
+    }
+}
+
 /// Center ROI 实现块
 impl CtLabel {
     /// 获取中心 ROI.
     ///
     /// 该函数对肝脏 CT label 进行三维形态学腐蚀, 找到形态学中心,
-    /// 并以此为基准, 半径为 `radius` 获取周围所有肝脏 + 肿瘤体素.
+    /// 并以此为基准, 半径为 `radius` 获取周围所有前景体素.
     ///
     /// 如果指定 `anisotropic` 为 `true`,
     /// 则在三维腐蚀时会考虑体素本身的各向异性 (算法由该库提出并实现).
-    /// 否则, 按照各向同性进行腐蚀. 如果 `include_tumor` 为 `true`,
-    /// 则返回值不包含肿瘤索引. 实际上若 `include_tumor` 为 `false`,
-    /// 则该函数将 `LITS_LIVER`, `LITS_TUMOR` 完全视为等价.
+    /// 否则, 按照各向同性进行腐蚀. 返回值只包含属于 `fg` 的前景体素,
+    /// 但腐蚀时 `LITS_LIVER`, `LITS_TUMOR` 总是被视为等价.
     ///
     /// # 注意
     ///
@@ -110,23 +152,35 @@ impl CtLabel {
     /// # 返回值
     ///
     /// 目标索引集合. 无顺序保证. 如果不存在前景, 则返回空 `Vec`.
-    pub fn center_roi_3d(&self, radius: f64, anisotropic: bool, include_tumor: bool) -> Vec<Idx3d> {
+    pub fn center_roi_3d_fg(&self, radius: f64, anisotropic: bool, fg: Foreground) -> Vec<Idx3d> {
         assert!(radius >= 0.0);
         let Some(center) = self.center(anisotropic) else {
             return vec![];
         };
-        RoiGenerator::new(self, &center).extract_roi_3d(radius, include_tumor)
+        RoiGenerator::new(self, &center).extract_roi_3d(radius, fg)
     }
 
-    /// 与 [`Self::center_roi_3d`] 类似, 但在三维形态学腐蚀到中心后获取二维 ROI.
+    /// 同 [`Self::center_roi_3d_fg`]. `include_tumor` 为 `true` 时对应
+    /// [`Foreground::LiverAndTumor`], 否则对应 [`Foreground::LiverOnly`].
+    #[deprecated(note = "请使用 `center_roi_3d_fg`")]
+    #[inline]
+    pub fn center_roi_3d(&self, radius: f64, anisotropic: bool, include_tumor: bool) -> Vec<Idx3d> {
+        self.center_roi_3d_fg(
+            radius,
+            anisotropic,
+            Foreground::from_include_tumor(include_tumor),
+        )
+    }
+
+    /// 与 [`Self::center_roi_3d_fg`] 类似, 但在三维形态学腐蚀到中心后获取二维 ROI.
     /// 相应地, 返回值的第一个分量代表 ROI 所在的水平切片索引,
     /// 第二个分量是该水平切片上的二维索引集合. 如果不存在前景,
     /// 返回值元组的第一个分量为 `usize::MAX`, 第二个分量为空 `Vec`.
-    pub fn center_roi_2d(
+    pub fn center_roi_2d_fg(
         &self,
         radius: f64,
         anisotropic: bool,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> (usize, Vec<Idx2d>) {
         assert!(radius >= 0.0);
         let Some(center) = self.center(anisotropic) else {
@@ -134,10 +188,58 @@ impl CtLabel {
         };
         (
             center.0,
-            RoiGenerator::new(self, &center).extract_roi_2d(radius, include_tumor),
+            RoiGenerator::new(self, &center).extract_roi_2d(radius, fg),
         )
     }
 
+    /// 同 [`Self::center_roi_2d_fg`]. `include_tumor` 为 `true` 时对应
+    /// [`Foreground::LiverAndTumor`], 否则对应 [`Foreground::LiverOnly`].
+    #[deprecated(note = "请使用 `center_roi_2d_fg`")]
+    #[inline]
+    pub fn center_roi_2d(
+        &self,
+        radius: f64,
+        anisotropic: bool,
+        include_tumor: bool,
+    ) -> (usize, Vec<Idx2d>) {
+        self.center_roi_2d_fg(
+            radius,
+            anisotropic,
+            Foreground::from_include_tumor(include_tumor),
+        )
+    }
+
+    /// 与 [`Self::center_roi_3d_fg`] 相同地获取中心 ROI, 但将 ROI 作为独立的子标注返回,
+    /// 便于单独处理或可视化.
+    ///
+    /// 子标注覆盖 ROI 的包围盒, 其中属于 ROI 的体素保留原标签值, 其余体素均为
+    /// `LITS_BACKGROUND`. 返回值的第一个分量是包围盒在原标注中的起点 (z, H, W),
+    /// 即子标注索引 `(z, h, w)` 对应原标注索引 `(z0 + z, h0 + h, w0 + w)`.
+    ///
+    /// 如果不存在前景, 或 ROI 中没有属于 `fg` 的体素, 则返回 `None`.
+    pub fn center_roi_sub_label(
+        &self,
+        radius: f64,
+        anisotropic: bool,
+        fg: Foreground,
+    ) -> Option<(Idx3d, CtLabel)> {
+        let roi = self.center_roi_3d_fg(radius, anisotropic, fg);
+        let first = *roi.first()?;
+        let (min, max) = roi.iter().fold((first, first), |(lo, hi), &(z, h, w)| {
+            (
+                (lo.0.min(z), lo.1.min(h), lo.2.min(w)),
+                (hi.0.max(z), hi.1.max(h), hi.2.max(w)),
+            )
+        });
+
+        let mut sub = self.crop(min, (max.0 + 1, max.1 + 1, max.2 + 1));
+        sub.data.fill(LITS_BACKGROUND);
+        for (z, h, w) in roi {
+            sub.data[(z - min.0, h - min.1, w - min.2)] = self.data[(z, h, w)];
+        }
+        Some((min, sub))
+    }
+
     /// 获取中心索引.
     ///
     /// 该函数对肝脏 CT label 进行形态学腐蚀, 直至到达中心. 如果指定
@@ -151,40 +253,48 @@ impl CtLabel {
     /// 2. 原扫描中不应当存在 `LITS_BACKGROUND` 背景空洞, 否则程序行为可能异常.
     ///   因此一般情况下请确保刚调用过 `self.fill_background_hollow()`.
     /// 3. 如果原图不存在 `LITS_{LIVER, TUMOR}` 则返回 `None`.
+    /// 4. `LITS_BOUNDARY` 等其他取值与 `LITS_BACKGROUND` 等价, 腐蚀也会从它们的边界开始.
     ///
     /// # 返回值
     ///
     /// 中心点索引. 函数保证对相同扫描运行多次的结果一致 (稳定性).
+    #[inline]
     pub fn center(&self, anisotropic: bool) -> Option<Idx3d> {
+        self.center_with_tiebreak(anisotropic, CenterTiebreak::MinIndex)
+    }
+
+    /// 同 [`Self::center`], 但由 `tiebreak` 决定如何从腐蚀的最后一轮所剩的
+    /// 多个候选体素中选取中心. 对于任何规则, 结果都是确定的.
+    ///
+    /// 以不同规则多次运行, 可以考察中心定位对平局处理的敏感程度.
+    pub fn center_with_tiebreak(
+        &self,
+        anisotropic: bool,
+        tiebreak: CenterTiebreak,
+    ) -> Option<Idx3d> {
         assert_eq!(self.height_mm(), self.width_mm());
 
-        let (db, vox_cnt) = self.init_book_keeping();
-        (vox_cnt != 0).then(|| {
-            if anisotropic {
-                self.center_roi_anisotropic(db, vox_cnt)
-            } else {
-                self.center_roi_isotropic(db, vox_cnt)
-            }
-        })
+        let (db, vox_cnt) = self.init_book_keeping(Foreground::LiverAndTumor);
+        (vox_cnt != 0)
+            .then(|| self.erode_to_center(db, vox_cnt, anisotropic, tiebreak, &mut |_| {}))
     }
 
     /// 遍历整个 3D 扫描, 返回适当初始化的 `PhantomMemento`
-    /// 和目前肝脏 + 肿瘤总体素个数.
-    fn init_book_keeping(&self) -> (PhantomMemento, usize) {
+    /// 和目前属于 `fg` 的前景体素总个数.
+    ///
+    /// 所有不属于 `fg` 的体素 (包括 `LITS_BOUNDARY`) 都按背景处理, 与之相邻的前景体素
+    /// 位于表面.
+    fn init_book_keeping(&self, fg: Foreground) -> (PhantomMemento, usize) {
         let mut db = PhantomMemento::new();
         let mut vox_cnt = 0usize;
 
-        for (pos, _) in self
-            .data
-            .indexed_iter()
-            .filter(|(_, p)| is_liver_or_tumor(**p))
-        {
+        for (pos, _) in self.data.indexed_iter().filter(|(_, p)| fg.contains(**p)) {
             vox_cnt += 1;
-            db.set_foreground(&pos); // <= 前景是 "肝脏 + 肿瘤"
+            db.set_foreground(&pos);
 
             let mut is_surface = false; // 当前前景像素是否位于表面 (边缘)
             for neigh_pos in self.diamond_neighbours(pos) {
-                if is_background(self[neigh_pos]) {
+                if !fg.contains(self[neigh_pos]) {
                     is_surface = true;
                     db.set_background(&neigh_pos);
                 }
@@ -197,8 +307,71 @@ impl CtLabel {
         (db, vox_cnt)
     }
 
+    /// 按 `anisotropic` 选择腐蚀方式, 直至腐蚀到中心. 每一轮腐蚀 (包括最后一轮)
+    /// 的体素集合都会传给 `on_round`.
+    #[inline]
+    fn erode_to_center(
+        &self,
+        db: PhantomMemento,
+        vox_cnt: usize,
+        anisotropic: bool,
+        tiebreak: CenterTiebreak,
+        on_round: &mut dyn FnMut(&[Idx3d]),
+    ) -> Idx3d {
+        if anisotropic {
+            self.center_roi_anisotropic(db, vox_cnt, tiebreak, on_round)
+        } else {
+            self.center_roi_isotropic(db, vox_cnt, tiebreak, on_round)
+        }
+    }
+
+    /// 腐蚀深度图.
+    ///
+    /// 以与 [`Self::center`] 相同的方式对 `fg` 前景进行完全腐蚀, 返回与扫描形状相同的
+    /// 3D 数组: 每个前景体素的值为它被腐蚀掉的轮次 (从 1 开始), 非前景体素为 0.
+    /// 因此最大值所在的体素即为腐蚀中心的候选, 而表面体素的值为 1.
+    ///
+    /// 与 [`Self::center`] 不同, 当 `fg` 为 [`Foreground::LiverOnly`] 时,
+    /// 肿瘤体素被视为背景, 腐蚀也会从肿瘤边界开始.
+    ///
+    /// 各向异性腐蚀中只沿部分方向腐蚀的轮次同样计数, 因此深度可以看作以
+    /// 较小体素间距为单位的近似物理距离.
+    ///
+    /// # Panics
+    ///
+    /// 轮次超过 `u16::MAX` 时 panic. 对于任何实际的 CT 标注不会发生.
+    pub fn erosion_depth_3d(&self, anisotropic: bool, fg: Foreground) -> Array3<u16> {
+        assert_eq!(self.height_mm(), self.width_mm());
+
+        let mut depth = Array3::zeros(self.data.raw_dim());
+        let (db, vox_cnt) = self.init_book_keeping(fg);
+        if vox_cnt == 0 {
+            return depth;
+        }
+        let mut round = 0u16;
+        self.erode_to_center(
+            db,
+            vox_cnt,
+            anisotropic,
+            CenterTiebreak::MinIndex,
+            &mut |eroded| {
+                round = round.checked_add(1).expect("腐蚀轮次超过 u16::MAX");
+                for &pos in eroded {
+                    depth[pos] = round;
+                }
+            },
+        );
+        depth
+    }
+
     /// 各向同性腐蚀到中心. 保证结果的稳定性.
-    fn center_roi_isotropic(&self, mut db: PhantomMemento, mut vox_cnt: usize) -> Idx3d {
+    fn center_roi_isotropic(
+        &self,
+        mut db: PhantomMemento,
+        mut vox_cnt: usize,
+        tiebreak: CenterTiebreak,
+        on_round: &mut dyn FnMut(&[Idx3d]),
+    ) -> Idx3d {
         debug_assert!(vox_cnt >= 1);
 
         loop {
@@ -228,9 +401,10 @@ impl CtLabel {
                     }
                 }
             }
+            on_round(&to_erode);
             if vox_cnt == to_erode.len() {
                 debug_assert!(vox_cnt >= 1);
-                break *to_erode.iter().min().unwrap();
+                break tiebreak.pick(&to_erode);
             }
 
             // 本轮腐蚀
@@ -246,21 +420,35 @@ impl CtLabel {
 
     /// 各向异性腐蚀到中心. 保证结果的稳定性.
     #[inline]
-    fn center_roi_anisotropic(&self, db: PhantomMemento, vox_cnt: usize) -> Idx3d {
+    fn center_roi_anisotropic(
+        &self,
+        db: PhantomMemento,
+        vox_cnt: usize,
+        tiebreak: CenterTiebreak,
+        on_round: &mut dyn FnMut(&[Idx3d]),
+    ) -> Idx3d {
         debug_assert!(vox_cnt >= 1);
 
         let Some(o) = self.height_mm().partial_cmp(&self.z_mm()) else {
             unreachable!()
         };
         match o {
-            Ordering::Greater => self.center_roi_with_height_greater(db, vox_cnt),
-            Ordering::Less => self.center_roi_with_z_greater(db, vox_cnt),
-            Ordering::Equal => self.center_roi_isotropic(db, vox_cnt),
+            Ordering::Greater => {
+                self.center_roi_with_height_greater(db, vox_cnt, tiebreak, on_round)
+            }
+            Ordering::Less => self.center_roi_with_z_greater(db, vox_cnt, tiebreak, on_round),
+            Ordering::Equal => self.center_roi_isotropic(db, vox_cnt, tiebreak, on_round),
         }
     }
 
     /// 各向异性腐蚀到中心. `height > z`.
-    fn center_roi_with_height_greater(&self, mut db: PhantomMemento, mut vox_cnt: usize) -> Idx3d {
+    fn center_roi_with_height_greater(
+        &self,
+        mut db: PhantomMemento,
+        mut vox_cnt: usize,
+        tiebreak: CenterTiebreak,
+        on_round: &mut dyn FnMut(&[Idx3d]),
+    ) -> Idx3d {
         debug_assert!(self.height_mm() > self.z_mm());
 
         let (step, barrier, mut cur_step) = (self.z_mm(), self.height_mm(), 0.0);
@@ -328,8 +516,9 @@ impl CtLabel {
                 }
             }
 
+            on_round(&to_erode);
             if to_erode.len() == vox_cnt {
-                break *to_erode.iter().min().unwrap();
+                break tiebreak.pick(&to_erode);
             }
             // 本轮腐蚀
             for pos in to_erode.iter() {
@@ -343,7 +532,13 @@ impl CtLabel {
     }
 
     /// 各向异性腐蚀到中心. `z > height`.
-    fn center_roi_with_z_greater(&self, mut db: PhantomMemento, mut vox_cnt: usize) -> Idx3d {
+    fn center_roi_with_z_greater(
+        &self,
+        mut db: PhantomMemento,
+        mut vox_cnt: usize,
+        tiebreak: CenterTiebreak,
+        on_round: &mut dyn FnMut(&[Idx3d]),
+    ) -> Idx3d {
         debug_assert!(self.z_mm() > self.height_mm());
 
         let (step, barrier, mut cur_step) = (self.height_mm(), self.z_mm(), 0.0);
@@ -409,8 +604,9 @@ impl CtLabel {
                 }
             }
 
+            on_round(&to_erode);
             if to_erode.len() == vox_cnt {
-                break *to_erode.iter().min().unwrap();
+                break tiebreak.pick(&to_erode);
             }
             // 本轮腐蚀
             for pos in to_erode.iter() {
@@ -434,9 +630,8 @@ impl CtLabel {
     ///
     /// 如果指定 `anisotropic` 为 `true`,
     /// 则在三维腐蚀时会考虑体素本身的各向异性 (算法由该库提出并实现).
-    /// 否则, 按照各向同性进行腐蚀. 如果 `include_tumor` 为 `true`,
-    /// 则返回值不包含肿瘤索引. 实际上若 `include_tumor` 为 `false`,
-    /// 则该函数将 `LITS_LIVER`, `LITS_TUMOR` 完全视为等价.
+    /// 否则, 按照各向同性进行腐蚀. 返回值只包含属于 `fg` 的前景体素,
+    /// 但腐蚀时 `LITS_LIVER`, `LITS_TUMOR` 总是被视为等价.
     ///
     /// # 注意
     ///
@@ -453,12 +648,12 @@ impl CtLabel {
     /// 3. 侧面 (lateral).
     ///
     /// 每个 `Vec` 的顺序没有保证. 如果不存在前景, 则返回三个空 `Vec`.
-    pub fn peripheral_roi_3d(
+    pub fn peripheral_roi_3d_fg(
         &self,
         radius: f64,
         alpha: f64,
         anisotropic: bool,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> [Vec<Idx3d>; 3] {
         assert!(radius >= 0.0);
         assert!((0.0..=1.0).contains(&alpha));
@@ -466,23 +661,42 @@ impl CtLabel {
         let Some(center) = self.center(anisotropic) else {
             return Default::default();
         };
-        self.get_three_circle_3d(center, radius, alpha, include_tumor)
+        self.get_three_circle_3d(center, radius, alpha, fg)
+    }
+
+    /// 同 [`Self::peripheral_roi_3d_fg`]. `include_tumor` 为 `true` 时对应
+    /// [`Foreground::LiverAndTumor`], 否则对应 [`Foreground::LiverOnly`].
+    #[deprecated(note = "请使用 `peripheral_roi_3d_fg`")]
+    #[inline]
+    pub fn peripheral_roi_3d(
+        &self,
+        radius: f64,
+        alpha: f64,
+        anisotropic: bool,
+        include_tumor: bool,
+    ) -> [Vec<Idx3d>; 3] {
+        self.peripheral_roi_3d_fg(
+            radius,
+            alpha,
+            anisotropic,
+            Foreground::from_include_tumor(include_tumor),
+        )
     }
 
     /// 获取 3 个外围 3D ROI.
     ///
     /// 除了我们需要手动指定肝脏中心 `center` 之外,
-    /// 该函数功能与 [`Self::peripheral_roi_3d`] 完全相同.
+    /// 该函数功能与 [`Self::peripheral_roi_3d_fg`] 完全相同.
     ///
     /// # 注意
     ///
     /// 必须保证 `center` 不越界且其索引对应的体素值为肝脏, 否则程序 panic.
-    pub fn peripheral_roi_3d_with_center(
+    pub fn peripheral_roi_3d_with_center_fg(
         &self,
         center: Idx3d,
         radius: f64,
         alpha: f64,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> [Vec<Idx3d>; 3] {
         assert!(self
             .data()
@@ -491,7 +705,26 @@ impl CtLabel {
         assert!(radius >= 0.0);
         assert!((0.0..=1.0).contains(&alpha));
 
-        self.get_three_circle_3d(center, radius, alpha, include_tumor)
+        self.get_three_circle_3d(center, radius, alpha, fg)
+    }
+
+    /// 同 [`Self::peripheral_roi_3d_with_center_fg`]. `include_tumor` 为 `true` 时对应
+    /// [`Foreground::LiverAndTumor`], 否则对应 [`Foreground::LiverOnly`].
+    #[deprecated(note = "请使用 `peripheral_roi_3d_with_center_fg`")]
+    #[inline]
+    pub fn peripheral_roi_3d_with_center(
+        &self,
+        center: Idx3d,
+        radius: f64,
+        alpha: f64,
+        include_tumor: bool,
+    ) -> [Vec<Idx3d>; 3] {
+        self.peripheral_roi_3d_with_center_fg(
+            center,
+            radius,
+            alpha,
+            Foreground::from_include_tumor(include_tumor),
+        )
     }
 
     /// 获取 3 个外围 ROI 中心.
@@ -507,7 +740,7 @@ impl CtLabel {
         center: Idx3d,
         radius: f64,
         alpha: f64,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> [Vec<Idx3d>; 3] {
         let [d1, d2, d3] = self.get_peripheral_unit_vectors();
         let (c1, c2, c3) = (
@@ -519,9 +752,9 @@ impl CtLabel {
         // debug!("Posterior center: {c2:?}");
         // debug!("Lateral center: {c3:?}");
         [
-            self.collect_liver_circle_3d(&c1, radius, include_tumor),
-            self.collect_liver_circle_3d(&c2, radius, include_tumor),
-            self.collect_liver_circle_3d(&c3, radius, include_tumor),
+            self.collect_liver_circle_3d(&c1, radius, fg),
+            self.collect_liver_circle_3d(&c2, radius, fg),
+            self.collect_liver_circle_3d(&c3, radius, fg),
         ]
     }
 
@@ -568,13 +801,8 @@ impl CtLabel {
 
     /// 以 `center` 为中心, 收集体素体积总和为 `radius`
     /// (最接近的, 小于等于; 单位为立方毫米) 的体素索引集合.
-    fn collect_liver_circle_3d(
-        &self,
-        center: &Idx3d,
-        radius: f64,
-        include_tumor: bool,
-    ) -> Vec<Idx3d> {
-        RoiGenerator::new(self, center).extract_roi_3d(radius, include_tumor)
+    fn collect_liver_circle_3d(&self, center: &Idx3d, radius: f64, fg: Foreground) -> Vec<Idx3d> {
+        RoiGenerator::new(self, center).extract_roi_3d(radius, fg)
     }
 }
 
@@ -589,9 +817,8 @@ impl CtLabel {
     ///
     /// 如果指定 `anisotropic` 为 `true`,
     /// 则在三维腐蚀时会考虑体素本身的各向异性 (算法由该库提出并实现).
-    /// 否则, 按照各向同性进行腐蚀. 如果 `include_tumor` 为 `true`,
-    /// 则返回值不包含肿瘤索引. 实际上若 `include_tumor` 为 `false`,
-    /// 则该函数将 `LITS_LIVER`, `LITS_TUMOR` 完全视为等价.
+    /// 否则, 按照各向同性进行腐蚀. 返回值只包含属于 `fg` 的前景体素,
+    /// 但腐蚀时 `LITS_LIVER`, `LITS_TUMOR` 总是被视为等价.
     ///
     /// # 注意
     ///
@@ -609,12 +836,12 @@ impl CtLabel {
     ///
     /// 每个 `Vec` 的顺序没有保证.
     /// 如果不存在前景, 则第一个分量为 `usize::MAX`, 第二个分量为三个空 `Vec`.
-    pub fn peripheral_roi_2d(
+    pub fn peripheral_roi_2d_fg(
         &self,
         radius: f64,
         alpha: f64,
         anisotropic: bool,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> (usize, [Vec<Idx2d>; 3]) {
         assert!(radius >= 0.0);
         assert!((0.0..=1.0).contains(&alpha));
@@ -624,25 +851,44 @@ impl CtLabel {
         };
         (
             center.0,
-            self.get_three_circle_2d(center, radius, alpha, include_tumor),
+            self.get_three_circle_2d(center, radius, alpha, fg),
+        )
+    }
+
+    /// 同 [`Self::peripheral_roi_2d_fg`]. `include_tumor` 为 `true` 时对应
+    /// [`Foreground::LiverAndTumor`], 否则对应 [`Foreground::LiverOnly`].
+    #[deprecated(note = "请使用 `peripheral_roi_2d_fg`")]
+    #[inline]
+    pub fn peripheral_roi_2d(
+        &self,
+        radius: f64,
+        alpha: f64,
+        anisotropic: bool,
+        include_tumor: bool,
+    ) -> (usize, [Vec<Idx2d>; 3]) {
+        self.peripheral_roi_2d_fg(
+            radius,
+            alpha,
+            anisotropic,
+            Foreground::from_include_tumor(include_tumor),
         )
     }
 
     /// 获取 3 个外围 2D ROI.
     ///
     /// 除了我们需要手动指定肝脏中心 `center` 之外,
-    /// 该函数功能与 [`Self::peripheral_roi_2d`] 完全相同.
+    /// 该函数功能与 [`Self::peripheral_roi_2d_fg`] 完全相同.
     ///
     /// # 注意
     ///
     /// 1. 必须保证 `center` 不越界且其索引对应的体素值为肝脏, 否则程序 panic.
-    /// 2. 返回值不像 [`Self::peripheral_roi_2d`] 那样包含第一个分量, 因为它就是 `center.0`.
-    pub fn peripheral_roi_2d_with_center(
+    /// 2. 返回值不像 [`Self::peripheral_roi_2d_fg`] 那样包含第一个分量, 因为它就是 `center.0`.
+    pub fn peripheral_roi_2d_with_center_fg(
         &self,
         center: Idx3d,
         radius: f64,
         alpha: f64,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> [Vec<Idx2d>; 3] {
         assert!(self
             .data()
@@ -651,7 +897,26 @@ impl CtLabel {
         assert!(radius >= 0.0);
         assert!((0.0..=1.0).contains(&alpha));
 
-        self.get_three_circle_2d(center, radius, alpha, include_tumor)
+        self.get_three_circle_2d(center, radius, alpha, fg)
+    }
+
+    /// 同 [`Self::peripheral_roi_2d_with_center_fg`]. `include_tumor` 为 `true` 时对应
+    /// [`Foreground::LiverAndTumor`], 否则对应 [`Foreground::LiverOnly`].
+    #[deprecated(note = "请使用 `peripheral_roi_2d_with_center_fg`")]
+    #[inline]
+    pub fn peripheral_roi_2d_with_center(
+        &self,
+        center: Idx3d,
+        radius: f64,
+        alpha: f64,
+        include_tumor: bool,
+    ) -> [Vec<Idx2d>; 3] {
+        self.peripheral_roi_2d_with_center_fg(
+            center,
+            radius,
+            alpha,
+            Foreground::from_include_tumor(include_tumor),
+        )
     }
 
     /// 获取三个方向的外围 ROI, 以 2D 格式表示.
@@ -661,7 +926,7 @@ impl CtLabel {
         center: Idx3d,
         radius: f64,
         alpha: f64,
-        include_tumor: bool,
+        fg: Foreground,
     ) -> [Vec<Idx2d>; 3] {
         let [d1, d2, d3] = self.get_peripheral_unit_vectors();
 
@@ -673,21 +938,227 @@ impl CtLabel {
         );
 
         [
-            self.collect_liver_circle_2d(&c1, radius, include_tumor),
-            self.collect_liver_circle_2d(&c2, radius, include_tumor),
-            self.collect_liver_circle_2d(&c3, radius, include_tumor),
+            self.collect_liver_circle_2d(&c1, radius, fg),
+            self.collect_liver_circle_2d(&c2, radius, fg),
+            self.collect_liver_circle_2d(&c3, radius, fg),
         ]
     }
 
     /// 以 `center.0` 为水平切片索引, 以 `(center.1, center.2)` 为中心,
     /// 在该切片上收集半径不大于 `radius` (最接近的, 小于等于; 单位为平方毫米)
     /// 的所有像素索引集合.
-    fn collect_liver_circle_2d(
-        &self,
-        center: &Idx3d,
-        radius: f64,
-        include_tumor: bool,
-    ) -> Vec<Idx2d> {
-        RoiGenerator::new(self, center).extract_roi_2d(radius, include_tumor)
+    fn collect_liver_circle_2d(&self, center: &Idx3d, radius: f64, fg: Foreground) -> Vec<Idx2d> {
+        RoiGenerator::new(self, center).extract_roi_2d(radius, fg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// 中心为肿瘤的 \[w, h, z\] = \[24, 24, 6\] 长方体肝脏.
+    fn label_with_tumor() -> CtLabel {
+        let mut data = Array3::<u8>::zeros((24, 24, 6));
+        data.slice_mut(ndarray::s![2..22, 2..22, ..])
+            .fill(LITS_LIVER);
+        data.slice_mut(ndarray::s![9..15, 9..15, 1..5])
+            .fill(LITS_TUMOR);
+        CtLabel::fake(data, [1.0, 1.0, 2.0], 0, [0.0; 3])
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_foreground_matches_include_tumor() {
+        let label = label_with_tumor();
+        for (include_tumor, fg) in [
+            (true, Foreground::LiverAndTumor),
+            (false, Foreground::LiverOnly),
+        ] {
+            assert_eq!(Foreground::from_include_tumor(include_tumor), fg);
+            assert_eq!(
+                label.center_roi_3d(6.0, true, include_tumor),
+                label.center_roi_3d_fg(6.0, true, fg)
+            );
+            assert_eq!(
+                label.center_roi_2d(6.0, false, include_tumor),
+                label.center_roi_2d_fg(6.0, false, fg)
+            );
+            assert_eq!(
+                label.peripheral_roi_3d(3.0, 0.5, true, include_tumor),
+                label.peripheral_roi_3d_fg(3.0, 0.5, true, fg)
+            );
+            assert_eq!(
+                label.peripheral_roi_2d(3.0, 0.5, true, include_tumor),
+                label.peripheral_roi_2d_fg(3.0, 0.5, true, fg)
+            );
+            let center = label.center(true).unwrap();
+            assert_eq!(
+                label.peripheral_roi_3d_with_center(center, 3.0, 0.5, include_tumor),
+                label.peripheral_roi_3d_with_center_fg(center, 3.0, 0.5, fg)
+            );
+            assert_eq!(
+                label.peripheral_roi_2d_with_center(center, 3.0, 0.5, include_tumor),
+                label.peripheral_roi_2d_with_center_fg(center, 3.0, 0.5, fg)
+            );
+        }
+    }
+
+    #[test]
+    fn test_foreground_selects_pixels() {
+        let label = label_with_tumor();
+        let with_tumor = label.center_roi_3d_fg(6.0, true, Foreground::LiverAndTumor);
+        let liver_only = label.center_roi_3d_fg(6.0, true, Foreground::LiverOnly);
+        assert!(with_tumor.iter().any(|&p| is_tumor(label[p])));
+        assert!(liver_only.iter().all(|&p| is_liver(label[p])));
+        assert_eq!(
+            liver_only.len(),
+            with_tumor.iter().filter(|&&p| is_liver(label[p])).count()
+        );
+        assert_eq!(Foreground::LiverOnly.eval(LITS_TUMOR), ElemType::Background);
+        assert_eq!(
+            Foreground::LiverAndTumor.eval(LITS_TUMOR),
+            ElemType::Foreground
+        );
+    }
+
+    #[test]
+    fn test_center_tiebreak() {
+        // 一个扁平的长方体: 最后一轮腐蚀剩下沿宽方向的一排体素.
+        let mut data = Array3::<u8>::zeros((13, 7, 3));
+        data.slice_mut(ndarray::s![1..12, 1..6, 1..2])
+            .fill(LITS_LIVER);
+        let label = CtLabel::fake(data, [1.0; 3], 0, [0.0; 3]);
+
+        let min = label.center(false).unwrap();
+        assert_eq!(
+            label.center_with_tiebreak(false, CenterTiebreak::MinIndex),
+            Some(min)
+        );
+        let centroid = label
+            .center_with_tiebreak(false, CenterTiebreak::Centroid)
+            .unwrap();
+        let max = label
+            .center_with_tiebreak(false, CenterTiebreak::MaxIndex)
+            .unwrap();
+        // 形状关于宽方向中线 w = 6 对称.
+        assert_eq!(centroid, (1, 3, 6));
+        assert_ne!(centroid, min);
+        assert_eq!(min.2 + max.2, 12);
+        assert!(min < centroid && centroid < max);
+        for _ in 0..3 {
+            assert_eq!(
+                label.center_with_tiebreak(false, CenterTiebreak::Centroid),
+                Some(centroid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_erosion_depth_3d() {
+        // 半径为 8 的球, 中心为 (z, h, w) = (10, 10, 10).
+        let r = 8.0f64;
+        let data = Array3::from_shape_fn((21, 21, 21), |(w, h, z)| {
+            let d =
+                ((w as f64 - 10.0).powi(2) + (h as f64 - 10.0).powi(2) + (z as f64 - 10.0).powi(2))
+                    .sqrt();
+            if d <= r {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let label = CtLabel::fake(data, [1.0; 3], 0, [0.0; 3]);
+        for anisotropic in [false, true] {
+            let depth = label.erosion_depth_3d(anisotropic, Foreground::LiverAndTumor);
+            assert_eq!(depth.shape(), label.data().shape());
+            for (pos, &p) in label.data().indexed_iter() {
+                assert_eq!(depth[pos] == 0, !is_liver(p));
+            }
+
+            // 沿三个坐标轴从表面走向中心, 深度单调不减.
+            let c = 10;
+            for axis in 0..3 {
+                for sign in [-1isize, 1] {
+                    let mut last = 0;
+                    for k in (0..=c).rev() {
+                        let mut pos = [c; 3];
+                        pos[axis] = (c as isize + sign * k as isize) as usize;
+                        let d = depth[pos];
+                        assert!(d >= last, "{pos:?}: {d} < {last}");
+                        last = d;
+                    }
+                }
+            }
+            // 中心处深度最大, 且恰好是中心.
+            let max = *depth.iter().max().unwrap();
+            assert_eq!(depth[(c, c, c)], max);
+            assert!(max >= 8);
+            assert_eq!(label.center(anisotropic), Some((c, c, c)));
+        }
+
+        // 只把肝脏作为前景时, 肿瘤体素深度为 0, 肿瘤邻近的肝脏深度变浅.
+        let label = label_with_tumor();
+        let all = label.erosion_depth_3d(false, Foreground::LiverAndTumor);
+        let liver = label.erosion_depth_3d(false, Foreground::LiverOnly);
+        assert!(all[(3, 12, 12)] > 0);
+        assert_eq!(liver[(3, 12, 12)], 0);
+        assert_eq!(liver[(3, 12, 8)], 1);
+        assert!(all[(3, 12, 8)] > 1);
+    }
+
+    #[test]
+    fn test_boundary_voxels_are_background() {
+        // 长方体肝脏的 w = 22 一侧紧贴一层 `LITS_BOUNDARY`, 结果与该层为背景时相同.
+        let mut data = Array3::<u8>::zeros((24, 24, 24));
+        data.slice_mut(ndarray::s![2..22, 2..22, 2..22])
+            .fill(LITS_LIVER);
+        let plain = CtLabel::fake(data.clone(), [1.0; 3], 0, [0.0; 3]);
+        data.slice_mut(ndarray::s![22..23, 2..22, 2..22])
+            .fill(LITS_BOUNDARY);
+        let label = CtLabel::fake(data, [1.0; 3], 0, [0.0; 3]);
+
+        for anisotropic in [false, true] {
+            assert_eq!(label.center(anisotropic), plain.center(anisotropic));
+            for fg in [Foreground::LiverOnly, Foreground::LiverAndTumor] {
+                let depth = label.erosion_depth_3d(anisotropic, fg);
+                assert_eq!(depth, plain.erosion_depth_3d(anisotropic, fg));
+                assert_eq!(depth[(12, 12, 21)], 1);
+                assert_eq!(depth[(22, 12, 12)], 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_center_roi_sub_label() {
+        let label = label_with_tumor();
+        for fg in [Foreground::LiverOnly, Foreground::LiverAndTumor] {
+            let roi = label.center_roi_3d_fg(5.0, true, fg);
+            let (origin, sub) = label.center_roi_sub_label(5.0, true, fg).unwrap();
+            let fg_cnt = sub.data().iter().filter(|&&p| p != LITS_BACKGROUND).count();
+            assert_eq!(fg_cnt, roi.len());
+            for &(z, h, w) in roi.iter() {
+                let p = sub.data()[(z - origin.0, h - origin.1, w - origin.2)];
+                assert_eq!(p, label.data()[(z, h, w)]);
+                assert!(fg.contains(p));
+            }
+            // 包围盒是紧的: 每个维度的两端都有 ROI 体素.
+            let (z, h, w) = sub.data().dim();
+            assert_eq!(sub.header().dim[1..4], [w as u16, h as u16, z as u16]);
+            for (axis, len) in [z, h, w].into_iter().enumerate() {
+                for end in [0, len - 1] {
+                    let hit = sub
+                        .data()
+                        .indexed_iter()
+                        .any(|(p, &v)| v != LITS_BACKGROUND && [p.0, p.1, p.2][axis] == end);
+                    assert!(hit);
+                }
+            }
+        }
+
+        let empty = CtLabel::fake(Array3::zeros((4, 4, 4)), [1.0; 3], 0, [0.0; 3]);
+        assert!(empty
+            .center_roi_sub_label(5.0, true, Foreground::LiverAndTumor)
+            .is_none());
     }
 }
@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use binary_heap_plus::BinaryHeap;
 
 use super::{idx2d_to_u16, idx3d_to_u16};
-use crate::consts::gray::*;
+use crate::consts::Foreground;
 use crate::{CtLabel, Idx2d, Idx3d, Idx3dU16, NiftiHeaderAttr};
 
 #[inline]
@@ -15,15 +15,6 @@ fn idx3d_diff_pow((a, b, c): Idx3d, (x, y, z): Idx3d) -> (f64, f64, f64) {
     )
 }
 
-#[inline]
-const fn is_foreground(include_tumor: bool, pixel: u8) -> bool {
-    if include_tumor {
-        is_liver_or_tumor(pixel)
-    } else {
-        is_liver(pixel)
-    }
-}
-
 /// 实现提取 peripheral roi 所需要维护的相关数据结构.
 pub struct RoiGenerator<'a> {
     label: &'a CtLabel,
@@ -56,7 +47,7 @@ impl<'a> RoiGenerator<'a> {
 
     /// 以 `self.center` 为中心, 提取半径不大于 `radius`
     /// (单位: mm) 的球的所有前景体素索引.
-    pub fn extract_roi_3d(&self, radius: f64, include_tumor: bool) -> Vec<Idx3d> {
+    pub fn extract_roi_3d(&self, radius: f64, fg: Foreground) -> Vec<Idx3d> {
         // 堆顶距 `self.center` 最近
         let mut heap: BinaryHeap<Idx3d, _> = BinaryHeap::new_by(|a, b| {
             self.center_distance_to_squared_3d(b)
@@ -86,14 +77,14 @@ impl<'a> RoiGenerator<'a> {
                 }
             }
         }
-        ans.retain(|p| is_foreground(include_tumor, self.label[*p]));
+        ans.retain(|p| fg.contains(self.label[*p]));
         ans.shrink_to_fit();
         ans
     }
 
     /// 以 `self.center` 为中心, 提取半径不大于 `radius`
     /// (单位: mm) 的圆的所有前景体素索引.
-    pub fn extract_roi_2d(&self, radius: f64, include_tumor: bool) -> Vec<Idx2d> {
+    pub fn extract_roi_2d(&self, radius: f64, fg: Foreground) -> Vec<Idx2d> {
         let (z, h, w) = self.center;
 
         let mut heap: BinaryHeap<Idx2d, _> = BinaryHeap::new_by(|a, b| {
@@ -126,7 +117,7 @@ impl<'a> RoiGenerator<'a> {
                 }
             }
         }
-        ans.retain(|p| is_foreground(include_tumor, sli[*p]));
+        ans.retain(|p| fg.contains(sli[*p]));
         ans.shrink_to_fit();
         ans
     }
@@ -0,0 +1,107 @@
+//! 基于 HU 值的种子区域生长分割.
+
+use std::collections::VecDeque;
+
+use ndarray::Array3;
+
+use super::{CtLabel, CtScan, NiftiHeaderAttr};
+use crate::consts::gray::{LITS_BACKGROUND, LITS_LIVER};
+use crate::Idx3d;
+
+impl CtScan {
+    /// 从 `seed` 出发, 以 HU 值为依据做 6-相邻 (前后上下左右) 区域生长,
+    /// 返回一个二值标注: 区域内的体素为 `LITS_LIVER`, 其余为 `LITS_BACKGROUND`.
+    /// 在没有金标准时, 可以作为基线分割结果, 交给后续的精化流程处理.
+    ///
+    /// # 生长准则
+    ///
+    /// 区域初始仅包含种子体素. 算法按照广度优先的顺序检查区域边缘的每个邻居,
+    /// 若其 HU 值与 **检查时** 区域内所有体素的平均 HU 值之差不超过 `tolerance`,
+    /// 则将其加入区域并立即更新平均值. 由于平均值随区域增长而变化,
+    /// 结果与检查顺序有关, 但对于固定的输入总是确定的.
+    ///
+    /// 非有限的 HU 值 (如 NaN) 永远不会被加入区域. 若种子本身的 HU 值非有限,
+    /// 则返回全背景标注. 若 `seed` 越界, 则程序 panic.
+    ///
+    /// 返回标注的 header 复制自扫描, 仅把数据类型改为 `u8`.
+    pub fn region_grow(&self, seed: Idx3d, tolerance: f64) -> CtLabel {
+        assert!(self.check(&seed), "种子越界");
+        let mut data = Array3::from_elem(self.data.dim(), LITS_BACKGROUND);
+        let header = self.derived_label_header();
+
+        let seed_hu = self.data[seed];
+        if !seed_hu.is_finite() {
+            return CtLabel { header, data };
+        }
+        data[seed] = LITS_LIVER;
+        let (mut sum, mut count) = (seed_hu as f64, 1usize);
+        let mut bfs_q = VecDeque::from([seed]);
+        while let Some((z, h, w)) = bfs_q.pop_front() {
+            let neighbours = [
+                (z.wrapping_sub(1), h, w),
+                (z + 1, h, w),
+                (z, h.wrapping_sub(1), w),
+                (z, h + 1, w),
+                (z, h, w.wrapping_sub(1)),
+                (z, h, w + 1),
+            ];
+            for nb in neighbours {
+                if !self.check(&nb) || data[nb] != LITS_BACKGROUND {
+                    continue;
+                }
+                let hu = self.data[nb] as f64;
+                if hu.is_finite() && (hu - sum / count as f64).abs() <= tolerance {
+                    data[nb] = LITS_LIVER;
+                    sum += hu;
+                    count += 1;
+                    bfs_q.push_back(nb);
+                }
+            }
+        }
+        CtLabel { header, data }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_grow_blob() {
+        // [w, h, z] = [16, 16, 8], 背景 -100 HU, 球形肝脏约 60 HU (带轻微起伏).
+        let label = CtLabel::fake(Array3::zeros((16, 16, 8)), [1.0; 3], 1, [0.0; 3]);
+        let inside = |(z, h, w): Idx3d| {
+            let d2 = (z as f64 - 4.0).powi(2) + (h as f64 - 8.0).powi(2) + (w as f64 - 8.0).powi(2);
+            d2 <= 12.0
+        };
+        let data = Array3::from_shape_fn(label.data.dim(), |pos| {
+            if inside(pos) {
+                60.0 + ((pos.0 + pos.1 + pos.2) % 3) as f32 * 2.0
+            } else {
+                -100.0
+            }
+        });
+        let scan = CtScan {
+            header: label.header.clone(),
+            data,
+        };
+
+        let grown = scan.region_grow((4, 8, 8), 20.0);
+        assert_eq!(grown.shape(), scan.shape());
+        for (pos, &p) in grown.data.indexed_iter() {
+            assert_eq!(p == LITS_LIVER, inside(pos), "{pos:?}");
+        }
+
+        // 容差过小时只能得到种子附近 HU 值相同的体素, 且不会泄漏到背景.
+        let tight = scan.region_grow((4, 8, 8), 0.5);
+        let n = tight.data.iter().filter(|&&p| p == LITS_LIVER).count();
+        assert!(n >= 1 && n < grown.data.iter().filter(|&&p| p == LITS_LIVER).count());
+
+        // 种子位于背景时, 生长结果只覆盖背景.
+        let bg = scan.region_grow((0, 0, 0), 20.0);
+        assert!(bg
+            .data
+            .indexed_iter()
+            .all(|(pos, &p)| (p == LITS_LIVER) != inside(pos)));
+    }
+}
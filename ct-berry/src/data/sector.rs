@@ -11,7 +11,7 @@ use nifti::NiftiHeader;
 use num::ToPrimitive;
 use std::fmt::Formatter;
 
-use crate::Idx2d;
+use crate::{Idx2d, Idx2dF};
 
 type Idx2dI32 = (i32, i32);
 
@@ -30,6 +30,15 @@ pub struct Sector {
     arc2: f64,
 }
 
+/// 扇区的两条射线之一.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RaySide {
+    /// 起始射线 `arc1`.
+    Arc1,
+    /// 终止射线 `arc2` (从 `arc1` 逆时针到达).
+    Arc2,
+}
+
 /// 弧度转换为角度.
 fn arc_to_angle(arc: f64) -> f64 {
     arc * 180.0 * std::f64::consts::FRAC_1_PI
@@ -126,6 +135,80 @@ impl Sector {
         self.clamp(self.arc_to(p))
     }
 
+    /// 按行优先顺序迭代扇区内所有与中心距离不超过 `radius` (以像素为单位) 的像素.
+    /// 索引为负的像素被跳过; 由于扇区不负责检测图像越界, 调用者应自行过滤超出图像的像素.
+    pub fn pixels_within(&self, radius: f64) -> impl Iterator<Item = Idx2d> + '_ {
+        let r = if radius.is_finite() && radius >= 0.0 {
+            radius.floor() as i32
+        } else {
+            -1
+        };
+        let (ch, cw) = self.center;
+        let r2 = radius * radius;
+        (ch.saturating_sub(r).max(0)..=ch.saturating_add(r))
+            .flat_map(move |h| {
+                (cw.saturating_sub(r).max(0)..=cw.saturating_add(r)).map(move |w| (h, w))
+            })
+            .filter(move |&(h, w)| {
+                let (dh, dw) = ((h - ch) as f64, (w - cw) as f64);
+                dh * dh + dw * dw <= r2 && self.clamp(self.arc_to((h, w)))
+            })
+            .map(|(h, w)| (h as usize, w as usize))
+    }
+
+    /// 同 [`Self::pixels_within`], 但以物理坐标给出: `dim` 为水平切片像素分辨率 (mm),
+    /// `radius` 以毫米为单位. 每个像素附带其相对中心的 (高, 宽) 方向毫米偏移量,
+    /// 以及到中心的毫米距离, 便于在扇区内做径向 HU 值剖面.
+    pub fn pixels_within_mm(
+        &self,
+        radius: f64,
+        dim: f64,
+    ) -> impl Iterator<Item = (Idx2d, Idx2dF, f64)> + '_ {
+        assert!(dim > 0.0, "像素分辨率必须为正");
+        let (ch, cw) = self.center;
+        self.pixels_within(radius / dim).map(move |(h, w)| {
+            let dh = (h as i32 - ch) as f64 * dim;
+            let dw = (w as i32 - cw) as f64 * dim;
+            ((h, w), (dh, dw), dh.hypot(dw))
+        })
+    }
+
+    /// 光栅化扇区的一条边界射线: 从中心出发沿 `which` 射线方向 (DDA 步进,
+    /// 规范同 [`crate::LabelSlice::march_ray`]) 前进, 返回与中心距离不超过 `radius`
+    /// (以像素为单位) 的像素, 按与中心的距离升序排列, 第一个像素即为中心.
+    /// 可用于在可视化结果上绘制扇区轮廓.
+    ///
+    /// 射线在遇到负索引时终止; 由于扇区不负责检测图像越界,
+    /// 调用者应自行过滤超出图像的像素. 对于圆形扇区, 两条射线重合.
+    ///
+    /// 若 `radius` 为负数或不是有限值, 则程序 panic.
+    pub fn ray_pixels(&self, which: RaySide, radius: f64) -> Vec<Idx2d> {
+        assert!(radius.is_finite() && radius >= 0.0, "半径 `{radius}` 非法");
+        let arc = match which {
+            RaySide::Arc1 => self.arc1,
+            RaySide::Arc2 => self.arc2,
+        };
+        let (dh, dw) = (arc.cos(), arc.sin());
+        let major = dh.abs().max(dw.abs());
+        let (dh, dw) = (dh / major, dw / major);
+        let (ch, cw) = (self.height() as f64, self.width() as f64);
+
+        // 主方向每步恰好前进一个像素, 另一方向取整后的偏移量单调不减,
+        // 因此取整后像素到中心的距离单调递增, 超出 `radius` 时即可终止.
+        let mut ans = Vec::new();
+        for step in 0.. {
+            let (h, w) = (
+                (ch + dh * step as f64).round(),
+                (cw + dw * step as f64).round(),
+            );
+            if (h - ch).hypot(w - cw) > radius || h < 0.0 || w < 0.0 {
+                break;
+            }
+            ans.push((h as usize, w as usize));
+        }
+        ans
+    }
+
     /// 获取本扇区的弧度.
     pub fn arc(&self) -> f64 {
         match self.arc2 - self.arc1 {
@@ -389,7 +472,7 @@ pub enum InitLlsPatternError {
 
 #[cfg(test)]
 mod tests {
-    use super::{Idx2dI32, InitSectorError, Sector, PI_2};
+    use super::{Idx2dI32, InitSectorError, RaySide, Sector, PI_2};
     use crate::Idx2d;
     use std::f64::consts::*;
 
@@ -476,4 +559,58 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_sector_pixels_within_mm() {
+        let dim = 0.7;
+        let s = Sector::new((10, 10), FRAC_PI_6 - 1e-8, FRAC_PI_3 + 1e-8).unwrap();
+        let pixels: Vec<_> = s.pixels_within_mm(5.0 * dim, dim).collect();
+        assert!(!pixels.is_empty());
+        for &((h, w), (dh, dw), dist) in &pixels {
+            assert!(s.contains((h, w)));
+            let (ph, pw) = (h as f64 - 10.0, w as f64 - 10.0);
+            assert!(f64_eq(dh, ph * dim) && f64_eq(dw, pw * dim));
+            assert!(f64_eq(dist, dim * ph.hypot(pw)));
+            assert!(dist <= 5.0 * dim + 1e-8);
+        }
+        // 与按像素半径迭代的结果一致.
+        let idx: Vec<_> = pixels.iter().map(|p| p.0).collect();
+        assert_eq!(idx, s.pixels_within(5.0).collect::<Vec<_>>());
+        assert!(idx.contains(&(13, 12)) && !idx.contains(&(14, 14)));
+
+        // 整圆, 中心靠近图像左上角: 负索引被跳过.
+        let c = Sector::new_circle((1, 1)).unwrap();
+        let idx: Vec<_> = c.pixels_within(1.0).collect();
+        assert_eq!(idx, vec![(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)]);
+        assert_eq!(c.pixels_within(-1.0).count(), 0);
+    }
+
+    #[test]
+    fn test_ray_pixels() {
+        // 30° ~ 90° 的扇区.
+        let s = Sector::new((20, 20), FRAC_PI_6, FRAC_PI_2).unwrap();
+
+        let ray2 = s.ray_pixels(RaySide::Arc2, 5.0);
+        assert_eq!(ray2, (20..=25).map(|w| (20, w)).collect::<Vec<_>>());
+
+        let ray1 = s.ray_pixels(RaySide::Arc1, 10.0);
+        assert_eq!(ray1[0], (20, 20));
+        for (i, &(h, w)) in ray1.iter().enumerate() {
+            let (dh, dw) = (h as f64 - 20.0, w as f64 - 20.0);
+            // 主方向每步前进一个像素, 另一方向偏离理想射线不超过半个像素.
+            assert_eq!(h, 20 + i);
+            assert!((dw - dh * FRAC_PI_6.tan()).abs() <= 0.5);
+            assert!(dh.hypot(dw) <= 10.0);
+        }
+        assert_eq!(ray1.last().unwrap().0, 28);
+
+        // 第 8 步的理想位置距中心约 9.24, 但取整后的像素 (28, 25) 距中心约 9.43.
+        let ray1 = s.ray_pixels(RaySide::Arc1, 9.3);
+        assert_eq!(ray1.last(), Some(&(27, 24)));
+
+        // 射线遇到负索引时终止.
+        let s = Sector::new((2, 2), PI, 3.0 * FRAC_PI_2).unwrap();
+        assert_eq!(s.ray_pixels(RaySide::Arc1, 10.0), [(2, 2), (1, 2), (0, 2)]);
+        assert_eq!(s.ray_pixels(RaySide::Arc2, 0.0), [(2, 2)]);
+    }
 }
@@ -0,0 +1,495 @@
+//! 标注之间的体素级集合运算.
+
+use std::ops::Range;
+
+use ndarray::{s, Array3, Zip};
+
+use super::{CtLabel, NiftiHeaderAttr};
+use crate::consts::gray::{is_liver_or_tumor, DIFF_ADDED, DIFF_REMOVED, LITS_BACKGROUND};
+
+impl CtLabel {
+    /// 逐体素地对 `self` 和 `other` 中是否等于 `label` 求值 `op`,
+    /// 结果为 `true` 的体素设置为 `label`, 其余为背景. header 取自 `self`.
+    fn combine_with(&self, other: &CtLabel, label: u8, op: fn(bool, bool) -> bool) -> CtLabel {
+        assert_eq!(self.shape(), other.shape(), "两份标注形状不一致");
+        let data = Zip::from(&self.data)
+            .and(&other.data)
+            .map_collect(|&a, &b| {
+                if op(a == label, b == label) {
+                    label
+                } else {
+                    LITS_BACKGROUND
+                }
+            });
+        CtLabel {
+            header: self.header.clone(),
+            data,
+        }
+    }
+
+    /// 交集: 在 `self` 和 `other` 中都等于 `label` 的体素被设置为 `label`,
+    /// 其余体素为背景. 结果沿用 `self` 的 header.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn intersect(&self, other: &CtLabel, label: u8) -> CtLabel {
+        self.combine_with(other, label, |a, b| a && b)
+    }
+
+    /// 并集: 在 `self` 或 `other` 中等于 `label` 的体素被设置为 `label`,
+    /// 其余体素为背景. 结果沿用 `self` 的 header.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn union(&self, other: &CtLabel, label: u8) -> CtLabel {
+        self.combine_with(other, label, |a, b| a || b)
+    }
+
+    /// 差集: 在 `self` 中等于 `label` 而在 `other` 中不等于 `label` 的体素被设置为
+    /// `label`, 其余体素为背景. 结果沿用 `self` 的 header.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn difference(&self, other: &CtLabel, label: u8) -> CtLabel {
+        self.combine_with(other, label, |a, b| a && !b)
+    }
+
+    /// 差异标注: 以 `self` 为修改前, `other` 为修改后, 逐体素比较肝脏区域
+    /// (肝脏 + 肿瘤). 新增为肝脏的体素设置为 [`DIFF_ADDED`], 不再是肝脏的体素设置为
+    /// [`DIFF_REMOVED`], 未变化的体素为背景. 结果沿用 `self` 的 header.
+    ///
+    /// 两个差异值分别与 `LITS_LIVER`, `LITS_TUMOR` 相同, 因此可以直接沿用现有的
+    /// 可视化调色方案保存.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn diff_mask(&self, other: &CtLabel) -> CtLabel {
+        assert_eq!(self.shape(), other.shape(), "两份标注形状不一致");
+        let data = Zip::from(&self.data)
+            .and(&other.data)
+            .map_collect(
+                |&a, &b| match (is_liver_or_tumor(a), is_liver_or_tumor(b)) {
+                    (false, true) => DIFF_ADDED,
+                    (true, false) => DIFF_REMOVED,
+                    _ => LITS_BACKGROUND,
+                },
+            );
+        CtLabel {
+            header: self.header.clone(),
+            data,
+        }
+    }
+
+    /// 逐体素统计 `(交集体素数, 并集体素数)`.
+    fn overlap_counts(&self, other: &CtLabel, label: u8) -> (usize, usize) {
+        assert_eq!(self.shape(), other.shape(), "两份标注形状不一致");
+        let (mut inter, mut union) = (0, 0);
+        Zip::from(&self.data).and(&other.data).for_each(|&a, &b| {
+            let (a, b) = (a == label, b == label);
+            inter += (a && b) as usize;
+            union += (a || b) as usize;
+        });
+        (inter, union)
+    }
+
+    /// 两份标注的分歧体积 (立方毫米): 恰好只在其中一份标注中等于 `label`
+    /// 的体素个数 (即对称差) 乘以单个体素体积. 单个体素体积取自 `self`.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn disagreement_volume_mm3(&self, other: &CtLabel, label: u8) -> f64 {
+        let (inter, union) = self.overlap_counts(other, label);
+        (union - inter) as f64 * self.voxel()
+    }
+
+    /// 两份标注的一致率: 两者都等于 `label` 的体素数与至少一者等于 `label`
+    /// 的体素数之比 (即 Jaccard 指数), 位于 `[0, 1]` 之间.
+    /// 若两份标注中都没有 `label` 体素, 则返回 NaN.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn agreement_ratio(&self, other: &CtLabel, label: u8) -> f64 {
+        let (inter, union) = self.overlap_counts(other, label);
+        if union == 0 {
+            f64::NAN
+        } else {
+            inter as f64 / union as f64
+        }
+    }
+
+    /// 逐水平切片计算两份标注中 `label` 区域的 Dice 系数 `2|A ∩ B| / (|A| + |B|)`,
+    /// 按 z 升序返回, 用于定位两份标注分歧较大的切片.
+    /// 按照惯例, 两者都不含 `label` 的切片记为 1.0.
+    ///
+    /// 若两者形状不一致, 则程序 panic.
+    pub fn slice_dice(&self, other: &CtLabel, label: u8) -> Vec<f64> {
+        assert_eq!(self.shape(), other.shape(), "两份标注形状不一致");
+        self.slice_iter()
+            .zip(other.slice_iter())
+            .map(|(a, b)| {
+                let (mut inter, mut total) = (0usize, 0usize);
+                for (&p, &q) in a.iter().zip(b.iter()) {
+                    let (p, q) = (p == label, q == label);
+                    inter += (p && q) as usize;
+                    total += p as usize + q as usize;
+                }
+                if total == 0 {
+                    1.0
+                } else {
+                    2.0 * inter as f64 / total as f64
+                }
+            })
+            .collect()
+    }
+}
+
+impl CtLabel {
+    /// 多数投票融合 (简化版 STAPLE): 若 `labels` 中 **超过半数** 的标注在某体素处
+    /// 等于 `target`, 则结果在该处为 `target`, 否则为背景.
+    ///
+    /// 平票 (恰好半数, 仅可能在输入个数为偶数时出现) 时结果为背景,
+    /// 即只有严格多数才能得到前景. 结果沿用 `labels[0]` 的 header.
+    ///
+    /// 若 `labels` 为空, 或各输入的形状或方向信息 (`qform_code` 和 `quatern_*`)
+    /// 不一致, 则程序 panic.
+    pub fn majority_vote(labels: &[&CtLabel], target: u8) -> CtLabel {
+        let (first, rest) = labels.split_first().expect("至少需要一份标注");
+        let (h0, shape) = (first.header(), first.shape());
+        for l in rest {
+            assert_eq!(l.shape(), shape, "标注形状不一致");
+            let h = l.header();
+            assert!(
+                h.qform_code == h0.qform_code
+                    && (h.quatern_b, h.quatern_c, h.quatern_d)
+                        == (h0.quatern_b, h0.quatern_c, h0.quatern_d),
+                "标注方向不一致"
+            );
+        }
+
+        let mut votes = Array3::<usize>::zeros(first.data.raw_dim());
+        for l in labels {
+            Zip::from(&mut votes)
+                .and(&l.data)
+                .for_each(|v, &p| *v += (p == target) as usize);
+        }
+        let data = votes.mapv(|v| {
+            if 2 * v > labels.len() {
+                target
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        CtLabel {
+            header: first.header.clone(),
+            data,
+        }
+    }
+
+    /// 粗配准: 在每个轴 `±search` 的范围内穷举整数体素平移, 返回使 `moving`
+    /// 平移后与 `fixed` 的肝脏区域 (肝脏 + 肿瘤) Dice 系数最大的平移量及该 Dice 值.
+    ///
+    /// 平移量和 `search` 均按 `(z, h, w)` 顺序给出 (与 [`NiftiHeaderAttr::pix_dim`] 一致),
+    /// 含义是 `moving` 中位于 `p` 的体素被移动到 `p + shift`. 移出体积的体素视为丢失,
+    /// 但 Dice 的分母始终按完整的 `|fixed| + |moving|` 计算.
+    /// Dice 相同时取各分量绝对值之和最小的平移. 按照惯例, 两者都不含前景时返回
+    /// `([0, 0, 0], 1.0)`.
+    ///
+    /// 计算量与 `(2 * search + 1)` 各分量之积成正比, 只适用于较小的搜索范围.
+    ///
+    /// 若两者形状不一致, 或 `search` 中含有负数, 则程序 panic.
+    pub fn best_translation(
+        fixed: &CtLabel,
+        moving: &CtLabel,
+        search: [i32; 3],
+    ) -> ([i32; 3], f64) {
+        assert_eq!(fixed.shape(), moving.shape(), "两份标注形状不一致");
+        assert!(search.iter().all(|&r| r >= 0), "搜索范围不能为负");
+
+        let f = fixed.data.mapv(is_liver_or_tumor);
+        let m = moving.data.mapv(is_liver_or_tumor);
+        let total = f.iter().filter(|&&p| p).count() + m.iter().filter(|&&p| p).count();
+        if total == 0 {
+            return ([0; 3], 1.0);
+        }
+
+        let (z, h, w) = f.dim();
+        let mut best = ([0; 3], 0usize);
+        for dz in -search[0]..=search[0] {
+            for dh in -search[1]..=search[1] {
+                for dw in -search[2]..=search[2] {
+                    let (Some((fz, mz)), Some((fh, mh)), Some((fw, mw))) = (
+                        shift_ranges(dz, z),
+                        shift_ranges(dh, h),
+                        shift_ranges(dw, w),
+                    ) else {
+                        continue;
+                    };
+                    let mut inter = 0;
+                    Zip::from(f.slice(s![fz, fh, fw]))
+                        .and(m.slice(s![mz, mh, mw]))
+                        .for_each(|&a, &b| inter += (a && b) as usize);
+
+                    let shift = [dz, dh, dw];
+                    let norm = |s: [i32; 3]| s.iter().map(|v| v.abs()).sum::<i32>();
+                    if inter > best.1 || (inter == best.1 && norm(shift) < norm(best.0)) {
+                        best = (shift, inter);
+                    }
+                }
+            }
+        }
+        (best.0, 2.0 * best.1 as f64 / total as f64)
+    }
+
+    /// 将整个体积平移 `shift` 个体素, 形状和 header 保持不变.
+    ///
+    /// 平移量按 `(z, h, w)` 顺序给出, 符号约定与 [`CtLabel::best_translation`] 相同:
+    /// 原先位于 `p` 的体素被移动到 `p + shift`. 因此对 `moving` 应用
+    /// `best_translation(fixed, moving, ..)` 返回的平移即可使其与 `fixed` 对齐.
+    /// 移出体积的体素被丢弃, 空出的区域填充为 `fill`.
+    pub fn translate(&self, shift: [i32; 3], fill: u8) -> CtLabel {
+        let (z, h, w) = self.data.dim();
+        let mut data = Array3::from_elem((z, h, w), fill);
+        if let (Some((dz, sz)), Some((dh, sh)), Some((dw, sw))) = (
+            shift_ranges(shift[0], z),
+            shift_ranges(shift[1], h),
+            shift_ranges(shift[2], w),
+        ) {
+            data.slice_mut(s![dz, dh, dw])
+                .assign(&self.data.slice(s![sz, sh, sw]));
+        }
+        CtLabel {
+            header: self.header.clone(),
+            data,
+        }
+    }
+}
+
+/// 沿长度为 `len` 的轴平移 `d` 后, 目标与来源各自参与重叠的下标范围.
+/// 若完全没有重叠, 则返回 `None`.
+fn shift_ranges(d: i32, len: usize) -> Option<(Range<isize>, Range<isize>)> {
+    let (d, len) = (d as isize, len as isize);
+    let lo = d.max(0);
+    let hi = (len + d).min(len);
+    if lo >= hi {
+        None
+    } else {
+        Some((lo..hi, lo - d..hi - d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::{LITS_LIVER, LITS_TUMOR};
+
+    /// \[w, h, z\] = \[20, 20, 4\] 的标注, 以 `(cw, ch)` 为中心, 半径为 6 的圆柱为肝脏.
+    fn blob(cw: f64, ch: f64) -> CtLabel {
+        let data = Array3::from_shape_fn((20, 20, 4), |(w, h, _)| {
+            if (w as f64 - cw).hypot(h as f64 - ch) <= 6.0 {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        CtLabel::fake(data, [0.8, 0.8, 2.0], 1, [0.0, 0.0, 1.0])
+    }
+
+    #[test]
+    fn test_label_set_ops() {
+        let (a, b) = (blob(8.0, 10.0), blob(12.0, 10.0));
+        let (na, nb) = (a.count(LITS_LIVER), b.count(LITS_LIVER));
+
+        let inter = a.intersect(&b, LITS_LIVER);
+        let union = a.union(&b, LITS_LIVER);
+        let diff = a.difference(&b, LITS_LIVER);
+        for l in [&inter, &union, &diff] {
+            assert_eq!(l.header, a.header);
+            assert_eq!(l.count(LITS_LIVER) + l.count(LITS_BACKGROUND), l.size());
+        }
+
+        let ni = inter.count(LITS_LIVER);
+        assert!(0 < ni && ni < na.min(nb));
+        for ((pos, &i), (&u, &d)) in inter
+            .data
+            .indexed_iter()
+            .zip(union.data.iter().zip(diff.data.iter()))
+        {
+            let (in_a, in_b) = (a[pos] == LITS_LIVER, b[pos] == LITS_LIVER);
+            // 交集是两者的子集, 并集是两者的超集.
+            if i == LITS_LIVER {
+                assert!(in_a && in_b);
+            }
+            if in_a || in_b {
+                assert_eq!(u, LITS_LIVER);
+            }
+            assert_eq!(d == LITS_LIVER, in_a && !in_b);
+        }
+        assert_eq!(union.count(LITS_LIVER), na + nb - ni);
+        assert_eq!(diff.count(LITS_LIVER), na - ni);
+
+        // 其它标签值不参与运算.
+        assert_eq!(a.intersect(&b, LITS_TUMOR).count(LITS_TUMOR), 0);
+    }
+
+    #[test]
+    fn test_majority_vote() {
+        let (a, b, c) = (blob(6.0, 10.0), blob(10.0, 10.0), blob(14.0, 10.0));
+        let fused = CtLabel::majority_vote(&[&a, &b, &c], LITS_LIVER);
+        assert_eq!(fused.header, a.header);
+        for (pos, &f) in fused.data.indexed_iter() {
+            let n = [&a, &b, &c].iter().filter(|l| l[pos] == LITS_LIVER).count();
+            assert_eq!(f == LITS_LIVER, n >= 2, "{pos:?}: {n}");
+        }
+        // 仅被 `a` 标注的体素 (w = 0) 为背景, 被 `a`, `b` 标注的体素 (w = 7) 为前景.
+        assert_eq!(a[(0, 10, 0)], LITS_LIVER);
+        assert_eq!(fused[(0, 10, 0)], LITS_BACKGROUND);
+        assert_eq!(c[(0, 10, 7)], LITS_BACKGROUND);
+        assert_eq!(fused[(0, 10, 7)], LITS_LIVER);
+
+        // 平票时为背景.
+        let tie = CtLabel::majority_vote(&[&a, &c], LITS_LIVER);
+        assert_eq!(tie[(0, 10, 0)], LITS_BACKGROUND);
+        assert_eq!(tie.data, a.intersect(&c, LITS_LIVER).data);
+    }
+
+    #[test]
+    fn test_disagreement_volume() {
+        // 两个 4x4x2 的方块沿宽方向错开 1 个体素: 交集 3x4x2, 并集 5x4x2.
+        let cube = |w0: usize| {
+            let mut data = Array3::<u8>::zeros((10, 8, 4));
+            data.slice_mut(ndarray::s![w0..w0 + 4, 2..6, 1..3])
+                .fill(LITS_LIVER);
+            CtLabel::fake(data, [0.5, 0.5, 2.0], 1, [0.0, 0.0, 1.0])
+        };
+        let (a, b) = (cube(2), cube(3));
+        // 对称差 16 个体素, 每个体素 0.5 立方毫米.
+        assert_eq!(a.disagreement_volume_mm3(&b, LITS_LIVER), 8.0);
+        assert_eq!(b.disagreement_volume_mm3(&a, LITS_LIVER), 8.0);
+        assert_eq!(a.agreement_ratio(&b, LITS_LIVER), 24.0 / 40.0);
+
+        assert_eq!(a.disagreement_volume_mm3(&a, LITS_LIVER), 0.0);
+        assert_eq!(a.agreement_ratio(&a, LITS_LIVER), 1.0);
+        assert!(a.agreement_ratio(&b, LITS_TUMOR).is_nan());
+    }
+
+    #[test]
+    fn test_slice_dice() {
+        let a = blob(10.0, 10.0);
+        let mut b = a.clone();
+        // 破坏 z = 2 的切片: 去掉一半肝脏.
+        b.data
+            .slice_mut(ndarray::s![2, .., 10..])
+            .fill(LITS_BACKGROUND);
+
+        let dice = a.slice_dice(&b, LITS_LIVER);
+        assert_eq!(dice.len(), 4);
+        for (z, &d) in dice.iter().enumerate() {
+            if z == 2 {
+                assert!(d > 0.5 && d < 0.8, "{d}");
+            } else {
+                assert_eq!(d, 1.0);
+            }
+        }
+        // 两者都没有肿瘤时记为 1.0.
+        assert!(a.slice_dice(&b, LITS_TUMOR).iter().all(|&d| d == 1.0));
+    }
+
+    #[test]
+    fn test_diff_mask() {
+        let before = blob(8.0, 10.0);
+        let mut after = blob(12.0, 10.0);
+        // 肿瘤仍属于肝脏区域, 不算作变化.
+        after
+            .data
+            .mapv_inplace(|p| if p == LITS_LIVER { LITS_TUMOR } else { p });
+        let diff = before.diff_mask(&after);
+        assert_eq!(diff.shape(), before.shape());
+        let (mut added, mut removed) = (0, 0);
+        for ((&a, &b), &d) in before
+            .data
+            .iter()
+            .zip(after.data.iter())
+            .zip(diff.data.iter())
+        {
+            let (a, b) = (a != LITS_BACKGROUND, b != LITS_BACKGROUND);
+            match d {
+                DIFF_ADDED => {
+                    assert!(!a && b);
+                    added += 1;
+                }
+                DIFF_REMOVED => {
+                    assert!(a && !b);
+                    removed += 1;
+                }
+                _ => assert_eq!((d, a), (LITS_BACKGROUND, b)),
+            }
+        }
+        // 平移前后的圆柱面积相同.
+        assert!(added > 0);
+        assert_eq!(added, removed);
+        // 左侧只被移除, 右侧只被新增, 公共部分不变.
+        assert_eq!(diff.data[(0, 10, 3)], DIFF_REMOVED);
+        assert_eq!(diff.data[(0, 10, 17)], DIFF_ADDED);
+        assert_eq!(diff.data[(0, 10, 10)], LITS_BACKGROUND);
+        assert_eq!(
+            before
+                .diff_mask(&before)
+                .data
+                .iter()
+                .filter(|&&p| p != 0)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_best_translation() {
+        let fixed = blob(9.0, 10.0);
+        // 圆心沿 w 方向偏移 3, 沿 h 方向偏移 -2.
+        let moving = blob(12.0, 8.0);
+        let (shift, dice) = CtLabel::best_translation(&fixed, &moving, [1, 3, 4]);
+        assert_eq!(shift, [0, 2, -3]);
+        assert!((dice - 1.0).abs() < 1e-12, "{dice}");
+
+        // 搜索范围不足时只能得到次优解.
+        let (shift, dice) = CtLabel::best_translation(&fixed, &moving, [0, 1, 1]);
+        assert_eq!(shift, [0, 1, -1]);
+        assert!(dice < 1.0);
+
+        let (shift, dice) = CtLabel::best_translation(&fixed, &fixed, [2, 2, 2]);
+        assert_eq!((shift, dice), ([0; 3], 1.0));
+
+        let empty = CtLabel::fake(Array3::zeros((20, 20, 4)), [0.8, 0.8, 2.0], 1, [0.0; 3]);
+        assert_eq!(
+            CtLabel::best_translation(&empty, &empty, [1; 3]),
+            ([0; 3], 1.0)
+        );
+        assert_eq!(CtLabel::best_translation(&fixed, &empty, [1; 3]).1, 0.0);
+    }
+
+    #[test]
+    fn test_translate() {
+        let fixed = blob(9.0, 10.0);
+        let moving = blob(12.0, 8.0);
+        let (shift, _) = CtLabel::best_translation(&fixed, &moving, [1, 3, 4]);
+        let aligned = moving.translate(shift, LITS_BACKGROUND);
+        assert_eq!(aligned.shape(), moving.shape());
+        assert_eq!(aligned.header(), moving.header());
+        let dice = aligned.slice_dice(&fixed, LITS_LIVER);
+        assert!(dice.iter().all(|&d| d > 0.99), "{dice:?}");
+
+        // 平移再移回: 未被截断的区域与原先一致, 其余为填充值.
+        let fill = 7;
+        let back = moving
+            .translate([1, -3, 2], fill)
+            .translate([-1, 3, -2], fill);
+        for ((z, h, w), &p) in back.data.indexed_iter() {
+            if z < 3 && h >= 3 && w < 18 {
+                assert_eq!(p, moving.data[(z, h, w)]);
+            } else {
+                assert_eq!(p, fill);
+            }
+        }
+
+        // 平移量超出体积时全部为填充值.
+        assert!(moving
+            .translate([0, 0, 25], fill)
+            .data
+            .iter()
+            .all(|&p| p == fill));
+    }
+}
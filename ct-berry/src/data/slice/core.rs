@@ -1,11 +1,12 @@
-use super::{LabelMirror, ScanMirror};
+use super::{CompressedLabelMirror, LabelMirror, ScanMirror};
 use crate::consts::gray::*;
+use crate::consts::Connectivity;
 use crate::{Area2d, Areas2d, Idx2d, Predicate};
 use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use ndarray::iter::{Iter, IterMut};
-use ndarray::{Array2, ArrayView2, ArrayViewMut2, Ix2};
+use ndarray::{Array2, ArrayView2, ArrayViewMut2, Ix2, Zip};
 use std::borrow::Cow;
 use std::collections::{HashSet, VecDeque};
 use std::io::{Read, Write};
@@ -61,11 +62,22 @@ impl<'a> LabelSliceMut<'a> {
     /// 如果 `mirror` 大小与 `self.len()` 不符, 则程序 panic.
     pub fn resume(&mut self, mirror: &LabelMirror) {
         assert_eq!(self.size(), mirror.0.len(), "镜像大小不符");
-        for (r, w) in self.mirror().0.iter().zip(self.iter_mut()) {
+        for (r, w) in mirror.0.iter().zip(self.iter_mut()) {
             *w = *r;
         }
     }
 
+    /// 用压缩镜像 `mirror` 覆写原本 `self` 的内容. 回填前需要先解压缩,
+    /// 因此比 [`Self::resume`] 慢.
+    ///
+    /// 如果 `mirror` 大小与 `self.len()` 不符, 则程序 panic.
+    pub fn resume_compressed(&mut self, mirror: &CompressedLabelMirror) {
+        assert_eq!(self.size(), mirror.len(), "镜像大小不符");
+        for (r, w) in mirror.decompress().into_iter().zip(self.iter_mut()) {
+            *w = r;
+        }
+    }
+
     /// 将水平切片标注中值为 `old` 的像素全部替换为 `new`.
     ///
     /// 返回总共成功替换的个数.
@@ -112,6 +124,84 @@ impl<'a> LabelSliceMut<'a> {
         self.non_max_filling(bg_areas, LITS_LIVER);
         non_trivial
     }
+
+    /// 同 `fill_background_hollow`, 但按照 `conn` 规则划分背景连通区域.
+    ///
+    /// 连通规则影响空洞的判定: 若空洞仅通过对角方向与外部背景相接, 则它在 4-相邻规则下
+    /// 是一个独立的空洞 (会被填充), 在 8-相邻规则下则与外部背景连通 (不会被填充).
+    pub fn fill_holes_2d(&mut self, conn: Connectivity) -> bool {
+        let bg = self.data.map(|&p| is_background(p));
+        let bg_areas = super::morph::areas(&bg, conn);
+        let non_trivial = bg_areas.len() > 1;
+        self.non_max_filling(bg_areas, LITS_LIVER);
+        non_trivial
+    }
+
+    /// 按照 4-相邻规则获取所有满足 `pred` 的连通区域, 仅保留至少包含一个 `reference`
+    /// 像素的区域, 并将其余区域全部填充为 `fill_with`. 返回被填充的像素个数.
+    ///
+    /// 与只保留最大区域的做法相比, 该方法可以保留多个区域, 也可以保留较小的区域,
+    /// 适合去除远离参考区域 (如主肝脏) 的分割噪声. `reference` 中越界的索引被忽略.
+    pub fn keep_components_touching(
+        &mut self,
+        pred: Predicate,
+        reference: &[Idx2d],
+        fill_with: u8,
+    ) -> usize {
+        let reference: HashSet<Idx2d> = reference.iter().copied().collect();
+        let mut filled = 0;
+        for area in self.areas(pred) {
+            if !area.iter().any(|p| reference.contains(p)) {
+                filled += area.len();
+                self.fill_batch(area, fill_with);
+            }
+        }
+        filled
+    }
+
+    /// 填充前景 (肝脏和肿瘤) 之间的细小背景缝隙, 返回被填充为肝脏的像素个数.
+    ///
+    /// 算法先以 `(2 * max_gap + 1)` 边长的方形结构元对前景进行形态学闭运算
+    /// (膨胀 `max_gap` 次后腐蚀 `max_gap` 次), 宽度不超过 `2 * max_gap`
+    /// 的缝隙会被闭运算填满. 然后按照 4-相邻规则对被闭运算新增的像素分组,
+    /// 仅将面积不超过 `max_area` 的组填充为肝脏. 这样, 较大的合法缝隙
+    /// (如血管) 即使足够窄也会被保留.
+    ///
+    /// 与 `fill_background_hollow` 相比, 该方法只处理窄缝, 不会填充宽阔的空洞.
+    pub fn close_gaps(&mut self, max_gap: u32, max_area: usize) -> usize {
+        let fg = self.data.map(|&p| is_liver_or_tumor(p));
+        let r = max_gap as usize;
+        let closed = super::morph::erode_square(&super::morph::dilate_square(&fg, r), r);
+        let added = ndarray::Zip::from(&closed)
+            .and(&fg)
+            .map_collect(|&c, &f| c && !f);
+
+        let mut filled = 0;
+        for area in super::morph::areas4(&added) {
+            if area.len() <= max_area {
+                filled += area.len();
+                for pos in area {
+                    self.data[pos] = LITS_LIVER;
+                }
+            }
+        }
+        filled
+    }
+
+    /// 基于梯度的边界吸附 (简化的主动轮廓): 把前景 (肝脏和肿瘤) 边界沿法向移动到
+    /// `scan` 上梯度最强的位置, 返回被修改的像素个数.
+    ///
+    /// 对每个边界像素 (4-邻域中有背景的前景像素), 以前景指示函数的 Sobel 梯度确定外法向,
+    /// 在法向上 `±search` 像素的范围内寻找 [`ScanSlice::sobel_magnitude`] 最强的边缘,
+    /// 得到该像素的位移. 边界两侧的条带内的像素沿用最近的边界像素的位移,
+    /// 据此把背景像素填充为肝脏, 或把肝脏像素置为背景, 因此扩张时相邻法线之间不会留下空隙.
+    /// 肿瘤像素不会被修改. 所有修改都基于调用前的标注确定, 与遍历顺序无关.
+    ///
+    /// 该方法只做局部调整, 不保证结果的拓扑; 如有需要, 可以之后调用
+    /// [`Self::fill_holes_2d`] 等方法清理. 若 `self` 与 `scan` 形状不一致, 则程序 panic.
+    pub fn snap_to_edges(&mut self, scan: &ScanSlice, search: u32) -> usize {
+        super::snap::snap_to_edges(self.data.view_mut(), scan.data(), search as usize)
+    }
 }
 
 impl Index<Idx2d> for LabelSliceMut<'_> {
@@ -222,6 +312,13 @@ macro_rules! impl_label_slice_immut {
                 self.into()
             }
 
+            /// 获取以 zlib 压缩的镜像, 供以后可能的恢复.
+            /// 比 [`Self::mirror`] 节省内存, 但压缩和恢复都更慢.
+            #[inline]
+            pub fn compress_mirror(&self) -> CompressedLabelMirror {
+                self.into()
+            }
+
             /// 获得一份不可变的 **本体** shallow copy.
             #[inline]
             pub fn shallow_copy(&self) -> LabelSlice {
@@ -235,6 +332,27 @@ macro_rules! impl_label_slice_immut {
                 }
             }
 
+            /// 以 COCO 未压缩 RLE 的约定对 `target` 像素做游程编码.
+            ///
+            /// 像素按 **列优先** 顺序 (即先沿 h 方向, 再沿 w 方向) 展开, 游程交替地描述
+            /// "非 `target`" 和 "`target`" 像素的个数, 且总是以 "非 `target`" 开始
+            /// (因此首个游程可能为 0). 所有游程之和等于像素总数.
+            /// 解码见 [`OwnedLabelSlice::from_rle`].
+            pub fn to_rle(&self, target: u8) -> Vec<u32> {
+                let mut rle = Vec::new();
+                let (mut cur, mut run) = (false, 0u32);
+                for &p in self.data.t().iter() {
+                    if (p == target) != cur {
+                        rle.push(run);
+                        cur = !cur;
+                        run = 0;
+                    }
+                    run += 1;
+                }
+                rle.push(run);
+                rle
+            }
+
             /// 获得图像的高.
             #[inline]
             pub fn height(&self) -> usize {
@@ -247,6 +365,139 @@ macro_rules! impl_label_slice_immut {
                 self.shape().1
             }
 
+            /// 获取所有满足 `pred` 的像素坐标的凸包顶点 (Andrew 单调链算法).
+            ///
+            /// 顶点按照 [`crate::post_proc::signed_polygon_area`] 意义下的逆时针顺序排列,
+            /// 不重复首个顶点, 且不包含共线的中间点. 若满足条件的像素不足以构成多边形
+            /// (如少于 3 个或全部共线), 则返回去重后的凸包端点.
+            pub fn convex_hull(&self, pred: Predicate) -> Vec<Idx2d> {
+                super::hull::monotone_chain(
+                    self.data
+                        .indexed_iter()
+                        .filter_map(|(pos, &p)| pred(p).then_some(pos))
+                        .collect(),
+                )
+            }
+
+            /// 计算满足 `pred` 的区域的凸性 (solidity), 即区域面积与其凸包面积之比.
+            /// 该值位于 `(0, 1]` 之间, 越接近 1 说明形状越凸.
+            ///
+            /// 为了让完整的矩形区域凸性恰好为 1, 凸包基于每个像素的四个角点
+            /// (而非像素坐标本身) 计算. 若不存在满足 `pred` 的像素, 则返回 NaN.
+            pub fn solidity(&self, pred: Predicate) -> f64 {
+                let mut count = 0usize;
+                let mut corners = Vec::new();
+                for ((h, w), &p) in self.data.indexed_iter() {
+                    if pred(p) {
+                        count += 1;
+                        corners.extend([(h, w), (h + 1, w), (h, w + 1), (h + 1, w + 1)]);
+                    }
+                }
+                if count == 0 {
+                    return f64::NAN;
+                }
+                let hull = super::hull::monotone_chain(corners);
+                count as f64 / crate::post_proc::polygon_area(&hull)
+            }
+
+            /// 以 `markers` 为种子, 对满足 `pred` 的区域做标记分水岭分割,
+            /// 用于把相互接触的对象 (如在切片上相连的两个肝叶) 分开.
+            ///
+            /// 算法先求出区域内每个像素到区域外的欧几里得距离, 再把距离的相反数作为地形,
+            /// 从所有种子出发按照 4-邻域进行优先级泛洪: 离区域边缘越远的像素越先被淹没,
+            /// 两个种子的 "水域" 在最窄处 (腰部) 相遇.
+            ///
+            /// 每个种子为 `(位置, 标签)`. 返回值中每个像素为其所属种子的标签;
+            /// 不满足 `pred` 或不与任何种子连通的像素为 0. 标签为 0 或不满足 `pred`
+            /// 的种子被忽略. 种子越界时程序 panic.
+            pub fn watershed(&self, markers: &[(Idx2d, u8)], pred: Predicate) -> Array2<u8> {
+                let mask = self.data.mapv(pred);
+                super::watershed::flood(&mask, markers)
+            }
+
+            /// 满足 `pred` 的区域的中轴 (带半径的骨架), 可用于形状重建或血管宽度分析.
+            ///
+            /// 中轴取自距离变换的脊线: 沿水平、竖直或两条对角线中至少一个方向上,
+            /// 到区域外的欧几里得距离为严格局部极大值的像素. 图像以外视为区域外.
+            /// 每个像素附带其内切圆半径, 即像素中心到区域边界 (像素边缘) 的距离.
+            /// 以所有 `(位置, 半径)` 为圆心和半径的圆盘之并近似地重建原区域.
+            ///
+            /// 结果按行优先排列. 脊线在离散网格上可能有两个像素宽
+            /// (如宽度为偶数的条带), 如需单像素宽的骨架, 可再做细化.
+            pub fn medial_axis(&self, pred: Predicate) -> Vec<(Idx2d, f64)> {
+                super::watershed::medial_axis(&self.data.mapv(pred))
+            }
+
+            /// 满足 `pred` 的区域内的最大内切圆, 可用于自动放置有代表性的 ROI.
+            ///
+            /// 圆心为距离变换 (到区域外的欧几里得距离, 图像以外视为区域外) 的最大值所在像素,
+            /// 有多个最大值时取行优先顺序下的第一个. 半径 (单位: 像素) 的定义与
+            /// [`Self::medial_axis`] 一致, 为圆心像素中心到区域边界 (像素边缘) 的距离.
+            ///
+            /// 对于接近圆形的截面, 圆心比基于腐蚀的中心更准确. 若不存在满足 `pred`
+            /// 的像素, 则返回 `None`.
+            pub fn largest_inscribed_circle(&self, pred: Predicate) -> Option<(Idx2d, f64)> {
+                let dist = super::watershed::squared_edt(&self.data.mapv(pred));
+                let mut best: Option<(Idx2d, f64)> = None;
+                for (pos, &d) in dist.indexed_iter() {
+                    if d > best.map_or(0.0, |b| b.1) {
+                        best = Some((pos, d));
+                    }
+                }
+                best.map(|(pos, d)| (pos, d.sqrt() - 0.5))
+            }
+
+            /// 从 `start` 出发沿弧度 `arc` 方向发射一条射线 (DDA 步进),
+            /// 返回射线离开图像或遇到不满足 `pred` 的像素之前, 最后一个满足 `pred` 的像素.
+            ///
+            /// 弧度规范与 [`crate::sector::Sector`] 一致: h 增加的方向为 `0`,
+            /// w 增加的方向为 `pi / 2`. 射线每步沿主方向前进一个像素,
+            /// 另一方向的坐标四舍五入到最近的像素.
+            ///
+            /// 若 `start` 越界, 不满足 `pred`, 或 `arc` 不是有限值, 则返回 `None`.
+            pub fn march_ray(&self, start: Idx2d, arc: f64, pred: Predicate) -> Option<Idx2d> {
+                if !arc.is_finite() || !self.get(start).is_some_and(|&p| pred(p)) {
+                    return None;
+                }
+                let (dh, dw) = (arc.cos(), arc.sin());
+                let major = dh.abs().max(dw.abs());
+                let (dh, dw) = (dh / major, dw / major);
+                let (h0, w0) = (start.0 as f64, start.1 as f64);
+                let (h_len, w_len) = self.shape();
+
+                let mut last = start;
+                for step in 1.. {
+                    let h = (h0 + dh * step as f64).round();
+                    let w = (w0 + dw * step as f64).round();
+                    if h < 0.0 || w < 0.0 || h >= h_len as f64 || w >= w_len as f64 {
+                        break;
+                    }
+                    let pos = (h as usize, w as usize);
+                    if !pred(self.data[pos]) {
+                        break;
+                    }
+                    last = pos;
+                }
+                Some(last)
+            }
+
+            /// 从 `center` 出发, 在 `n_angles` 个等分的方向上 (第 `i` 个方向的弧度为
+            /// `2 * pi * i / n_angles`, 规范同 [`Self::march_ray`]) 发射射线,
+            /// 返回每个方向上最后一个满足 `pred` 的像素到 `center` 的距离 (单位: 像素).
+            ///
+            /// 该剖面刻画了切片上前景区域从中心向外的径向范围, 可用于描述截面形状.
+            /// 若 `center` 越界或不满足 `pred`, 则所有距离均为 NaN.
+            pub fn radial_profile(&self, center: Idx2d, n_angles: usize, pred: Predicate) -> Vec<f64> {
+                (0..n_angles)
+                    .map(|i| {
+                        let arc = std::f64::consts::TAU * i as f64 / n_angles as f64;
+                        self.march_ray(center, arc, pred).map_or(f64::NAN, |(h, w)| {
+                            (h as f64 - center.0 as f64).hypot(w as f64 - center.1 as f64)
+                        })
+                    })
+                    .collect()
+            }
+
             /// 判断一个索引是否位于图像的边缘.
             #[inline]
             pub fn is_at_border(&self, (h, w): Idx2d) -> bool {
@@ -258,17 +509,17 @@ macro_rules! impl_label_slice_immut {
 
             /// 获得 `pos` 的 4-邻域像素索引. 保证返回的索引都不越界.
             pub fn n4_positions(&self, pos: Idx2d) -> Vec<Idx2d> {
-                crate::eight::neighbour4(pos)
+                crate::eight::neighbour4_checked(pos, self.shape())
                     .into_iter()
-                    .filter(|p| self.check(*p))
+                    .flatten()
                     .collect()
             }
 
             /// 获得 `pos` 的 8-邻域像素索引. 保证返回的索引都不越界.
             pub fn n8_positions(&self, pos: Idx2d) -> Vec<Idx2d> {
-                crate::eight::neighbour8(pos)
+                crate::eight::neighbour8_checked(pos, self.shape())
                     .into_iter()
-                    .filter(|p| self.check(*p))
+                    .flatten()
                     .collect()
             }
 
@@ -499,8 +750,8 @@ use serde::{Deserialize, Serialize};
 
 /// 拥有所有权的二维水平 CT 标签切片.
 ///
-/// `OwnedLabelSlice` 仅提供到 `LabelSlice` 和 `LabelSliceMut`
-/// 的轻量转换和底层数据移动, 不提供任何其它方法.
+/// `OwnedLabelSlice` 主要提供到 `LabelSlice` 和 `LabelSliceMut`
+/// 的轻量转换和底层数据移动, 以及少量无需转换即可使用的掩膜操作.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub struct OwnedLabelSlice {
@@ -525,21 +776,112 @@ impl OwnedLabelSlice {
     pub fn into_raw(self) -> Array2<u8> {
         self.data
     }
+
+    /// 从形状为 `shape` 的列优先游程编码解码 (约定见 [`LabelSlice::to_rle`]).
+    /// 编码中的前景像素被设置为 `LITS_LIVER`, 其余为 `LITS_BACKGROUND`.
+    ///
+    /// 若所有游程之和不等于像素总数, 则程序 panic.
+    pub fn from_rle(rle: &[u32], (h, w): Idx2d) -> Self {
+        let total: u64 = rle.iter().map(|&r| r as u64).sum();
+        assert_eq!(total, (h * w) as u64, "游程之和与切片形状不一致");
+
+        // 按 (w, h) 形状填充后转置, 即得到列优先的结果.
+        let mut data = Array2::from_elem((w, h), LITS_BACKGROUND);
+        let buf = data.as_slice_mut().unwrap();
+        let mut start = 0;
+        for (i, &run) in rle.iter().enumerate() {
+            let end = start + run as usize;
+            if i % 2 == 1 {
+                buf[start..end].fill(LITS_LIVER);
+            }
+            start = end;
+        }
+        Self {
+            data: data.reversed_axes().as_standard_layout().into_owned(),
+        }
+    }
+
+    /// 将 `mask` 中不等于 `target` 的位置设置为 `fill`, 其余像素保持不变.
+    ///
+    /// 若 `mask` 与 `self` 形状不一致, 则程序 panic.
+    pub fn apply_mask(&mut self, mask: &LabelSlice, target: u8, fill: u8) {
+        assert_eq!(self.data.dim(), mask.shape(), "掩膜与切片形状不一致");
+        Zip::from(&mut self.data)
+            .and(mask.array_view())
+            .for_each(|p, &m| {
+                if m != target {
+                    *p = fill;
+                }
+            });
+    }
 }
 
 impl OwnedLabelSlice {
-    /// 压缩数据.
+    /// 压缩数据. 等价于 `self.compress_with(CompressionBackend::Zlib)`.
+    #[inline]
     pub fn compress(&self) -> CompactLabelSlice {
+        self.compress_with(CompressionBackend::Zlib)
+    }
+
+    /// 使用指定的压缩后端压缩数据.
+    pub fn compress_with(&self, backend: CompressionBackend) -> CompactLabelSlice {
         let data = self.as_immut();
-        let buf = data.as_row_major_slice();
-        let mut e = ZlibEncoder::new(Vec::with_capacity(8), Compression::best());
-        e.write_all(buf.as_ref()).expect("Compression error");
-        let sh = data.shape();
         CompactLabelSlice {
-            buf: e.finish().expect("Compression error"),
-            sh,
+            buf: backend.compress(&data.as_row_major_slice()),
+            sh: data.shape(),
+            backend,
+        }
+    }
+}
+
+/// `OwnedLabelSlice` 的压缩后端.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompressionBackend {
+    /// zlib, 使用最高压缩等级. 压缩率高, 但速度较慢.
+    #[default]
+    Zlib,
+
+    /// zstd, 压缩等级为 `level` (1 ~ 22, 0 代表 zstd 默认等级).
+    /// 对于稀疏的标签切片, 其速度通常远快于 zlib.
+    Zstd {
+        /// 压缩等级.
+        level: i32,
+    },
+}
+
+impl CompressionBackend {
+    /// 压缩字节流 `data`.
+    pub(super) fn compress(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            CompressionBackend::Zlib => {
+                let mut e = ZlibEncoder::new(Vec::with_capacity(8), Compression::best());
+                e.write_all(data).expect("Compression error");
+                e.finish().expect("Compression error")
+            }
+            CompressionBackend::Zstd { level } => {
+                zstd::bulk::compress(data, level).expect("Compression error")
+            }
         }
     }
+
+    /// 解压缩由 [`Self::compress`] 得到的字节流, 原字节流长度为 `len`.
+    pub(super) fn decompress(self, buf: &[u8], len: usize) -> Vec<u8> {
+        let out = match self {
+            CompressionBackend::Zlib => {
+                let mut out = Vec::with_capacity(len);
+                ZlibDecoder::new(buf)
+                    .read_to_end(&mut out)
+                    .expect("Decompression error");
+                out
+            }
+            CompressionBackend::Zstd { .. } => {
+                zstd::bulk::decompress(buf, len).expect("Decompression error")
+            }
+        };
+        debug_assert_eq!(out.len(), len);
+        out
+    }
 }
 
 /// 压缩存储的 `OwnedLabelSlice`; 不透明类型.
@@ -551,19 +893,36 @@ pub struct CompactLabelSlice {
 
     /// 形状.
     sh: Idx2d,
+
+    /// 压缩 `buf` 时使用的后端. 缺少该字段的旧数据均由 zlib 压缩.
+    #[cfg_attr(feature = "serde", serde(default))]
+    backend: CompressionBackend,
 }
 
 impl CompactLabelSlice {
     /// 解压缩数据.
     pub fn decompress(self) -> OwnedLabelSlice {
-        let Self { buf, sh: (h, w) } = self;
-        let mut d = ZlibDecoder::new(buf.as_slice());
-        let mut buf = Vec::with_capacity(h * w);
-        d.read_to_end(&mut buf).expect("Decompression error");
-        debug_assert_eq!(buf.len(), h * w);
+        let Self {
+            buf,
+            sh: (h, w),
+            backend,
+        } = self;
+        let buf = backend.decompress(&buf, h * w);
         let data = Array2::<u8>::from_shape_vec((h, w), buf).unwrap();
         OwnedLabelSlice { data }
     }
+
+    /// 获取压缩后的字节数.
+    #[inline]
+    pub fn compressed_len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// 获取压缩时使用的后端.
+    #[inline]
+    pub fn backend(&self) -> CompressionBackend {
+        self.backend
+    }
 }
 
 /// 不可变、借用的二维水平 CT 扫描切片.
@@ -616,7 +975,7 @@ impl<'a> ScanSliceMut<'a> {
     /// 如果 `mirror` 大小与 `self.len()` 不符, 则程序 panic.
     pub fn resume(&mut self, mirror: &ScanMirror) {
         assert_eq!(self.size(), mirror.0.len(), "镜像大小不符");
-        for (r, w) in self.mirror().0.iter().zip(self.iter_mut()) {
+        for (r, w) in mirror.0.iter().zip(self.iter_mut()) {
             *w = *r;
         }
     }
@@ -701,6 +1060,36 @@ macro_rules! impl_scan_slice_immut {
             pub fn indexed_iter(&self) -> impl Iterator<Item = (Idx2d, &f32)> {
                 self.data.indexed_iter()
             }
+
+            /// 对 CT HU 值进行双边滤波, 在去噪的同时保留肝脏边缘.
+            ///
+            /// `spatial_sigma` 为空间高斯核标准差 (以像素为单位), 窗口半径约为其 3 倍;
+            /// `range_sigma` 为值域高斯核标准差 (以 HU 为单位). 当 `range_sigma`
+            /// 趋于无穷时, 该滤波退化为普通高斯模糊.
+            ///
+            /// 若任一参数不为正数, 则程序 panic.
+            pub fn bilateral_filter(&self, spatial_sigma: f64, range_sigma: f64) -> OwnedScanSlice {
+                OwnedScanSlice {
+                    data: super::filter::bilateral(self.data.view(), spatial_sigma, range_sigma),
+                }
+            }
+
+            /// 以 Sobel 算子计算每个像素的梯度幅值 (未归一化), 可用于定位肝脏边缘.
+            /// 越界位置取最近的边缘值.
+            pub fn sobel_magnitude(&self) -> Array2<f32> {
+                let (gh, gw) = super::filter::sobel(self.data.view());
+                Zip::from(&gh).and(&gw).map_collect(|a, b| a.hypot(*b))
+            }
+
+            /// 以 `window * window` 的滑动窗口计算每个像素的局部 HU 均值和方差
+            /// (依次返回), 可作为肝脏纹理分析的特征.
+            ///
+            /// 越界位置取最近的边缘值. 基于积分图实现, 每个像素的计算量与窗口大小无关.
+            ///
+            /// 若 `window` 不是正奇数, 则程序 panic.
+            pub fn local_mean_variance(&self, window: usize) -> (Array2<f32>, Array2<f32>) {
+                super::filter::local_mean_variance(self.data.view(), window)
+            }
         }
     };
 }
@@ -710,8 +1099,8 @@ impl_scan_slice_immut!('a, ScanSliceMut<'a>, ArrayViewMut2<'a, f32>);
 
 /// 拥有所有权的二维水平 CT 扫描切片.
 ///
-/// `OwnedScanSlice` 仅提供到 `ScanSlice` 和 `ScanSliceMut`
-/// 的轻量转换和底层数据移动, 不提供任何其它方法.
+/// `OwnedScanSlice` 主要提供到 `ScanSlice` 和 `ScanSliceMut`
+/// 的轻量转换和底层数据移动, 以及少量无需转换即可使用的掩膜操作.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OwnedScanSlice {
@@ -736,4 +1125,569 @@ impl OwnedScanSlice {
     pub fn into_raw(self) -> Array2<f32> {
         self.data
     }
+
+    /// 将 `mask` 中不等于 `target` 的位置的 HU 值设置为 0, 其余像素保持不变.
+    ///
+    /// 若 `mask` 与 `self` 形状不一致, 则程序 panic.
+    pub fn zero_outside(&mut self, mask: &LabelSlice, target: u8) {
+        assert_eq!(self.data.dim(), mask.shape(), "掩膜与切片形状不一致");
+        Zip::from(&mut self.data)
+            .and(mask.array_view())
+            .for_each(|p, &m| {
+                if m != target {
+                    *p = 0.0;
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 512 * 512 的合成标签: 中间一个肝脏圆盘, 内含一个肿瘤圆盘.
+    fn synthetic_mask() -> OwnedLabelSlice {
+        let data = Array2::from_shape_fn((512, 512), |(h, w)| {
+            let d = |(ch, cw): (f64, f64)| f64::hypot(h as f64 - ch, w as f64 - cw);
+            if d((300.0, 200.0)) < 20.0 {
+                LITS_TUMOR
+            } else if d((256.0, 256.0)) < 150.0 {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        OwnedLabelSlice { data }
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let mask = synthetic_mask();
+        let raw_len = mask.as_immut().size();
+        for backend in [
+            CompressionBackend::Zlib,
+            CompressionBackend::Zstd { level: 0 },
+            CompressionBackend::Zstd { level: 3 },
+            CompressionBackend::Zstd { level: 19 },
+        ] {
+            let compact = mask.compress_with(backend);
+            assert_eq!(compact.backend(), backend);
+            assert!(compact.compressed_len() < raw_len / 10);
+            assert_eq!(compact.decompress().into_raw(), mask.data);
+        }
+        assert_eq!(mask.compress().backend(), CompressionBackend::Zlib);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_compact_without_backend_field() {
+        // 引入压缩后端之前序列化的数据没有 `backend` 字段, 应当按 zlib 解压.
+        let mask = synthetic_mask();
+        let mut json = serde_json::to_value(mask.compress()).unwrap();
+        json.as_object_mut().unwrap().remove("backend").unwrap();
+        let compact: CompactLabelSlice = serde_json::from_value(json).unwrap();
+        assert_eq!(compact.backend(), CompressionBackend::Zlib);
+        assert_eq!(compact.decompress().into_raw(), mask.data);
+    }
+
+    #[test]
+    fn test_resume_from_mirror() {
+        let original = synthetic_mask();
+        let mut work = original.clone();
+        let mirror = work.as_immut().mirror();
+        work.as_mutable().replace(LITS_LIVER, LITS_BACKGROUND);
+        assert_ne!(work.data, original.data);
+        work.as_mutable().resume(&mirror);
+        assert_eq!(work.data, original.data);
+
+        let mut scan = OwnedScanSlice {
+            data: Array2::from_shape_fn((16, 24), |(i, j)| (i * 24 + j) as f32 - 7.5),
+        };
+        let original = scan.clone();
+        let mirror = scan.as_immutable().mirror();
+        scan.data.fill(0.0);
+        scan.as_mutable().resume(&mirror);
+        assert_eq!(scan.data, original.data);
+    }
+
+    #[test]
+    fn test_compressed_mirror_resume() {
+        let original = synthetic_mask();
+        let mut work = original.clone();
+        let compressed = work.as_immut().compress_mirror();
+        assert_eq!(compressed.len(), original.as_immut().size());
+        assert!(compressed.compressed_len() < compressed.len() / 10);
+
+        work.as_mutable().replace(LITS_TUMOR, LITS_LIVER);
+        work.as_mutable().replace(LITS_BACKGROUND, LITS_TUMOR);
+        assert_ne!(work.data, original.data);
+        work.as_mutable().resume_compressed(&compressed);
+        assert_eq!(work.data, original.data);
+
+        // 非标准内存布局 (转置视图) 同样可以往返.
+        let mut transposed = original.data.t().to_owned();
+        let view = LabelSlice::new(original.data.t());
+        let compressed = view.compress_mirror();
+        transposed.fill(LITS_BACKGROUND);
+        let mut owned = OwnedLabelSlice { data: transposed };
+        owned.as_mutable().resume_compressed(&compressed);
+        assert_eq!(owned.data, original.data.t());
+    }
+
+    #[test]
+    fn test_medial_axis() {
+        // 高 11 宽 30 的矩形, 位于 (2, 3) 处.
+        let rect = Array2::from_shape_fn((16, 36), |(h, w)| {
+            if (2..13).contains(&h) && (3..33).contains(&w) {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let axis = LabelSlice::new(rect.view()).medial_axis(is_liver);
+        let on_axis = |pos: Idx2d| axis.iter().find(|a| a.0 == pos).map(|a| a.1);
+
+        // 中线 (h = 7) 远离两端的部分都在中轴上, 半径为短边的一半.
+        for w in 9..27 {
+            assert_eq!(on_axis((7, w)), Some(5.5), "{w}");
+        }
+        for &((h, w), r) in axis.iter() {
+            assert!(is_liver(rect[(h, w)]));
+            // 矩形内的半径即为到最近一条边的距离.
+            let edge = [h - 2, 12 - h, w - 3, 32 - w].into_iter().min().unwrap();
+            assert_eq!(r, edge as f64 + 0.5, "{h}, {w}");
+            // 其余中轴像素都位于四个角的角平分线附近 (中轴的四条分支).
+            if h != 7 {
+                let (dh, dw) = (h.min(14 - h) - 2, w.min(35 - w) - 3);
+                assert!(dh.abs_diff(dw) <= 1, "{h}, {w}");
+            }
+        }
+        // 不满足谓词时为空.
+        assert!(LabelSlice::new(rect.view())
+            .medial_axis(is_tumor)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_largest_inscribed_circle() {
+        for (ch, cw, r) in [(20.0, 20.0, 12.0), (15.0, 24.0, 7.5)] {
+            let disc = Array2::from_shape_fn((40, 45), |(h, w)| {
+                if f64::hypot(h as f64 - ch, w as f64 - cw) <= r {
+                    LITS_LIVER
+                } else {
+                    LITS_BACKGROUND
+                }
+            });
+            let slice = LabelSlice::new(disc.view());
+            let ((h, w), radius) = slice.largest_inscribed_circle(is_liver).unwrap();
+            assert!((h as f64 - ch).abs() <= 0.5 && (w as f64 - cw).abs() <= 0.5);
+            assert!((radius - r).abs() <= 1.0, "{radius} vs {r}");
+            // 内切圆中的像素都在圆盘内.
+            for ((i, j), &p) in disc.indexed_iter() {
+                if f64::hypot(i as f64 - h as f64, j as f64 - w as f64) <= radius {
+                    assert!(is_liver(p));
+                }
+            }
+            assert!(slice.largest_inscribed_circle(is_tumor).is_none());
+        }
+    }
+
+    #[test]
+    fn test_convex_hull_and_solidity() {
+        // 实心圆盘.
+        let disc = Array2::from_shape_fn((40, 40), |(h, w)| {
+            if f64::hypot(h as f64 - 20.0, w as f64 - 20.0) <= 12.0 {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let label = LabelSlice::new(disc.view());
+        let solidity = label.solidity(is_liver);
+        assert!(solidity > 0.9 && solidity <= 1.0, "{solidity}");
+        let hull = label.convex_hull(is_liver);
+        assert!(!crate::post_proc::is_clockwise_polygon(&hull));
+        assert!(hull.contains(&(8, 20)) && hull.contains(&(32, 20)));
+
+        // 完整的矩形; 凸包只保留四个角.
+        let mut rect = Array2::from_elem((10, 10), LITS_BACKGROUND);
+        rect.slice_mut(ndarray::s![2..5, 3..8]).fill(LITS_TUMOR);
+        let label = LabelSlice::new(rect.view());
+        assert_eq!(label.solidity(is_tumor), 1.0);
+        assert_eq!(
+            label.convex_hull(is_tumor),
+            vec![(2, 3), (4, 3), (4, 7), (2, 7)]
+        );
+
+        // 开口向右的 C 形.
+        let mut c_shape = Array2::from_elem((30, 30), LITS_BACKGROUND);
+        c_shape
+            .slice_mut(ndarray::s![5..25, 5..25])
+            .fill(LITS_LIVER);
+        c_shape
+            .slice_mut(ndarray::s![9..21, 9..25])
+            .fill(LITS_BACKGROUND);
+        let solidity = LabelSlice::new(c_shape.view()).solidity(is_liver);
+        // (400 - 192) / 400.
+        assert!((solidity - 0.52).abs() < 1e-9, "{solidity}");
+
+        let empty = Array2::from_elem((4, 4), LITS_BACKGROUND);
+        assert!(LabelSlice::new(empty.view())
+            .convex_hull(is_liver)
+            .is_empty());
+        assert!(LabelSlice::new(empty.view()).solidity(is_liver).is_nan());
+    }
+
+    #[test]
+    fn test_close_gaps() {
+        // 40 * 40 的肝脏, 四周留出 2 像素的背景边框.
+        let mut data = Array2::from_shape_fn((40, 40), |(h, w)| {
+            if (2..38).contains(&h) && (2..38).contains(&w) {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        // 2 像素宽, 与外部背景相连的短裂缝.
+        data.slice_mut(ndarray::s![2..8, 10..12])
+            .fill(LITS_BACKGROUND);
+        // 2 像素宽, 但很长的 "血管".
+        data.slice_mut(ndarray::s![2..32, 25..27])
+            .fill(LITS_BACKGROUND);
+        // 8 * 8 的大空洞.
+        data.slice_mut(ndarray::s![20..28, 8..16])
+            .fill(LITS_BACKGROUND);
+        let orig = data.clone();
+
+        let mut label = LabelSliceMut::new(data.view_mut());
+        let filled = label.close_gaps(1, 20);
+        assert_eq!(filled, 12);
+
+        // 裂缝被填充, 血管和大空洞被保留.
+        assert!(data
+            .slice(ndarray::s![2..8, 10..12])
+            .iter()
+            .all(|&p| p == LITS_LIVER));
+        for (pos, &p) in data.indexed_iter() {
+            if !((2..8).contains(&pos.0) && (10..12).contains(&pos.1)) {
+                assert_eq!(p, orig[pos], "{pos:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_bilateral_filter_step_edge() {
+        // 左半部分 0 HU, 右半部分 100 HU, 边缘位于第 15 / 16 列之间.
+        let data = Array2::from_shape_fn((16, 32), |(_, w)| if w < 16 { 0.0 } else { 100.0 });
+        let scan = ScanSlice::new(data.view());
+        let sharpness = |s: &OwnedScanSlice| s.data[(8, 16)] - s.data[(8, 15)];
+
+        let bilateral = scan.bilateral_filter(2.0, 10.0);
+        // 值域权重 exp(-100^2 / 200) 可以忽略, 边缘几乎不受影响.
+        assert!(sharpness(&bilateral) > 99.0);
+        // 平坦区域保持不变.
+        assert!((bilateral.data[(8, 4)] - 0.0).abs() < 1e-4);
+        assert!((bilateral.data[(8, 28)] - 100.0).abs() < 1e-4);
+
+        // `range_sigma` 为无穷时退化为高斯模糊, 边缘被明显平滑.
+        let gaussian = scan.bilateral_filter(2.0, f64::INFINITY);
+        assert!(sharpness(&gaussian) < 30.0);
+        assert!(sharpness(&bilateral) > 3.0 * sharpness(&gaussian));
+    }
+
+    #[test]
+    fn test_snap_to_edges() {
+        // 扫描左侧 (w < 20) 为 60 HU 的肝脏, 右侧为 -100 HU 的脂肪;
+        // 标注只覆盖到 w < 16, 比真实边缘偏左 4 个像素.
+        let scan = Array2::from_shape_fn((24, 40), |(_, w)| if w < 20 { 60.0 } else { -100.0 });
+        let scan = ScanSlice::new(scan.view());
+        let magnitude = scan.sobel_magnitude();
+        assert_eq!(magnitude[(5, 19)], 640.0);
+        assert_eq!(magnitude[(5, 20)], 640.0);
+        assert_eq!(magnitude[(5, 10)], 0.0);
+
+        let mut data = Array2::from_shape_fn((24, 40), |(_, w)| (w < 16) as u8);
+        let changed = LabelSliceMut::new(data.view_mut()).snap_to_edges(&scan, 5);
+        assert_eq!(changed, 24 * 4);
+        for ((_, w), &p) in data.indexed_iter() {
+            assert_eq!(p, (w < 20) as u8, "{w}");
+        }
+        // 已经吸附到边缘后不再变化; 超出搜索范围的边缘不可达.
+        assert_eq!(
+            LabelSliceMut::new(data.view_mut()).snap_to_edges(&scan, 5),
+            0
+        );
+        let mut far = Array2::from_shape_fn((24, 40), |(_, w)| (w < 8) as u8);
+        assert_eq!(
+            LabelSliceMut::new(far.view_mut()).snap_to_edges(&scan, 5),
+            0
+        );
+
+        // 圆盘: 扫描中的真实半径为 12, 标注半径分别偏小和偏大 3 个像素.
+        let disc = |r: f64| {
+            Array2::from_shape_fn((48, 48), |(h, w)| {
+                f64::hypot(h as f64 - 24.0, w as f64 - 24.0) <= r
+            })
+        };
+        let truth = disc(12.0);
+        let scan = truth.mapv(|f| if f { 60.0 } else { -100.0 });
+        let scan = ScanSlice::new(scan.view());
+        let iou = |d: &Array2<u8>| {
+            let inter = Zip::from(d)
+                .and(&truth)
+                .fold(0, |acc, &p, &t| acc + (p == LITS_LIVER && t) as usize);
+            let union = Zip::from(d)
+                .and(&truth)
+                .fold(0, |acc, &p, &t| acc + (p == LITS_LIVER || t) as usize);
+            inter as f64 / union as f64
+        };
+        for r in [9.0, 15.0] {
+            let mut data = disc(r).mapv(|f| f as u8);
+            let before = iou(&data);
+            LabelSliceMut::new(data.view_mut()).snap_to_edges(&scan, 5);
+            let after = iou(&data);
+            assert!(
+                after > 0.9 && after > before + 0.3,
+                "{r}: {before} -> {after}"
+            );
+            // 剩余的差异只是真实边缘附近一个像素以内的离散化误差.
+            for ((h, w), &p) in data.indexed_iter() {
+                if (p == LITS_LIVER) != truth[(h, w)] {
+                    let d = f64::hypot(h as f64 - 24.0, w as f64 - 24.0);
+                    assert!((d - 12.0).abs() < 1.5, "{r}: ({h}, {w})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_local_mean_variance() {
+        let data = Array2::from_shape_fn((7, 9), |(h, w)| {
+            ((h * 13 + w * 7) % 11) as f32 * 10.0 - 40.0
+        });
+        let scan = ScanSlice::new(data.view());
+        let (h, w) = scan.shape();
+        for window in [1, 3, 5, 11] {
+            let (mean, var) = scan.local_mean_variance(window);
+            let r = (window / 2) as isize;
+            for (((i, j), &m), &v) in mean.indexed_iter().zip(var.iter()) {
+                // 朴素计算, 越界时取最近的边缘值.
+                let mut vals = vec![];
+                for di in -r..=r {
+                    for dj in -r..=r {
+                        let ni = (i as isize + di).clamp(0, h as isize - 1) as usize;
+                        let nj = (j as isize + dj).clamp(0, w as isize - 1) as usize;
+                        vals.push(data[(ni, nj)] as f64);
+                    }
+                }
+                let n = vals.len() as f64;
+                let nm = vals.iter().sum::<f64>() / n;
+                let nv = vals.iter().map(|x| (x - nm).powi(2)).sum::<f64>() / n;
+                assert!((m as f64 - nm).abs() < 1e-3, "{window} {i} {j}");
+                assert!((v as f64 - nv).abs() < 1e-2, "{window} {i} {j}");
+            }
+            if window == 1 {
+                assert_eq!(mean, data);
+                assert!(var.iter().all(|&v| v == 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_watershed_figure_eight() {
+        // 两个相交的圆盘构成 "8" 字形, 腰部位于 w = 24 附近.
+        let data = Array2::from_shape_fn((40, 48), |(h, w)| {
+            let (h, w) = (h as f64, w as f64);
+            let inside = |cw: f64| (h - 20.0).hypot(w - cw) <= 10.0;
+            if inside(15.0) || inside(33.0) {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let sli = OwnedLabelSlice { data };
+        let sli = sli.as_immut();
+        // 同一个连通区域
+        assert_eq!(sli.liver_areas().len(), 1);
+
+        let split = sli.watershed(&[((20, 15), 1), ((20, 33), 2)], is_liver);
+        for (pos @ (_, w), &l) in split.indexed_iter() {
+            if !is_liver(sli[pos]) {
+                assert_eq!(l, 0);
+            } else if w < 23 {
+                assert_eq!(l, 1, "{pos:?}");
+            } else if w > 25 {
+                assert_eq!(l, 2, "{pos:?}");
+            } else {
+                assert_ne!(l, 0);
+            }
+        }
+        // 两部分面积相等 (形状关于 w = 24 对称, 腰部像素不超过一列的差异).
+        let n1 = split.iter().filter(|&&l| l == 1).count();
+        let n2 = split.iter().filter(|&&l| l == 2).count();
+        assert!(n1.abs_diff(n2) <= 11, "{n1} vs {n2}");
+
+        // 非法种子被忽略.
+        let none = sli.watershed(&[((0, 0), 1), ((20, 15), 0)], is_liver);
+        assert!(none.iter().all(|&l| l == 0));
+    }
+
+    #[test]
+    fn test_march_ray() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+        // 以 (20, 20) 为中心, 半径为 8 的圆盘.
+        let data = Array2::from_shape_fn((40, 40), |(h, w)| {
+            if (h as f64 - 20.0).hypot(w as f64 - 20.0) <= 8.0 {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let sli = OwnedLabelSlice { data };
+        let sli = sli.as_immut();
+        let c = (20, 20);
+
+        // 坐标轴方向
+        assert_eq!(sli.march_ray(c, 0.0, is_liver), Some((28, 20)));
+        assert_eq!(sli.march_ray(c, FRAC_PI_2, is_liver), Some((20, 28)));
+        assert_eq!(sli.march_ray(c, PI, is_liver), Some((12, 20)));
+        assert_eq!(sli.march_ray(c, 3.0 * FRAC_PI_2, is_liver), Some((20, 12)));
+
+        // 对角线方向: 8 / sqrt(2) ≈ 5.66, 故最远为 (25, 25).
+        assert_eq!(sli.march_ray(c, FRAC_PI_4, is_liver), Some((25, 25)));
+        assert_eq!(sli.march_ray(c, 5.0 * FRAC_PI_4, is_liver), Some((15, 15)));
+
+        // 射线离开图像时停在边缘.
+        assert_eq!(sli.march_ray(c, PI, |_| true), Some((0, 20)));
+        assert_eq!(sli.march_ray(c, FRAC_PI_4, |_| true), Some((39, 39)));
+
+        // 起点不满足条件或越界.
+        assert_eq!(sli.march_ray((0, 0), 0.0, is_liver), None);
+        assert_eq!(sli.march_ray((40, 0), 0.0, |_| true), None);
+        assert_eq!(sli.march_ray(c, f64::NAN, is_liver), None);
+    }
+
+    #[test]
+    fn test_rle() {
+        // 3 x 4 切片, 列优先展开为
+        // [0, 1, 1, | 1, 0, 0, | 0, 0, 1, | 1, 1, 1].
+        let data = ndarray::array![[0, 1, 0, 1], [1, 0, 0, 1], [1, 0, 1, 1]];
+        let sli = OwnedLabelSlice { data };
+        assert_eq!(sli.as_immut().to_rle(1), [1, 3, 4, 4]);
+        assert_eq!(sli.as_immut().to_rle(0), [0, 1, 3, 4, 4]);
+        assert_eq!(sli.as_immut().to_rle(2), [12]);
+        assert_eq!(
+            OwnedLabelSlice::from_rle(&[1, 3, 4, 4], (3, 4)).data,
+            sli.data
+        );
+
+        let mask = synthetic_mask();
+        let rle = mask.as_immut().to_rle(LITS_LIVER);
+        assert_eq!(rle.iter().sum::<u32>(), 512 * 512);
+        let decoded = OwnedLabelSlice::from_rle(&rle, (512, 512));
+        assert!(decoded.data.is_standard_layout());
+        for (pos, &p) in mask.data.indexed_iter() {
+            assert_eq!(decoded.data[pos] == LITS_LIVER, p == LITS_LIVER);
+        }
+        assert_eq!(decoded.as_immut().to_rle(LITS_LIVER), rle);
+    }
+
+    #[test]
+    fn test_owned_slice_masking() {
+        let mask = synthetic_mask();
+        let mask = mask.as_immut();
+        let (h, w) = mask.shape();
+
+        let mut label = OwnedLabelSlice {
+            data: Array2::from_shape_fn((h, w), |(i, j)| ((i + j) % 3) as u8),
+        };
+        let orig = label.clone();
+        label.apply_mask(&mask, LITS_LIVER, LITS_BOUNDARY);
+
+        let mut scan = OwnedScanSlice {
+            data: Array2::from_shape_fn((h, w), |(i, j)| (i * w + j) as f32 - 7.5),
+        };
+        let orig_scan = scan.clone();
+        scan.zero_outside(&mask, LITS_LIVER);
+
+        for (pos, &m) in mask.array_view().indexed_iter() {
+            if m == LITS_LIVER {
+                assert_eq!(label.data[pos], orig.data[pos]);
+                assert_eq!(scan.data[pos], orig_scan.data[pos]);
+            } else {
+                assert_eq!(label.data[pos], LITS_BOUNDARY);
+                assert_eq!(scan.data[pos], 0.0);
+            }
+        }
+        assert!(mask.array_view().iter().any(|&m| m == LITS_LIVER));
+        assert!(mask.array_view().iter().any(|&m| m != LITS_LIVER));
+    }
+
+    #[test]
+    fn test_radial_profile() {
+        // 以 (20, 20) 为中心, 半径为 10 的圆盘.
+        let data = Array2::from_shape_fn((40, 40), |(h, w)| {
+            if (h as f64 - 20.0).hypot(w as f64 - 20.0) <= 10.0 {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let sli = OwnedLabelSlice { data };
+        let sli = sli.as_immut();
+
+        let profile = sli.radial_profile((20, 20), 36, is_liver);
+        assert_eq!(profile.len(), 36);
+        // 坐标轴方向恰好等于半径, 其余方向因像素化误差不超过一个像素.
+        for i in [0, 9, 18, 27] {
+            assert_eq!(profile[i], 10.0);
+        }
+        assert!(
+            profile.iter().all(|d| (d - 10.0).abs() <= 1.0),
+            "{profile:?}"
+        );
+
+        assert!(sli.radial_profile((20, 20), 0, is_liver).is_empty());
+        assert!(sli
+            .radial_profile((0, 0), 4, is_liver)
+            .iter()
+            .all(|d| d.is_nan()));
+    }
+
+    #[test]
+    fn test_fill_holes_2d_connectivity() {
+        // 方环内有一个 3x3 的空洞, 环的左上角缺失, 空洞仅通过对角方向与外部相接.
+        let mut data = Array2::from_elem((9, 9), LITS_BACKGROUND);
+        data.slice_mut(ndarray::s![2..7, 2..7]).fill(LITS_LIVER);
+        data.slice_mut(ndarray::s![3..6, 3..6])
+            .fill(LITS_BACKGROUND);
+        data[(2, 2)] = LITS_BACKGROUND;
+
+        let mut four = OwnedLabelSlice { data: data.clone() };
+        assert!(four.as_mutable().fill_holes_2d(Connectivity::Four));
+        assert_eq!(four.as_immut().count(LITS_LIVER), 15 + 9);
+
+        let mut eight = OwnedLabelSlice { data: data.clone() };
+        assert!(!eight.as_mutable().fill_holes_2d(Connectivity::Eight));
+        assert_eq!(eight.data, data);
+    }
+
+    #[test]
+    fn test_keep_components_touching() {
+        let mut data = Array2::from_elem((12, 12), LITS_BACKGROUND);
+        data.slice_mut(ndarray::s![1..4, 1..4]).fill(LITS_LIVER);
+        data.slice_mut(ndarray::s![6..11, 6..10]).fill(LITS_LIVER);
+        let mut sli = OwnedLabelSlice { data };
+
+        let removed = sli.as_mutable().keep_components_touching(
+            is_liver,
+            &[(0, 0), (8, 8), (99, 99)],
+            LITS_BACKGROUND,
+        );
+        assert_eq!(removed, 9);
+        let sli = sli.as_immut();
+        assert_eq!(sli.count(LITS_LIVER), 20);
+        assert_eq!(sli[(2, 2)], LITS_BACKGROUND);
+        assert_eq!(sli[(8, 8)], LITS_LIVER);
+    }
 }
@@ -0,0 +1,130 @@
+//! 扫描切片的滤波算法.
+
+use ndarray::{Array2, ArrayView2};
+
+/// 双边滤波. 空间权重和值域权重均为高斯核, 空间窗口半径为 `ceil(3 * spatial_sigma)`.
+///
+/// 越界的邻居不参与计算 (权重按实际参与计算的邻居归一化).
+///
+/// 若 `spatial_sigma` 或 `range_sigma` 不为正数, 则程序 panic.
+pub(super) fn bilateral(
+    data: ArrayView2<f32>,
+    spatial_sigma: f64,
+    range_sigma: f64,
+) -> Array2<f32> {
+    assert!(spatial_sigma > 0.0, "spatial_sigma 必须为正数");
+    assert!(range_sigma > 0.0, "range_sigma 必须为正数");
+
+    let radius = (3.0 * spatial_sigma).ceil() as isize;
+    let (h, w) = data.dim();
+
+    // 空间权重只与偏移量有关, 预先计算.
+    let side = (2 * radius + 1) as usize;
+    let spatial = Array2::from_shape_fn((side, side), |(dh, dw)| {
+        let (dh, dw) = (dh as f64 - radius as f64, dw as f64 - radius as f64);
+        (-(dh * dh + dw * dw) / (2.0 * spatial_sigma * spatial_sigma)).exp()
+    });
+    let range_coef = -1.0 / (2.0 * range_sigma * range_sigma);
+
+    Array2::from_shape_fn((h, w), |(ch, cw)| {
+        let center = data[(ch, cw)] as f64;
+        let (mut acc, mut norm) = (0.0, 0.0);
+        for dh in -radius..=radius {
+            let Some(nh) = ch.checked_add_signed(dh).filter(|&nh| nh < h) else {
+                continue;
+            };
+            for dw in -radius..=radius {
+                let Some(nw) = cw.checked_add_signed(dw).filter(|&nw| nw < w) else {
+                    continue;
+                };
+                let v = data[(nh, nw)] as f64;
+                let diff = v - center;
+                let weight = spatial[((dh + radius) as usize, (dw + radius) as usize)]
+                    * (diff * diff * range_coef).exp();
+                acc += weight * v;
+                norm += weight;
+            }
+        }
+        // 中心像素自身的权重恒为 1, 因此 `norm > 0`.
+        (acc / norm) as f32
+    })
+}
+
+/// Sobel 算子, 依次返回沿高 (h) 和宽 (w) 方向的梯度. 越界位置取最近的边缘值.
+///
+/// 核为 `[1, 2, 1]ᵀ [-1, 0, 1]` 及其转置, 未做归一化: 单位步长的阶跃边缘两侧的梯度为
+/// `4 * 阶跃高度`.
+pub(super) fn sobel(data: ArrayView2<f32>) -> (Array2<f32>, Array2<f32>) {
+    let (h, w) = data.dim();
+    if h == 0 || w == 0 {
+        return (Array2::zeros((h, w)), Array2::zeros((h, w)));
+    }
+    let at = |i: usize, j: usize, di: isize, dj: isize| {
+        let i = (i as isize + di).clamp(0, h as isize - 1) as usize;
+        let j = (j as isize + dj).clamp(0, w as isize - 1) as usize;
+        data[(i, j)]
+    };
+    let mut gh = Array2::zeros((h, w));
+    let mut gw = Array2::zeros((h, w));
+    for i in 0..h {
+        for j in 0..w {
+            gh[(i, j)] = (at(i, j, 1, -1) + 2.0 * at(i, j, 1, 0) + at(i, j, 1, 1))
+                - (at(i, j, -1, -1) + 2.0 * at(i, j, -1, 0) + at(i, j, -1, 1));
+            gw[(i, j)] = (at(i, j, -1, 1) + 2.0 * at(i, j, 0, 1) + at(i, j, 1, 1))
+                - (at(i, j, -1, -1) + 2.0 * at(i, j, 0, -1) + at(i, j, 1, -1));
+        }
+    }
+    (gh, gw)
+}
+
+/// 以 `window * window` 的滑动窗口计算每个像素的局部均值和 (总体) 方差.
+///
+/// 越界位置取最近的边缘值 (replicate). 借助积分图 (summed-area table),
+/// 每个输出像素只需常数次查表. 积分图建立在四周各填充 `window / 2` 个像素的图像上,
+/// 因此总开销和内存约为 `(h + window) * (w + window)`. 累加在 `f64` 下进行以减小方差的舍入误差.
+///
+/// 若 `window` 不是正奇数, 则程序 panic.
+pub(super) fn local_mean_variance(
+    data: ArrayView2<f32>,
+    window: usize,
+) -> (Array2<f32>, Array2<f32>) {
+    assert!(window % 2 == 1, "窗口边长必须为正奇数");
+    let r = window / 2;
+    let (h, w) = data.dim();
+    if h == 0 || w == 0 {
+        return (Array2::zeros((h, w)), Array2::zeros((h, w)));
+    }
+
+    // 在四周各填充 `r` 个复制的边缘值, 并在最上方和最左侧额外留出一行/列 0,
+    // 使得 `sum[(i, j)]` 是填充后图像 `[0, i) x [0, j)` 范围内的和.
+    let (ph, pw) = (h + 2 * r, w + 2 * r);
+    let mut sum = Array2::<f64>::zeros((ph + 1, pw + 1));
+    let mut sq_sum = Array2::<f64>::zeros((ph + 1, pw + 1));
+    for i in 0..ph {
+        let sh = i.saturating_sub(r).min(h - 1);
+        let (mut row, mut sq_row) = (0.0, 0.0);
+        for j in 0..pw {
+            let v = data[(sh, j.saturating_sub(r).min(w - 1))] as f64;
+            row += v;
+            sq_row += v * v;
+            sum[(i + 1, j + 1)] = sum[(i, j + 1)] + row;
+            sq_sum[(i + 1, j + 1)] = sq_sum[(i, j + 1)] + sq_row;
+        }
+    }
+
+    // 原图像素 (i, j) 的窗口对应填充后图像的 [i, i + window) x [j, j + window).
+    let area = (window * window) as f64;
+    let rect = |t: &Array2<f64>, i: usize, j: usize| {
+        t[(i + window, j + window)] - t[(i, j + window)] - t[(i + window, j)] + t[(i, j)]
+    };
+    let mut mean = Array2::zeros((h, w));
+    let mut var = Array2::zeros((h, w));
+    for i in 0..h {
+        for j in 0..w {
+            let m = rect(&sum, i, j) / area;
+            mean[(i, j)] = m as f32;
+            var[(i, j)] = (rect(&sq_sum, i, j) / area - m * m).max(0.0) as f32;
+        }
+    }
+    (mean, var)
+}
@@ -0,0 +1,43 @@
+//! 二维点集的凸包.
+
+use crate::Idx2d;
+
+/// 叉积 `(a - o) x (b - o)`.
+#[inline]
+fn cross(o: Idx2d, a: Idx2d, b: Idx2d) -> i128 {
+    let (o0, o1) = (o.0 as i128, o.1 as i128);
+    (a.0 as i128 - o0) * (b.1 as i128 - o1) - (a.1 as i128 - o1) * (b.0 as i128 - o0)
+}
+
+/// 使用 Andrew 单调链算法计算 `points` 的凸包.
+///
+/// 返回的顶点按照 [`crate::post_proc::signed_polygon_area`] 意义下的逆时针顺序排列,
+/// 从字典序最小的点开始, 不重复首个顶点, 且不包含共线的中间点.
+/// 若去重后不足 3 个点, 则直接返回去重后的点.
+pub(super) fn monotone_chain(mut points: Vec<Idx2d>) -> Vec<Idx2d> {
+    points.sort_unstable();
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut hull: Vec<Idx2d> = Vec::with_capacity(2 * points.len());
+    // 下凸壳.
+    for &p in &points {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // 上凸壳.
+    let lower_len = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // 最后一个点与起点重合.
+    hull.pop();
+    hull
+}
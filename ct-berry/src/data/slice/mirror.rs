@@ -1,6 +1,6 @@
 //! 水平切片镜像. 用于提取和复原 CT 切片.
 
-use super::{LabelSlice, LabelSliceMut, ScanSlice, ScanSliceMut};
+use super::{CompressionBackend, LabelSlice, LabelSliceMut, ScanSlice, ScanSliceMut};
 
 /// 一个拥有所有权的 CT 扫描水平切片的不透明镜像.
 /// 用于临时保存一个水平标签切片的值，并在随后恢复.
@@ -38,3 +38,61 @@ impl From<&LabelSliceMut<'_>> for LabelMirror {
         Self(value.iter().copied().collect())
     }
 }
+
+/// 以 zlib 压缩存储的 CT 标签水平切片的不透明镜像.
+///
+/// 与 [`LabelMirror`] 相比, 该结构以回填速度换取内存: 标签切片通常高度稀疏,
+/// 压缩后往往只有原大小的百分之一左右, 适合同时保存大量撤销快照.
+#[derive(Clone, Debug)]
+pub struct CompressedLabelMirror {
+    /// zlib 压缩的行优先字节流.
+    buf: Vec<u8>,
+
+    /// 原切片的像素个数.
+    len: usize,
+}
+
+impl CompressedLabelMirror {
+    /// 以 zlib 压缩行优先字节流 `data`.
+    fn compress(data: &[u8]) -> Self {
+        Self {
+            buf: CompressionBackend::Zlib.compress(data),
+            len: data.len(),
+        }
+    }
+
+    /// 解压缩得到行优先字节流.
+    pub(crate) fn decompress(&self) -> Vec<u8> {
+        CompressionBackend::Zlib.decompress(&self.buf, self.len)
+    }
+
+    /// 原切片的像素个数.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 原切片是否为空.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 获取压缩后的字节数.
+    #[inline]
+    pub fn compressed_len(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl From<&LabelSlice<'_>> for CompressedLabelMirror {
+    fn from(value: &LabelSlice<'_>) -> Self {
+        Self::compress(&value.as_row_major_slice())
+    }
+}
+
+impl From<&LabelSliceMut<'_>> for CompressedLabelMirror {
+    fn from(value: &LabelSliceMut<'_>) -> Self {
+        Self::compress(&value.as_row_major_slice())
+    }
+}
@@ -1,19 +1,26 @@
 //! CT scan/label 切片对象的操作.
 
 mod core;
+mod filter;
+mod hull;
 mod iter;
 mod mirror;
+mod morph;
 mod save;
+mod snap;
+mod watershed;
 
 pub use core::{
-    CompactLabelSlice, LabelSlice, LabelSliceMut, OwnedLabelSlice, OwnedScanSlice, ScanSlice,
-    ScanSliceMut,
+    CompactLabelSlice, CompressionBackend, LabelSlice, LabelSliceMut, OwnedLabelSlice,
+    OwnedScanSlice, ScanSlice, ScanSliceMut,
 };
 
-pub use mirror::{LabelMirror, ScanMirror};
+pub use mirror::{CompressedLabelMirror, LabelMirror, ScanMirror};
 
 pub use save::{ImgWriteRaw, ImgWriteVis};
 
+pub(crate) use watershed::{signed_edt, squared_edt_3d};
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "plot")] {
         mod plot;
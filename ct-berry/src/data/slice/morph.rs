@@ -0,0 +1,70 @@
+//! 二维二值掩膜的形态学操作.
+
+use crate::consts::Connectivity;
+use crate::eight::{neighbour4, neighbour8};
+use crate::{Area2d, Areas2d, Idx2d};
+use ndarray::Array2;
+use std::collections::VecDeque;
+
+/// 以 `(2r + 1) * (2r + 1)` 的方形结构元对 `mask` 进行膨胀. 越界部分视为 `false`.
+pub(super) fn dilate_square(mask: &Array2<bool>, r: usize) -> Array2<bool> {
+    // 方形结构元是可分离的: 先沿宽方向, 再沿高方向.
+    let (h, w) = mask.dim();
+    let rows = Array2::from_shape_fn((h, w), |(i, j)| {
+        (j.saturating_sub(r)..(j + r + 1).min(w)).any(|k| mask[(i, k)])
+    });
+    Array2::from_shape_fn((h, w), |(i, j)| {
+        (i.saturating_sub(r)..(i + r + 1).min(h)).any(|k| rows[(k, j)])
+    })
+}
+
+/// 以 `(2r + 1) * (2r + 1)` 的方形结构元对 `mask` 进行腐蚀. 越界部分视为 `true`,
+/// 即图像边界本身不会侵蚀前景.
+pub(super) fn erode_square(mask: &Array2<bool>, r: usize) -> Array2<bool> {
+    let (h, w) = mask.dim();
+    let rows = Array2::from_shape_fn((h, w), |(i, j)| {
+        (j.saturating_sub(r)..(j + r + 1).min(w)).all(|k| mask[(i, k)])
+    });
+    Array2::from_shape_fn((h, w), |(i, j)| {
+        (i.saturating_sub(r)..(i + r + 1).min(h)).all(|k| rows[(k, j)])
+    })
+}
+
+/// 按照 4-相邻规则获取 `mask` 中所有为 `true` 的连通区域.
+#[inline]
+pub(super) fn areas4(mask: &Array2<bool>) -> Areas2d {
+    areas(mask, Connectivity::Four)
+}
+
+/// 按照 `conn` 规则获取 `mask` 中所有为 `true` 的连通区域.
+pub(super) fn areas(mask: &Array2<bool>, conn: Connectivity) -> Areas2d {
+    let (h, w) = mask.dim();
+    let mut visited = Array2::from_elem((h, w), false);
+    let mut ans = Areas2d::new();
+    let mut q = VecDeque::new();
+    for (pos, &m) in mask.indexed_iter() {
+        if !m || visited[pos] {
+            continue;
+        }
+        visited[pos] = true;
+        q.push_back(pos);
+        let mut area = Area2d::new();
+        while let Some(cur) = q.pop_front() {
+            area.push(cur);
+            let n4 = neighbour4(cur);
+            let n8 = neighbour8(cur);
+            let nbs: &[Idx2d] = match conn {
+                Connectivity::Four => &n4,
+                Connectivity::Eight => &n8,
+            };
+            for &nb in nbs {
+                if mask.get(nb).is_some_and(|&m| m) && !visited[nb] {
+                    visited[nb] = true;
+                    q.push_back(nb);
+                }
+            }
+        }
+        ans.push(area);
+    }
+    ans
+}
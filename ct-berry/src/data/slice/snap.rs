@@ -0,0 +1,107 @@
+//! 沿法向把标注边界吸附到扫描的强梯度处.
+
+use super::filter::sobel;
+use super::watershed::signed_edt;
+use crate::consts::gray::{is_liver_or_tumor, LITS_BACKGROUND, LITS_LIVER};
+use crate::eight::checked_offset_2d;
+use crate::Idx2d;
+use ndarray::{Array2, ArrayView2, ArrayViewMut2};
+use std::collections::VecDeque;
+
+/// 见 `LabelSliceMut::snap_to_edges`. 返回被修改的像素个数.
+pub(super) fn snap_to_edges(
+    mut label: ArrayViewMut2<u8>,
+    scan: ArrayView2<f32>,
+    search: usize,
+) -> usize {
+    assert_eq!(label.dim(), scan.dim(), "标注和扫描切片形状不一致");
+    let shape = label.dim();
+    let (h, w) = shape;
+    let fg = label.map(|&p| is_liver_or_tumor(p));
+    let (gh, gw) = sobel(scan);
+    let magnitude = Array2::from_shape_fn(shape, |pos| gh[pos].hypot(gw[pos]));
+    // 前景指示函数的梯度指向前景内部, 其反方向即为外法向.
+    let (nh, nw) = sobel(fg.mapv(|f| f as u8 as f32).view());
+
+    let sample = |(ph, pw): Idx2d, (dh, dw): (f64, f64), t: isize| {
+        let i = (ph as f64 + dh * t as f64).round();
+        let j = (pw as f64 + dw * t as f64).round();
+        (i >= 0.0 && j >= 0.0 && (i as usize) < h && (j as usize) < w)
+            .then_some((i as usize, j as usize))
+    };
+    let n4 = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+    let at_edge = |pos: Idx2d| {
+        n4.iter()
+            .any(|&(dh, dw)| checked_offset_2d(pos, dh, dw, shape).is_some_and(|n| !fg[n]))
+    };
+
+    // 1. 每个边界像素沿外法向的位移 (单位: 像素, 向外为正).
+    let search = search as isize;
+    let mut shift = Array2::<Option<isize>>::from_elem(shape, None);
+    let mut queue = VecDeque::new();
+    for (pos, _) in fg.indexed_iter().filter(|&(pos, &f)| f && at_edge(pos)) {
+        let norm = nh[pos].hypot(nw[pos]) as f64;
+        let dir = (-nh[pos] as f64 / norm, -nw[pos] as f64 / norm);
+
+        // 边界位于 `t` (最后一个前景像素) 与 `t + 1` (第一个背景像素) 之间,
+        // 以两侧梯度幅值之和衡量该位置的边缘强度. 强度相同时取移动距离最小者.
+        let mut best: (isize, f32) = (0, f32::NEG_INFINITY);
+        if norm > 0.0 {
+            for t in -search..=search {
+                let (Some(a), Some(b)) = (sample(pos, dir, t), sample(pos, dir, t + 1)) else {
+                    continue;
+                };
+                let strength = magnitude[a] + magnitude[b];
+                if strength > best.1 || (strength == best.1 && t.abs() < best.0.abs()) {
+                    best = (t, strength);
+                }
+            }
+        }
+        shift[pos] = Some(best.0);
+        queue.push_back(pos);
+    }
+
+    // 2. 以 8-邻域的多源 BFS 把位移传播到边界两侧 `search + 1` 像素宽的条带内,
+    //    使相邻法线之间的像素也能被覆盖 (如圆盘扩张时).
+    let sd = signed_edt(&fg);
+    // 像素在法向上的 "层级": 边界像素为 0, 向外依次为 1, 2, ..., 向内依次为 -1, -2, ...
+    let level = |pos: Idx2d| {
+        let d = sd[pos];
+        if d > 0.0 {
+            1.0 - d
+        } else {
+            -d
+        }
+    };
+    let band = (search + 1) as f64;
+    while let Some(pos) = queue.pop_front() {
+        for dh in -1..=1 {
+            for dw in -1..=1 {
+                let Some(n) = checked_offset_2d(pos, dh, dw, shape) else {
+                    continue;
+                };
+                if shift[n].is_none() && level(n).abs() <= band {
+                    shift[n] = shift[pos];
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+
+    // 3. 层级不超过所属边界像素的位移的像素为前景. 只新增背景像素, 只移除肝脏像素.
+    let mut changed = 0;
+    for (pos, s) in shift.indexed_iter() {
+        let Some(s) = *s else {
+            continue;
+        };
+        let inside = level(pos) <= s as f64 + 0.5;
+        let new = match (inside, label[pos]) {
+            (true, LITS_BACKGROUND) => LITS_LIVER,
+            (false, LITS_LIVER) => LITS_BACKGROUND,
+            _ => continue,
+        };
+        label[pos] = new;
+        changed += 1;
+    }
+    changed
+}
@@ -0,0 +1,269 @@
+//! 基于距离变换的标记分水岭算法.
+
+use crate::eight::checked_offset_2d;
+use ndarray::{s, Array2, Array3, Axis, Zip};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// 一维精确平方距离变换 (Felzenszwalb & Huttenlocher 下包络算法).
+///
+/// `f` 为采样函数, 结果写回 `f`. 相邻采样点的间距为 `spacing2` 的平方根.
+fn squared_edt_1d(f: &mut [f64], spacing2: f64) {
+    let n = f.len();
+    // 下包络中各抛物线的顶点位置, 以及相邻抛物线的分界点.
+    let mut v = vec![0usize; n];
+    let mut z = vec![0.0; n + 1];
+    let (mut k, orig) = (0usize, f.to_vec());
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    let intersect = |q: usize, p: usize| {
+        let (q2, p2) = ((q * q) as f64 * spacing2, (p * p) as f64 * spacing2);
+        ((orig[q] + q2) - (orig[p] + p2)) / (2.0 * spacing2 * (q as f64 - p as f64))
+    };
+    for q in 1..n {
+        let mut s = intersect(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersect(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+    k = 0;
+    for (q, out) in f.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let d = q as f64 - v[k] as f64;
+        *out = d * d * spacing2 + orig[v[k]];
+    }
+}
+
+/// 计算 `mask` 中每个 `true` 像素到最近的 `false` 像素的欧几里得距离的平方.
+/// 图像以外的区域视为 `false`, `false` 像素本身的结果为 0.
+pub(super) fn squared_edt(mask: &Array2<bool>) -> Array2<f64> {
+    squared_edt_with_border(mask, false)
+}
+
+/// 同 [`squared_edt`], 但图像以外的区域视为 `border`.
+///
+/// `border` 为 `true` 时, 若 `mask` 中不存在 `false` 像素, 则所有结果都是一个
+/// 大于图像内任意两点距离平方的有限值.
+fn squared_edt_with_border(mask: &Array2<bool>, border: bool) -> Array2<f64> {
+    let (h, w) = mask.dim();
+    // 四周补一圈边界值, 使图像边界也参与距离计算.
+    // 用一个足够大的有限值代替无穷大, 避免 `inf - inf`.
+    let big = ((h + 2) * (h + 2) + (w + 2) * (w + 2)) as f64;
+    let mut f = Array2::<f64>::from_elem((h + 2, w + 2), if border { big } else { 0.0 });
+    f.slice_mut(s![1..=h, 1..=w])
+        .zip_mut_with(mask, |d, &m| *d = if m { big } else { 0.0 });
+
+    let mut buf = Vec::with_capacity(h.max(w) + 2);
+    for mut row in f.rows_mut() {
+        buf.clear();
+        buf.extend(row.iter().copied());
+        squared_edt_1d(&mut buf, 1.0);
+        row.iter_mut().zip(&buf).for_each(|(d, &b)| *d = b);
+    }
+    for mut col in f.columns_mut() {
+        buf.clear();
+        buf.extend(col.iter().copied());
+        squared_edt_1d(&mut buf, 1.0);
+        col.iter_mut().zip(&buf).for_each(|(d, &b)| *d = b);
+    }
+    f.slice(s![1..=h, 1..=w]).to_owned()
+}
+
+/// 三维版本的 [`squared_edt`]: 计算 `mask` 中每个 `true` 体素到最近的 `false` 体素的
+/// 欧几里得距离的平方. 体积以外的区域视为 `false`.
+///
+/// `spacing` 是三个方向上相邻体素的物理间距 (如毫米), 顺序与 `mask` 的维度一致.
+pub(crate) fn squared_edt_3d(mask: &Array3<bool>, spacing: [f64; 3]) -> Array3<f64> {
+    let (z, h, w) = mask.dim();
+    let big = [z, h, w]
+        .iter()
+        .zip(spacing)
+        .map(|(&n, s)| ((n + 2) as f64 * s).powi(2))
+        .sum::<f64>();
+    let mut f = Array3::<f64>::zeros((z + 2, h + 2, w + 2));
+    f.slice_mut(s![1..=z, 1..=h, 1..=w])
+        .zip_mut_with(mask, |d, &m| *d = if m { big } else { 0.0 });
+
+    let mut buf = Vec::with_capacity(z.max(h).max(w) + 2);
+    for (axis, s) in spacing.into_iter().enumerate() {
+        for mut lane in f.lanes_mut(Axis(axis)) {
+            buf.clear();
+            buf.extend(lane.iter().copied());
+            squared_edt_1d(&mut buf, s * s);
+            lane.iter_mut().zip(&buf).for_each(|(d, &b)| *d = b);
+        }
+    }
+    f.slice(s![1..=z, 1..=h, 1..=w]).to_owned()
+}
+
+/// 有符号欧几里得距离: `mask` 中的 `true` 像素取到最近的 `false` 像素的距离 (正值),
+/// `false` 像素取到最近的 `true` 像素的距离的相反数 (负值). 两者的绝对值都至少为 1.
+///
+/// 与 [`squared_edt`] 不同, 图像以外的区域不参与计算, 因此触及图像边界的前景不会
+/// 被边界 "截断". 若 `mask` 全为 `true` 或全为 `false`, 则结果的绝对值为一个
+/// 大于图像对角线长度的有限值.
+pub(crate) fn signed_edt(mask: &Array2<bool>) -> Array2<f64> {
+    let inside = squared_edt_with_border(mask, true);
+    let outside = squared_edt_with_border(&mask.mapv(|m| !m), true);
+    Zip::from(&inside)
+        .and(&outside)
+        .map_collect(|&i, &o| i.sqrt() - o.sqrt())
+}
+
+/// 以 `markers` 为种子, 在 `mask` 上按照距离变换从高到低 (即把距离变换取负作为地形)
+/// 进行 4-邻域的优先级泛洪, 返回标记结果. 未被任何种子到达的像素以及
+/// `mask` 以外的像素为 0.
+///
+/// 标签为 0 或位于 `mask` 以外的种子被忽略. 同一高度的像素按照先进先出的顺序处理,
+/// 因此结果是确定的.
+pub(super) fn flood(mask: &Array2<bool>, markers: &[((usize, usize), u8)]) -> Array2<u8> {
+    let (h, w) = mask.dim();
+    // 平方距离都是整数, 可以精确地转换为整数比较.
+    let dist = squared_edt(mask).mapv(|d| d as u64);
+    let mut out = Array2::<u8>::zeros((h, w));
+
+    // (距离, 入队顺序, 位置, 标签). 距离大者优先, 同距离先入队者优先.
+    let mut heap = BinaryHeap::new();
+    let mut order = 0u64;
+    for &(pos, label) in markers {
+        if label != 0 && mask[pos] {
+            heap.push((dist[pos], Reverse(order), pos, label));
+            order += 1;
+        }
+    }
+
+    while let Some((_, _, pos @ (i, j), label)) = heap.pop() {
+        if out[pos] != 0 {
+            continue;
+        }
+        out[pos] = label;
+        let neighbours = [
+            (i.wrapping_sub(1), j),
+            (i + 1, j),
+            (i, j.wrapping_sub(1)),
+            (i, j + 1),
+        ];
+        for n in neighbours {
+            if n.0 < h && n.1 < w && mask[n] && out[n] == 0 {
+                heap.push((dist[n], Reverse(order), n, label));
+                order += 1;
+            }
+        }
+    }
+    out
+}
+
+/// 距离变换的脊线 (离散中轴): `mask` 中沿水平、竖直或两条对角线中至少一个方向
+/// 为距离变换严格局部极大值 (不小于两侧, 且大于其中一侧) 的像素.
+/// 图像以外的区域视为 `false`.
+///
+/// 返回 `(位置, 半径)`, 按行优先排列. 半径为像素中心到区域边界 (像素边缘) 的距离,
+/// 即到最近的 `false` 像素中心的距离减去 0.5.
+pub(super) fn medial_axis(mask: &Array2<bool>) -> Vec<((usize, usize), f64)> {
+    let shape = mask.dim();
+    // 平方距离都是整数, 可以精确地比较.
+    let dist = squared_edt(mask).mapv(|d| d as u64);
+    let at = |pos, dh, dw| checked_offset_2d(pos, dh, dw, shape).map_or(0, |n| dist[n]);
+
+    dist.indexed_iter()
+        .filter(|&(pos, &d)| {
+            d > 0
+                && [(0, 1), (1, 0), (1, 1), (1, -1)].iter().any(|&(dh, dw)| {
+                    let (a, b) = (at(pos, dh, dw), at(pos, -dh, -dw));
+                    d >= a && d >= b && (d > a || d > b)
+                })
+        })
+        .map(|(pos, &d)| (pos, (d as f64).sqrt() - 0.5))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squared_edt_brute_force() {
+        let mask = Array2::from_shape_fn((9, 13), |(i, j)| (i * 7 + j * 3) % 11 != 0);
+        let edt = squared_edt(&mask);
+        for ((i, j), &d) in edt.indexed_iter() {
+            let (i, j) = (i as i64 + 1, j as i64 + 1);
+            // 在补过边的坐标系中暴力求解.
+            let brute = (0..11i64)
+                .flat_map(|a| (0..15i64).map(move |b| (a, b)))
+                .filter(|&(a, b)| {
+                    a == 0
+                        || b == 0
+                        || a == 10
+                        || b == 14
+                        || !mask[((a - 1) as usize, (b - 1) as usize)]
+                })
+                .map(|(a, b)| (a - i).pow(2) + (b - j).pow(2))
+                .min()
+                .unwrap();
+            assert_eq!(d, brute as f64);
+        }
+    }
+
+    #[test]
+    fn test_signed_edt_brute_force() {
+        let mask = Array2::from_shape_fn((9, 13), |(i, j)| {
+            (i as i64 - 3).pow(2) + (j as i64 - 9).pow(2) < 12
+        });
+        let sd = signed_edt(&mask);
+        for ((i, j), &d) in sd.indexed_iter() {
+            let brute = mask
+                .indexed_iter()
+                .filter(|&(_, &m)| m != mask[(i, j)])
+                .map(|((a, b), _)| {
+                    ((a as f64 - i as f64).powi(2) + (b as f64 - j as f64).powi(2)).sqrt()
+                })
+                .fold(f64::INFINITY, f64::min);
+            let expected = if mask[(i, j)] { brute } else { -brute };
+            assert!((d - expected).abs() < 1e-9, "{i} {j}: {d} != {expected}");
+        }
+
+        let full = signed_edt(&Array2::from_elem((4, 5), true));
+        assert!(full.iter().all(|&d| d > 6.0));
+    }
+
+    #[test]
+    fn test_squared_edt_3d_brute_force() {
+        let mask = Array3::from_shape_fn((5, 6, 7), |(i, j, k)| (i * 5 + j * 3 + k * 2) % 7 != 0);
+        let spacing = [2.5, 0.7, 0.8];
+        let edt = squared_edt_3d(&mask, spacing);
+        let (z, h, w) = mask.dim();
+        for ((i, j, k), &d) in edt.indexed_iter() {
+            // 在补过边的坐标系中暴力求解.
+            let mut brute = f64::INFINITY;
+            for a in 0..z + 2 {
+                for b in 0..h + 2 {
+                    for c in 0..w + 2 {
+                        let inside =
+                            (1..=z).contains(&a) && (1..=h).contains(&b) && (1..=w).contains(&c);
+                        if inside && mask[(a - 1, b - 1, c - 1)] {
+                            continue;
+                        }
+                        let dist = [
+                            a as f64 - i as f64 - 1.0,
+                            b as f64 - j as f64 - 1.0,
+                            c as f64 - k as f64 - 1.0,
+                        ]
+                        .iter()
+                        .zip(spacing)
+                        .map(|(d, s)| (d * s).powi(2))
+                        .sum::<f64>();
+                        brute = brute.min(dist);
+                    }
+                }
+            }
+            assert!((d - brute).abs() < 1e-9, "{i} {j} {k}: {d} != {brute}");
+        }
+    }
+}
@@ -1,12 +1,35 @@
+use crate::ScanSlice;
+use image::Rgb;
+use ndarray::Array2;
+use std::hash::{Hash, Hasher};
+
 /// CT 窗口, 包含窗位 (window level) 和窗宽 (window width).
 ///
 /// 该窗口是只读的. 若要修改窗口参数, 你应该创建新的实例.
-#[derive(Copy, Clone, Debug)]
+///
+/// 窗口参数总是有限值, 因此 `CtWindow` 实现了 [`Eq`] 和 [`Hash`],
+/// 可以作为 (例如查找表的) 缓存键.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct CtWindow {
     level: f32,
     width: f32,
 }
 
+/// 构造函数保证了窗位和窗宽均为有限值 (不存在 NaN), 因此相等关系是自反的.
+impl Eq for CtWindow {}
+
+/// `Hash` 实现的量化精度: 参数先乘以该值再取整.
+const HASH_SCALE: f32 = 1e3;
+
+impl Hash for CtWindow {
+    /// 对窗位和窗宽量化 (精确到 0.001) 后再哈希. 相等的窗口量化结果一定相同,
+    /// 因此与 `PartialEq` 的语义一致.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ((self.level * HASH_SCALE).round() as i64).hash(state);
+        ((self.width * HASH_SCALE).round() as i64).hash(state);
+    }
+}
+
 impl CtWindow {
     /// 构建 CT 窗.
     ///
@@ -29,6 +52,21 @@ impl CtWindow {
         }
     }
 
+    /// 根据目标组织的平均 HU 值 `mean_hu` 和期望的对比度 `contrast`
+    /// 构建以该组织为中心的 CT 窗, 适用于没有标准窗口的组织.
+    ///
+    /// 窗位为 `mean_hu`, 窗宽为 `max(|mean_hu|, 1) / contrast`, 即对比度越高,
+    /// 窗口越窄. 例如肝脏 (60 HU) 取对比度 0.3 时, 得到与
+    /// [`Self::from_liver_visual`] 相同的窗口.
+    ///
+    /// 若 `contrast` 不是正的有限数, 或得到的窗口不合法 (见 [`Self::new`]), 则返回 `None`.
+    pub fn for_structure(mean_hu: f32, contrast: f32) -> Option<CtWindow> {
+        if !(contrast.is_finite() && contrast > 0.0) {
+            return None;
+        }
+        Self::new(mean_hu, mean_hu.abs().max(1.0) / contrast)
+    }
+
     /// 窗下限.
     #[inline]
     pub fn lower_bound(&self) -> f32 {
@@ -53,6 +91,12 @@ impl CtWindow {
         self.width
     }
 
+    /// 判断两个窗口的窗位和窗宽之差是否都不超过 `eps`.
+    #[inline]
+    pub fn approx_eq(&self, other: &CtWindow, eps: f32) -> bool {
+        (self.level - other.level).abs() <= eps && (self.width - other.width).abs() <= eps
+    }
+
     /// 求在当前 CT 窗设置下, `ct` HU 值对应的灰度图像素整数值 (0 <= value <= 255)
     ///
     /// 如果 `ct` 无意义 (如 inf, NaN), 则返回 `None`.
@@ -88,16 +132,105 @@ impl CtWindow {
             Some((ct - lb) / self.width() * 255.0)
         }
     }
+
+    /// 为闭区间 `[hu_min, hu_max]` 内的每个整数 HU 值预先计算窗口灰度值,
+    /// 结果按 HU 值升序排列. 对于以整数 (如 `i16`) 存储 HU 值的扫描,
+    /// 调用者可以直接使用 `lut[(hu - hu_min) as usize]` 查表,
+    /// 以代替对每个体素重复调用 `eval`.
+    ///
+    /// 查找表占用 `hu_max - hu_min + 1` 字节. 即使覆盖完整的 `i16` 范围,
+    /// 也仅需 64 KiB, 通常可以放入 CPU 缓存; 但若只需处理少量体素,
+    /// 构建查找表的开销可能超过直接计算的开销.
+    ///
+    /// 若 `hu_min > hu_max`, 则程序 panic.
+    pub fn build_lut(&self, hu_min: i32, hu_max: i32) -> Vec<u8> {
+        assert!(hu_min <= hu_max, "查找表范围为空");
+        (hu_min..=hu_max)
+            // 有限值一定能求出结果.
+            .map(|hu| self.eval(hu as f32).unwrap())
+            .collect()
+    }
+
+    /// 构建将 HU 值映射为伪彩色的函数, 用于以离散色带 (如脂肪/水/软组织/骨骼) 展示扫描.
+    ///
+    /// `bands` 中的每一项 `(阈值, 颜色)` 表示一个从该阈值开始 (含) 直到下一个阈值 (不含)
+    /// 的色带, 阈值必须严格升序. 低于第一个阈值的 HU 值使用第一个色带的颜色.
+    ///
+    /// 与 [`CtWindow::eval`] 一致, 不大于窗下限的 HU 值映射为黑色, 不小于窗上限的
+    /// HU 值映射为白色, 非有限值 (如 NaN) 映射为黑色; 只有窗内的值才按色带着色.
+    ///
+    /// 若 `bands` 为空或阈值不是严格升序, 则程序 panic.
+    pub fn banded(&self, bands: &[(f32, Rgb<u8>)]) -> impl Fn(f32) -> Rgb<u8> {
+        assert!(!bands.is_empty(), "色带不能为空");
+        assert!(
+            bands.windows(2).all(|w| w[0].0 < w[1].0),
+            "色带阈值必须严格升序"
+        );
+        let bands = bands.to_vec();
+        let (lb, ub) = (self.lower_bound(), self.upper_bound());
+        move |ct| {
+            if !ct.is_finite() || ct <= lb {
+                Rgb([u8::MIN; 3])
+            } else if ct >= ub {
+                Rgb([u8::MAX; 3])
+            } else {
+                let idx = bands.partition_point(|&(t, _)| t <= ct);
+                bands[idx.saturating_sub(1)].1
+            }
+        }
+    }
+}
+
+/// 以两个 CT 窗融合同一张切片, 生成同时显示两类组织 (如软组织和骨骼) 的灰度图像.
+///
+/// 每个像素的值为 `weight * w1.eval_f32(hu) + (1 - weight) * w2.eval_f32(hu)`,
+/// 与 [`CtWindow::eval`] 一样向下取整. 因此 `weight` 为 `1.0` 或 `0.0` 时,
+/// 结果分别与单独使用 `w1` 或 `w2` 的结果完全相同. 非有限的 HU 值 (如 NaN) 被转换为 0.
+///
+/// 若 `weight` 不在 `[0.0, 1.0]` 内, 则程序 panic.
+pub fn blend_windows(scan: ScanSlice, w1: &CtWindow, w2: &CtWindow, weight: f32) -> Array2<u8> {
+    assert!((0.0..=1.0).contains(&weight), "权重 `{weight}` 越界");
+    scan.data()
+        .mapv(|hu| match (w1.eval_f32(hu), w2.eval_f32(hu)) {
+            (Some(a), Some(b)) => (weight * a + (1.0 - weight) * b) as u8,
+            _ => u8::MIN,
+        })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::CtWindow;
+    use crate::{blend_windows, CtWindow, ScanSlice};
+    use image::Rgb;
+    use ndarray::Array2;
 
     fn is_valid_init(level: f32, width: f32) -> bool {
         CtWindow::new(level, width).is_some()
     }
 
+    #[test]
+    fn test_for_structure() {
+        let window = CtWindow::for_structure(60.0, 0.3).unwrap();
+        assert_eq!(window.level(), 60.0);
+        assert!((window.width() - 200.0).abs() < 1e-3);
+        assert!(window.approx_eq(&CtWindow::from_liver_visual(), 1e-3));
+
+        // 窗宽与 |mean_hu| 成正比, 与对比度成反比.
+        let fat = CtWindow::for_structure(-100.0, 0.5).unwrap();
+        assert_eq!((fat.level(), fat.width()), (-100.0, 200.0));
+        let sharper = CtWindow::for_structure(-100.0, 2.0).unwrap();
+        assert_eq!(sharper.width(), 50.0);
+        // 平均值接近 0 时以 1 为下限.
+        let water = CtWindow::for_structure(0.2, 0.01).unwrap();
+        assert_eq!((water.level(), water.width()), (0.2, 100.0));
+
+        assert!(CtWindow::for_structure(60.0, 0.0).is_none());
+        assert!(CtWindow::for_structure(60.0, -1.0).is_none());
+        assert!(CtWindow::for_structure(60.0, f32::NAN).is_none());
+        // 窗宽过大.
+        assert!(CtWindow::for_structure(60.0, 1e-6).is_none());
+        assert!(CtWindow::for_structure(f32::NAN, 1.0).is_none());
+    }
+
     #[test]
     fn test_ct_window_invalid_input() {
         assert!(!is_valid_init(0.0, -1.0));
@@ -146,4 +279,112 @@ mod tests {
         assert_eq!(ct.eval(100.0).unwrap(), u8::MAX);
         assert!(float_eq(ct.eval_f32(100.0).unwrap(), 255.0));
     }
+
+    #[test]
+    fn test_ct_window_lut() {
+        let ct = CtWindow::from_liver_visual();
+        let (hu_min, hu_max) = (i16::MIN as i32, i16::MAX as i32);
+        let lut = ct.build_lut(hu_min, hu_max);
+        assert_eq!(lut.len(), 65536);
+        for hu in i16::MIN..=i16::MAX {
+            assert_eq!(
+                lut[(hu as i32 - hu_min) as usize],
+                ct.eval(hu as f32).unwrap()
+            );
+        }
+
+        let ct = CtWindow::new(80.0, 40.0).unwrap();
+        assert_eq!(ct.build_lut(70, 70), vec![(255.0 * 0.25) as u8]);
+        assert_eq!(ct.build_lut(-5, 60), vec![0; 66]);
+    }
+
+    #[test]
+    fn test_ct_window_eq() {
+        use std::collections::HashMap;
+
+        let a = CtWindow::from_liver_visual();
+        let b = CtWindow::new(60.0, 200.0).unwrap();
+        let c = CtWindow::new(60.0 + 1e-4, 200.0).unwrap();
+        let d = CtWindow::new(40.0, 400.0).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+        assert!(a.approx_eq(&c, 1e-3));
+        assert!(!a.approx_eq(&c, 1e-5));
+        assert!(!a.approx_eq(&d, 1.0));
+
+        let mut cache = HashMap::new();
+        cache.insert(a, a.build_lut(-1024, 1024));
+        cache.insert(d, d.build_lut(-1024, 1024));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains_key(&b));
+        assert_eq!(cache[&b][1024], a.eval(0.0).unwrap());
+    }
+
+    #[test]
+    fn test_ct_window_banded() {
+        let fat = Rgb([255, 255, 0]);
+        let water = Rgb([0, 0, 255]);
+        let soft = Rgb([255, 0, 0]);
+        let bone = Rgb([255, 255, 255]);
+        // [-1000, 1000].
+        let window = CtWindow::new(0.0, 2000.0).unwrap();
+        let f = window.banded(&[(-100.0, fat), (-10.0, water), (20.0, soft), (300.0, bone)]);
+
+        // 阈值本身属于从该阈值开始的色带.
+        assert_eq!(f(-100.0), fat);
+        assert_eq!(f(-10.0), water);
+        assert_eq!(f(20.0), soft);
+        assert_eq!(f(300.0), bone);
+        assert_eq!(f(-10.5), fat);
+        assert_eq!(f(19.9), water);
+        assert_eq!(f(299.0), soft);
+        // 低于第一个阈值时使用第一个色带.
+        assert_eq!(f(-500.0), fat);
+        // 窗外和非有限值.
+        assert_eq!(f(-1000.0), Rgb([0; 3]));
+        assert_eq!(f(1500.0), Rgb([255; 3]));
+        assert_eq!(f(f32::NAN), Rgb([0; 3]));
+        assert_eq!(f(f32::INFINITY), Rgb([0; 3]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ct_window_banded_unsorted() {
+        let c = Rgb([0; 3]);
+        let _ = CtWindow::from_liver_visual().banded(&[(10.0, c), (10.0, c)]);
+    }
+
+    #[test]
+    fn test_blend_windows() {
+        let data = Array2::from_shape_fn((16, 16), |(h, w)| (h * 16 + w) as f32 * 8.0 - 800.0);
+        let scan = ScanSlice::new(data.view());
+        let soft = CtWindow::new(40.0, 400.0).unwrap();
+        let bone = CtWindow::new(400.0, 1800.0).unwrap();
+
+        let only = |w: &CtWindow| data.mapv(|hu| w.eval(hu).unwrap());
+        assert_eq!(
+            blend_windows(ScanSlice::new(data.view()), &soft, &bone, 1.0),
+            only(&soft)
+        );
+        assert_eq!(
+            blend_windows(ScanSlice::new(data.view()), &soft, &bone, 0.0),
+            only(&bone)
+        );
+
+        // 中间权重的结果位于两者之间.
+        let half = blend_windows(scan, &soft, &bone, 0.5);
+        for (pos, &v) in half.indexed_iter() {
+            let (a, b) = (soft.eval(data[pos]).unwrap(), bone.eval(data[pos]).unwrap());
+            assert!(
+                a.min(b) <= v && v <= a.max(b),
+                "{pos:?}: {v} not in [{a}, {b}]"
+            );
+        }
+        let nan = Array2::from_elem((2, 2), f32::NAN);
+        assert_eq!(
+            blend_windows(ScanSlice::new(nan.view()), &soft, &bone, 0.3),
+            Array2::<u8>::zeros((2, 2))
+        );
+    }
 }
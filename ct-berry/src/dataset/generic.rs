@@ -115,6 +115,58 @@ impl ExactSizeIterator for LabelLoader {
     }
 }
 
+/// 在 labels 加载器 (如 [`LabelLoader`] 或 [`crate::dataset::lits_train::LabelLoader`])
+/// 之上包装统一的预处理步骤. 每个成功加载的 label 在被返回之前都会先经过 `f` 处理,
+/// 加载失败的项则原样返回.
+///
+/// 例如, 重复实验时常用的 `fill_background_hollow` + `unify` 可以集中写在 `f` 中,
+/// 从而迭代得到的 label 都可以直接使用.
+pub fn preprocessed_label_loader<L, F>(loader: L, f: F) -> PreprocessedLabelLoader<L, F>
+where
+    L: Iterator<Item = (u32, nifti::Result<CtLabel>)>,
+    F: FnMut(&mut CtLabel),
+{
+    PreprocessedLabelLoader { loader, f }
+}
+
+/// 返回预处理后 3D CT labels 的数据加载器, 由 [`preprocessed_label_loader`] 创建.
+pub struct PreprocessedLabelLoader<L, F> {
+    loader: L,
+    f: F,
+}
+
+impl<L, F> Iterator for PreprocessedLabelLoader<L, F>
+where
+    L: Iterator<Item = (u32, nifti::Result<CtLabel>)>,
+    F: FnMut(&mut CtLabel),
+{
+    type Item = (u32, nifti::Result<CtLabel>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, mut data) = self.loader.next()?;
+        if let Ok(label) = data.as_mut() {
+            (self.f)(label);
+        }
+        Some((idx, data))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.loader.size_hint()
+    }
+}
+
+impl<L, F> ExactSizeIterator for PreprocessedLabelLoader<L, F>
+where
+    L: ExactSizeIterator<Item = (u32, nifti::Result<CtLabel>)>,
+    F: FnMut(&mut CtLabel),
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.loader.len()
+    }
+}
+
 /// 从指定索引、路径、文件名构造器来创建通用的 CT data 加载器.
 ///
 /// # 注意
@@ -171,3 +223,55 @@ impl Iterator for CtDataLoader {
         Some((idx, data))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::LITS_LIVER;
+    use crate::NiftiHeaderAttr;
+    use ndarray::Array3;
+    use nifti::writer::WriterOptions;
+
+    #[test]
+    fn test_preprocessed_label_loader() {
+        let dir = std::env::temp_dir().join(format!("ct-berry-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let label = CtLabel::fake(Array3::zeros((4, 4, 2)), [1.0, 1.0, 2.0], 0, [0.0; 3]);
+        let mut header = label.header().clone();
+        header.intent_name = Default::default();
+        for idx in [0, 1] {
+            WriterOptions::new(dir.join(format!("l-{idx}.nii")))
+                .reference_header(&header)
+                .write_nifti(&Array3::from_elem((4, 4, 2), LITS_LIVER))
+                .unwrap();
+        }
+        let builder: FilenameBuilder = |idx| format!("l-{idx}.nii");
+
+        // 原始加载器返回的 label 未经处理.
+        let (_, raw) = label_loader([0], &dir, builder).next().unwrap();
+        assert!(!raw.unwrap().is_faked());
+
+        let mut calls = 0;
+        let loader = preprocessed_label_loader(label_loader([0, 2, 1], &dir, builder), |l| {
+            calls += 1;
+            let data = l.data().reversed_axes().to_owned();
+            *l = CtLabel::fake_with_header(l.header(), data);
+        });
+        assert_eq!(loader.len(), 3);
+        let got: Vec<_> = loader.collect();
+        assert_eq!(got.iter().map(|(i, _)| *i).collect::<Vec<_>>(), [0, 2, 1]);
+        for (idx, res) in &got {
+            match res {
+                Ok(l) => {
+                    assert!(l.is_faked());
+                    assert!(l.data().iter().all(|&p| p == LITS_LIVER));
+                }
+                Err(_) => assert_eq!(*idx, 2),
+            }
+        }
+        // 加载失败的项不经过预处理.
+        assert_eq!(calls, 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,9 +2,11 @@
 
 mod core;
 
-use crate::Idx2d;
+use crate::{Idx2d, Idx3d, LabelSlice, Predicate};
+use ndarray::Array2;
+use std::collections::VecDeque;
 
-/// 获得 `(h, w)` 的 4-邻居索引. 不检查越界.
+/// 获得 `(h, w)` 的 4-邻居索引. 不检查越界, 需要检查时请使用 [`neighbour4_checked`].
 #[inline]
 pub(crate) fn neighbour4((h, w): Idx2d) -> [Idx2d; 4] {
     [
@@ -15,7 +17,7 @@ pub(crate) fn neighbour4((h, w): Idx2d) -> [Idx2d; 4] {
     ]
 }
 
-/// 获得 `(h, w)` 的 8-邻居索引. 不检查越界.
+/// 获得 `(h, w)` 的 8-邻居索引. 不检查越界, 需要检查时请使用 [`neighbour8_checked`].
 #[inline]
 pub(crate) fn neighbour8((h, w): Idx2d) -> [Idx2d; 8] {
     [
@@ -29,3 +31,215 @@ pub(crate) fn neighbour8((h, w): Idx2d) -> [Idx2d; 8] {
         (h.saturating_add(1), w.saturating_add(1)),
     ]
 }
+
+/// 将 `pos` 偏移 `(dh, dw)`. 若结果下溢 (小于 0) 或不在形状为 `shape` (高, 宽)
+/// 的图像内, 则返回 `None`.
+///
+/// 与 `wrapping_sub` 配合事后越界检查的写法相比, 该函数不会产生 `usize::MAX`
+/// 这样的中间索引, 新代码应优先使用它.
+#[inline]
+pub fn checked_offset_2d((h, w): Idx2d, dh: i32, dw: i32, shape: Idx2d) -> Option<Idx2d> {
+    let h = h.checked_add_signed(dh as isize).filter(|&v| v < shape.0)?;
+    let w = w.checked_add_signed(dw as isize).filter(|&v| v < shape.1)?;
+    Some((h, w))
+}
+
+/// 三维版本的 [`checked_offset_2d`]: 将 `pos` 偏移 `(dz, dh, dw)`. 若结果下溢或不在形状为
+/// `shape` (z, 高, 宽) 的体积内, 则返回 `None`.
+#[inline]
+pub fn checked_offset_3d(
+    (z, h, w): Idx3d,
+    dz: i32,
+    dh: i32,
+    dw: i32,
+    shape: Idx3d,
+) -> Option<Idx3d> {
+    let z = z.checked_add_signed(dz as isize).filter(|&v| v < shape.0)?;
+    let (h, w) = checked_offset_2d((h, w), dh, dw, (shape.1, shape.2))?;
+    Some((z, h, w))
+}
+
+/// 同 [`neighbour4`], 但检查越界: 不在形状为 `shape` 的图像内的邻居为 `None`.
+/// 邻居的顺序与 [`neighbour4`] 一致.
+#[inline]
+pub(crate) fn neighbour4_checked(pos: Idx2d, shape: Idx2d) -> [Option<Idx2d>; 4] {
+    [(-1, 0), (1, 0), (0, -1), (0, 1)].map(|(dh, dw)| checked_offset_2d(pos, dh, dw, shape))
+}
+
+/// 同 [`neighbour8`], 但检查越界: 不在形状为 `shape` 的图像内的邻居为 `None`.
+/// 邻居的顺序与 [`neighbour8`] 一致.
+#[inline]
+pub(crate) fn neighbour8_checked(pos: Idx2d, shape: Idx2d) -> [Option<Idx2d>; 8] {
+    [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, -1),
+        (0, 1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+    ]
+    .map(|(dh, dw)| checked_offset_2d(pos, dh, dw, shape))
+}
+
+/// 在 `slice` 中寻找一条从 `start` 到 `end` 的最短 8-连通路径, 路径上的所有像素都满足 `pred`.
+/// 可用于把手绘的线段吸附到前景边界上.
+///
+/// 返回的路径包含首尾两点. 若 `start` 或 `end` 越界或不满足 `pred`,
+/// 或两者不连通, 则返回 `None`.
+pub fn trace_path(
+    slice: &LabelSlice,
+    start: Idx2d,
+    end: Idx2d,
+    pred: Predicate,
+) -> Option<Vec<Idx2d>> {
+    let valid = |p: Idx2d| slice.check(p) && pred(slice[p]);
+    if !valid(start) || !valid(end) {
+        return None;
+    }
+
+    // 记录 BFS 树中每个像素的前驱.
+    let mut prev: Array2<Option<Idx2d>> = Array2::from_elem(slice.shape(), None);
+    prev[start] = Some(start);
+    let mut bfs_q = VecDeque::from([start]);
+    while let Some(cur) = bfs_q.pop_front() {
+        if cur == end {
+            let mut path = vec![end];
+            let mut p = end;
+            while p != start {
+                p = prev[p].unwrap();
+                path.push(p);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for nb in neighbour8_checked(cur, slice.shape()).into_iter().flatten() {
+            if pred(slice[nb]) && prev[nb].is_none() {
+                prev[nb] = Some(cur);
+                bfs_q.push_back(nb);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::gray::{LITS_BACKGROUND, LITS_BOUNDARY, LITS_LIVER};
+
+    #[test]
+    fn test_checked_offset() {
+        let shape = (3, 4);
+        assert_eq!(checked_offset_2d((1, 1), 1, 2, shape), Some((2, 3)));
+        assert_eq!(checked_offset_2d((0, 0), 0, 0, shape), Some((0, 0)));
+        // 上下左右四条边的下溢和上溢.
+        assert_eq!(checked_offset_2d((0, 2), -1, 0, shape), None);
+        assert_eq!(checked_offset_2d((2, 2), 1, 0, shape), None);
+        assert_eq!(checked_offset_2d((1, 0), 0, -1, shape), None);
+        assert_eq!(checked_offset_2d((1, 3), 0, 1, shape), None);
+        assert_eq!(
+            checked_offset_2d((usize::MAX, 0), 1, 0, (usize::MAX, 1)),
+            None
+        );
+        assert_eq!(checked_offset_2d((0, 0), i32::MIN, 0, shape), None);
+
+        let shape = (2, 3, 4);
+        assert_eq!(
+            checked_offset_3d((1, 2, 3), -1, -2, -3, shape),
+            Some((0, 0, 0))
+        );
+        for (pos, d) in [
+            ((0, 1, 1), (-1, 0, 0)),
+            ((1, 1, 1), (1, 0, 0)),
+            ((1, 0, 1), (0, -1, 0)),
+            ((1, 2, 1), (0, 1, 0)),
+            ((1, 1, 0), (0, 0, -1)),
+            ((1, 1, 3), (0, 0, 1)),
+        ] {
+            assert_eq!(
+                checked_offset_3d(pos, d.0, d.1, d.2, shape),
+                None,
+                "{pos:?} {d:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_neighbour_checked() {
+        let shape = (3, 3);
+        // 内部像素的邻居与不检查越界的版本一致.
+        assert_eq!(
+            neighbour4_checked((1, 1), shape),
+            neighbour4((1, 1)).map(Some)
+        );
+        assert_eq!(
+            neighbour8_checked((1, 1), shape),
+            neighbour8((1, 1)).map(Some)
+        );
+
+        // 四个角各自只保留图像内的邻居.
+        for pos in [(0, 0), (0, 2), (2, 0), (2, 2)] {
+            let n4: Vec<_> = neighbour4_checked(pos, shape)
+                .into_iter()
+                .flatten()
+                .collect();
+            let n8: Vec<_> = neighbour8_checked(pos, shape)
+                .into_iter()
+                .flatten()
+                .collect();
+            assert_eq!(n4.len(), 2);
+            assert_eq!(n8.len(), 3);
+            assert!(n8.iter().all(|&(h, w)| h < 3 && w < 3));
+            assert!(n4.iter().all(|p| neighbour4(pos).contains(p)));
+        }
+        assert_eq!(
+            neighbour4_checked((0, 1), shape),
+            [None, Some((1, 1)), Some((0, 0)), Some((0, 2))]
+        );
+    }
+
+    #[test]
+    fn test_trace_path_on_ring() {
+        // 圆盘边界 (有 4-邻居在圆盘外的像素) 标记为 `LITS_BOUNDARY`.
+        let disc = Array2::from_shape_fn((24, 24), |(h, w)| {
+            (h as f64 - 12.0).hypot(w as f64 - 12.0) <= 8.0
+        });
+        let mut data = Array2::from_shape_fn((24, 24), |pos| {
+            if !disc[pos] {
+                LITS_BACKGROUND
+            } else if neighbour4(pos).iter().any(|&nb| !disc[nb]) {
+                LITS_BOUNDARY
+            } else {
+                LITS_LIVER
+            }
+        });
+        let ring_len = data.iter().filter(|&&p| p == LITS_BOUNDARY).count();
+        // 一个孤立的边界像素, 与环不连通.
+        data[(1, 1)] = LITS_BOUNDARY;
+        let sli = LabelSlice::new(data.view());
+        let on_ring = |p: u8| p == LITS_BOUNDARY;
+
+        let (start, end) = ((4, 12), (20, 12));
+        let path = trace_path(&sli, start, end, on_ring).unwrap();
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        for w in path.windows(2) {
+            let (d_h, d_w) = (w[0].0.abs_diff(w[1].0), w[0].1.abs_diff(w[1].1));
+            assert!(d_h <= 1 && d_w <= 1 && (d_h, d_w) != (0, 0), "{w:?}");
+        }
+        assert!(path.iter().all(|&p| on_ring(sli[p])));
+        // 最短路径不会绕过大半个环.
+        assert!(
+            path.len() <= ring_len / 2 + 1,
+            "{} vs {ring_len}",
+            path.len()
+        );
+
+        assert_eq!(trace_path(&sli, start, start, on_ring), Some(vec![start]));
+        // 终点不在环上, 或两点不连通.
+        assert_eq!(trace_path(&sli, start, (12, 12), on_ring), None);
+        assert_eq!(trace_path(&sli, start, (1, 1), on_ring), None);
+    }
+}
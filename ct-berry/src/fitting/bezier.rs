@@ -0,0 +1,255 @@
+//! 分段三次 Bézier 曲线.
+//!
+//! 以累计弦长为参数, 通过最小二乘法估计所有控制点.
+
+use super::parametric::chord_length_params;
+use super::VecPair;
+use crate::Idx2dF;
+use ndarray::{Array1, Array2};
+use ndarray_linalg::Solve;
+
+/// 三次 Bernstein 基函数在 `u` 处的值.
+#[inline]
+fn bernstein(u: f64) -> [f64; 4] {
+    let v = 1.0 - u;
+    [v * v * v, 3.0 * u * v * v, 3.0 * u * u * v, u * u * u]
+}
+
+/// 三次 Bernstein 基函数在 `u` 处的一阶导数值.
+#[inline]
+fn bernstein_d1(u: f64) -> [f64; 4] {
+    let v = 1.0 - u;
+    [
+        -3.0 * v * v,
+        3.0 * v * (v - 2.0 * u),
+        3.0 * u * (2.0 * v - u),
+        3.0 * u * u,
+    ]
+}
+
+/// 三次 Bernstein 基函数在 `u` 处的二阶导数值.
+#[inline]
+fn bernstein_d2(u: f64) -> [f64; 4] {
+    let v = 1.0 - u;
+    [
+        6.0 * v,
+        6.0 * (3.0 * u - 2.0),
+        6.0 * (1.0 - 3.0 * u),
+        6.0 * u,
+    ]
+}
+
+/// 参数重估计 (Newton 迭代) 的最大轮数.
+const REPARAM_ROUNDS: usize = 20;
+
+pub(crate) struct BezierImp {
+    /// 所有控制点, 长度为 `3 * segments + 1`.
+    /// 第 `i` 段的控制点为 `ctrl[3i..=3i + 3]`, 相邻两段共享端点.
+    ctrl: Vec<Idx2dF>,
+}
+
+impl BezierImp {
+    /// 用 `segments` 段三次 Bézier 曲线拟合 `points`.
+    ///
+    /// 曲线经过首尾两点. 按累计弦长把参数区间等分为 `segments` 段,
+    /// 每个点按其参数值归入对应的段. 求出控制点后, 用 Newton 法把每个点的参数
+    /// 修正为曲线上离它最近的位置, 再重新求解控制点, 如此迭代若干轮.
+    pub fn fit(points: &[Idx2dF], segments: u32) -> Self {
+        assert_ne!(segments, 0, "至少需要一段曲线");
+        let n_ctrl = 3 * segments as usize + 1;
+        assert!(
+            chord_length_params(points).0.len() >= n_ctrl,
+            "拟合 {segments} 段曲线至少需要 {n_ctrl} 个不重合的点"
+        );
+        Self::try_fit(points, segments).expect("采样点分布过于不均, 部分曲线段无法确定")
+    }
+
+    /// 同 [`Self::fit`], 但在 `segments` 为 0, 不重合的点不足,
+    /// 或某一段曲线的控制点无法确定时返回 `None`.
+    pub fn try_fit(points: &[Idx2dF], segments: u32) -> Option<Self> {
+        let (t, x, y) = chord_length_params(points);
+        let segments = segments as usize;
+        let n_ctrl = 3 * segments + 1;
+        if segments == 0 || t.len() < n_ctrl {
+            return None;
+        }
+
+        let total = *t.last().unwrap();
+        // 每个点所属的段及其段内参数.
+        let mut params: Vec<(usize, f64)> = t
+            .iter()
+            .map(|&tj| {
+                let s = tj / total * segments as f64;
+                let seg = (s as usize).min(segments - 1);
+                (seg, s - seg as f64)
+            })
+            .collect();
+
+        let mut this = Self::solve(&params, &x, &y, n_ctrl)?;
+        for _ in 0..REPARAM_ROUNDS {
+            let mut max_delta: f64 = 0.0;
+            for (j, (seg, u)) in params.iter_mut().enumerate() {
+                let next = this.newton_step(*seg, *u, (x[j], y[j]));
+                max_delta = max_delta.max((next - *u).abs());
+                *u = next;
+            }
+            if max_delta < 1e-12 {
+                break;
+            }
+            this = Self::solve(&params, &x, &y, n_ctrl)?;
+        }
+        Some(this)
+    }
+
+    /// 给定每个点的 (段, 段内参数), 以最小二乘法求解所有控制点.
+    /// 若方程组奇异 (某一段中的点不足以确定其控制点), 则返回 `None`.
+    fn solve(params: &[(usize, f64)], x: &[f64], y: &[f64], n_ctrl: usize) -> Option<Self> {
+        let first = (x[0], y[0]);
+        let last = (*x.last().unwrap(), *y.last().unwrap());
+
+        // 设计矩阵: 第 j 行为第 j 个点对全部控制点的权重.
+        let mut design = Array2::<f64>::zeros((params.len(), n_ctrl));
+        for (j, &(seg, u)) in params.iter().enumerate() {
+            for (k, b) in bernstein(u).into_iter().enumerate() {
+                design[(j, 3 * seg + k)] = b;
+            }
+        }
+
+        // 首尾控制点固定为首尾两点, 其余控制点为未知量.
+        let inner = design.slice(ndarray::s![.., 1..n_ctrl - 1]).to_owned();
+        let normal = inner.t().dot(&inner);
+        let solve = |coord: &[f64], (c0, c1): (f64, f64)| -> Option<Array1<f64>> {
+            let rhs = Array1::from_shape_fn(coord.len(), |j| {
+                coord[j] - design[(j, 0)] * c0 - design[(j, n_ctrl - 1)] * c1
+            });
+            normal
+                .solve(&inner.t().dot(&rhs))
+                .ok()
+                .filter(|c| c.iter().all(|v| v.is_finite()))
+        };
+        let cx = solve(x, (first.0, last.0))?;
+        let cy = solve(y, (first.1, last.1))?;
+
+        let mut ctrl = Vec::with_capacity(n_ctrl);
+        ctrl.push(first);
+        ctrl.extend(cx.iter().copied().zip(cy.iter().copied()));
+        ctrl.push(last);
+        Some(Self { ctrl })
+    }
+
+    /// 对第 `seg` 段上参数为 `u` 的点做一步 Newton 迭代,
+    /// 使其向曲线上离 `p` 最近的位置移动. 结果被截断在 `[0, 1]` 内.
+    fn newton_step(&self, seg: usize, u: f64, (px, py): Idx2dF) -> f64 {
+        let c = &self.ctrl[3 * seg..3 * seg + 4];
+        let (bx, by) = Self::combine(c, bernstein(u));
+        let (d1x, d1y) = Self::combine(c, bernstein_d1(u));
+        let (d2x, d2y) = Self::combine(c, bernstein_d2(u));
+        let (ex, ey) = (bx - px, by - py);
+        let num = ex * d1x + ey * d1y;
+        let den = d1x * d1x + d1y * d1y + ex * d2x + ey * d2y;
+        if den.abs() < f64::EPSILON {
+            return u;
+        }
+        (u - num / den).clamp(0.0, 1.0)
+    }
+
+    #[inline]
+    fn combine(c: &[Idx2dF], weights: [f64; 4]) -> Idx2dF {
+        weights
+            .into_iter()
+            .zip(c)
+            .fold((0.0, 0.0), |(ax, ay), (b, &(px, py))| {
+                (ax + b * px, ay + b * py)
+            })
+    }
+
+    /// 在每段曲线上等距 (按参数 `u`) 取 `samples_per_segment` 个点,
+    /// 最后补上终点. 返回 `segments * samples_per_segment + 1` 个点.
+    pub fn make_curve(&self, samples_per_segment: u32) -> VecPair<f64> {
+        assert_ne!(samples_per_segment, 0, "每段至少需要采样一个点");
+        let n = samples_per_segment as usize;
+        let cap = (self.ctrl.len() - 1) / 3 * n + 1;
+        let (mut ans_x, mut ans_y) = (Vec::with_capacity(cap), Vec::with_capacity(cap));
+        for c in self.ctrl.windows(4).step_by(3) {
+            for k in 0..n {
+                let (px, py) = Self::eval_segment(c, k as f64 / n as f64);
+                ans_x.push(px);
+                ans_y.push(py);
+            }
+        }
+        let &(lx, ly) = self.ctrl.last().unwrap();
+        ans_x.push(lx);
+        ans_y.push(ly);
+        (ans_x, ans_y)
+    }
+
+    /// 每个输入点与加密曲线上离它最近 (欧几里得距离) 的采样点在 y 方向上的带符号差值.
+    pub fn residuals(&self, points: &[Idx2dF], samples_per_segment: u32) -> Vec<f64> {
+        let (cx, cy) = self.make_curve(samples_per_segment);
+        points
+            .iter()
+            .map(|&(px, py)| {
+                let (_, fy) = cx
+                    .iter()
+                    .zip(&cy)
+                    .map(|(&fx, &fy)| (f64::hypot(fx - px, fy - py), fy))
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .unwrap();
+                py - fy
+            })
+            .collect()
+    }
+
+    #[inline]
+    fn eval_segment(c: &[Idx2dF], u: f64) -> Idx2dF {
+        Self::combine(c, bernstein(u))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dist_to_curve(p: Idx2dF, curve: &[Idx2dF]) -> f64 {
+        curve
+            .iter()
+            .map(|&(x, y)| f64::hypot(x - p.0, y - p.1))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn test_bezier_recover_known_curve() {
+        let truth = [(0.0, 0.0), (1.0, 3.0), (4.0, 3.0), (5.0, 0.0)];
+        let dense: Vec<_> = (0..=2000)
+            .map(|k| BezierImp::eval_segment(&truth, k as f64 / 2000.0))
+            .collect();
+        let points: Vec<_> = (0..=30)
+            .map(|k| BezierImp::eval_segment(&truth, k as f64 / 30.0))
+            .collect();
+
+        let fitted = BezierImp::fit(&points, 1);
+        for (c, t) in fitted.ctrl.iter().zip(&truth) {
+            assert!(f64::hypot(c.0 - t.0, c.1 - t.1) < 0.05, "{c:?} vs {t:?}");
+        }
+        let (x, y) = fitted.make_curve(50);
+        assert_eq!(x.len(), 51);
+        assert_eq!((x[0], y[0]), (0.0, 0.0));
+        assert_eq!((x[50], y[50]), (5.0, 0.0));
+        let err = x
+            .iter()
+            .zip(&y)
+            .map(|(&px, &py)| dist_to_curve((px, py), &dense))
+            .fold(0.0, f64::max);
+        assert!(err < 0.02, "{err}");
+
+        // 多段拟合同样能很好地还原曲线.
+        let (x, y) = BezierImp::fit(&points, 3).make_curve(20);
+        assert_eq!(x.len(), 61);
+        let err = x
+            .iter()
+            .zip(&y)
+            .map(|(&px, &py)| dist_to_curve((px, py), &dense))
+            .fold(0.0, f64::max);
+        assert!(err < 0.02, "{err}");
+    }
+}
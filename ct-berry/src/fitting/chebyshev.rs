@@ -0,0 +1,156 @@
+//! Chebyshev 基下的多项式曲线.
+
+use super::VecPair;
+use ndarray::{s, Array1, Array2, ArrayView1};
+
+/// 以 Chebyshev 多项式 `T_0, T_1, ...` 为基表示的多项式, 自变量已映射到 `[-1, 1]`.
+struct ChebSeries {
+    coef: Array1<f64>,
+    /// 原始区间 `[min, max]`.
+    domain: (f64, f64),
+}
+
+impl ChebSeries {
+    /// 把原始自变量映射到 `[-1, 1]`.
+    #[inline]
+    fn to_unit(&self, x: f64) -> f64 {
+        let (a, b) = self.domain;
+        (2.0 * x - (a + b)) / (b - a)
+    }
+
+    /// 使用 Clenshaw 递推求值.
+    fn eval(&self, x: f64) -> f64 {
+        let u = self.to_unit(x);
+        let (mut b1, mut b2) = (0.0, 0.0);
+        for &c in self.coef.iter().skip(1).rev() {
+            (b1, b2) = (2.0 * u * b1 - b2 + c, b1);
+        }
+        u * b1 - b2 + self.coef[0]
+    }
+}
+
+pub(crate) struct ChebImp<'a> {
+    x: ArrayView1<'a, f64>,
+    y: ArrayView1<'a, f64>,
+    degree: u32,
+    points: u32,
+    domain: (f64, f64),
+}
+
+impl<'a> ChebImp<'a> {
+    /// `degree` 是多项式次数, `points` 是采样点个数, 会在区间内等距采样.
+    pub fn new(x: ArrayView1<'a, f64>, y: ArrayView1<'a, f64>, degree: u32, points: u32) -> Self {
+        assert_eq!(x.len(), y.len(), "x 值和 y 值必须一一对应");
+        assert!(x.len() >= 2, "至少需要拟合两个点");
+        assert_ne!(degree, 0, "拟合曲线的次数不能为 0");
+        assert!(points >= 3, "至少需要获得三个自变量 x.");
+
+        let domain = x
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+                (lo.min(v), hi.max(v))
+            });
+        assert!(domain.0 < domain.1, "所有 x 值都相同, 无法拟合");
+        Self {
+            x,
+            y,
+            degree,
+            points,
+            domain,
+        }
+    }
+
+    /// 若不同的 `x` 值不足以确定 `degree` 次多项式, 则返回 `None`.
+    pub fn make_curve(&self) -> Option<VecPair<f64>> {
+        let series = self.fit()?;
+        let (a, b) = self.domain;
+        let step = (b - a) / (self.points - 1) as f64;
+        let xs: Vec<f64> = (0..self.points)
+            .map(|i| {
+                if i + 1 == self.points {
+                    b
+                } else {
+                    a + step * i as f64
+                }
+            })
+            .collect();
+        let ys = xs.iter().map(|&x| series.eval(x)).collect();
+        Some((xs, ys))
+    }
+
+    /// 每个输入点处的带符号竖直残差 `y - p(x)`. 返回 `None` 的条件同 [`Self::make_curve`].
+    pub fn residuals(&self) -> Option<Vec<f64>> {
+        let series = self.fit()?;
+        let res = self
+            .x
+            .iter()
+            .zip(self.y.iter())
+            .map(|(&x, &y)| y - series.eval(x))
+            .collect();
+        Some(res)
+    }
+
+    /// 最小二乘法求解 Chebyshev 系数.
+    ///
+    /// 直接对设计矩阵做 QR 分解求解, 不构造法方程.
+    /// 若设计矩阵列不满秩 (不同的 `x` 值少于 `degree + 1` 个), 则返回 `None`.
+    fn fit(&self) -> Option<ChebSeries> {
+        let series = ChebSeries {
+            coef: Array1::zeros(0),
+            domain: self.domain,
+        };
+        let n = self.degree as usize + 1;
+        let mut basis = Array2::<f64>::zeros((self.x.len(), n));
+        for (mut row, &x) in basis.rows_mut().into_iter().zip(self.x.iter()) {
+            let u = series.to_unit(x);
+            row[0] = 1.0;
+            row[1] = u;
+            for k in 2..n {
+                row[k] = 2.0 * u * row[k - 1] - row[k - 2];
+            }
+        }
+
+        let coef = least_squares(basis, self.y)?;
+        Some(ChebSeries { coef, ..series })
+    }
+}
+
+/// 以 Householder QR 分解求解最小二乘问题 `min |a c - y|`.
+///
+/// `a` 的元素应在 `[-1, 1]` 内. 若 `a` 在数值意义上列不满秩, 则返回 `None`.
+fn least_squares(mut a: Array2<f64>, y: ArrayView1<f64>) -> Option<Array1<f64>> {
+    let (m, n) = a.dim();
+    if m < n {
+        return None;
+    }
+    let tol = 1e-10 * (m as f64).sqrt();
+    let mut b = y.to_owned();
+    for k in 0..n {
+        let col = a.slice(s![k.., k]);
+        let norm = col.dot(&col).sqrt();
+        if norm < tol {
+            return None;
+        }
+        // 反射向量 `v = col - alpha * e_1`, 符号选择避免相消.
+        let alpha = if col[0] > 0.0 { -norm } else { norm };
+        let mut v = col.to_owned();
+        v[0] -= alpha;
+        let vv = v.dot(&v);
+        for j in k..n {
+            let mut c = a.slice_mut(s![k.., j]);
+            let f = 2.0 * v.dot(&c) / vv;
+            c.scaled_add(-f, &v);
+        }
+        let mut c = b.slice_mut(s![k..]);
+        let f = 2.0 * v.dot(&c) / vv;
+        c.scaled_add(-f, &v);
+    }
+
+    // 回代求解上三角方程组 `R c = Q^T y`.
+    let mut coef = Array1::<f64>::zeros(n);
+    for k in (0..n).rev() {
+        let acc: f64 = (k + 1..n).map(|j| a[(k, j)] * coef[j]).sum();
+        coef[k] = (b[k] - acc) / a[(k, k)];
+    }
+    Some(coef)
+}
@@ -123,3 +123,33 @@ pub(crate) struct CubicSplineImp<'a, T: Float> {
 
 impl_cubic!(f32, 1.0_f32, 2.0_f32, 3.0_f32);
 impl_cubic!(f64, 1.0_f64, 2.0_f64, 3.0_f64);
+
+impl CubicSplineImp<'_, f64> {
+    /// 各段的样条系数, 第 `i` 行依次为第 `i` 段的一次, 二次, 三次项系数.
+    pub fn coefficients(&self) -> Array2<f64> {
+        self.spline_coefficient()
+    }
+
+    /// 每个节点处的值与其两侧相邻加密点 (每段 `k` 个点) 上样条值的平均之差.
+    ///
+    /// 插值样条经过所有节点, 节点处的残差恒为 0, 因此用该差值反映曲线在
+    /// 节点附近的局部弯曲程度: 约为 `-s''(x) * h^2 / 2`, 其中 `h` 为加密步长.
+    ///
+    /// 首尾节点只有一侧的加密点. 若把端点所在的段外推一步, 对称地取平均, 三次和一次项
+    /// 相互抵消, 差值恰为 `-s''(x) * h^2 / 2`; 而自然样条在端点的二阶导数为 0,
+    /// 因此首尾节点的残差固定为 0.
+    pub fn knot_residuals(&self) -> Vec<f64> {
+        let (_, ys) = self.make_spline();
+        let k = self.k as usize;
+        let last = self.x.len() - 1;
+        (0..=last)
+            .map(|i| {
+                if i == 0 || i == last {
+                    return 0.0;
+                }
+                let j = i * k;
+                self.y[i] - (ys[j - 1] + ys[j + 1]) / 2.0
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,53 @@
+//! 可以在任意位置求值的拟合曲线.
+
+use ndarray::Array2;
+
+/// 拟合得到的曲线本身 (而不是其采样点), 由 [`super::fit_polynomial_handle`]
+/// 或 [`super::fit_cubic_spline_handle`] 构造.
+///
+/// 只需要少数几个位置的函数值时, 直接调用 [`FittedCurve::eval`] 即可,
+/// 不必像 [`super::polynomial_f64`] 那样先在整个区间内加密采样.
+#[derive(Debug, Clone)]
+pub struct FittedCurve(Repr);
+
+#[derive(Debug, Clone)]
+enum Repr {
+    /// 单项式基下的系数, 按次数升序排列.
+    Polynomial(Vec<f64>),
+
+    /// 三次样条. `coe` 的第 `i` 行 `(c1, c2, c3)` 表示第 `i` 段
+    /// `y = y[i] + c1 * t + c2 * t² + c3 * t³`, 其中 `t = x - x[i]`.
+    Spline {
+        x: Vec<f64>,
+        y: Vec<f64>,
+        coe: Array2<f64>,
+    },
+}
+
+impl FittedCurve {
+    pub(super) fn polynomial(coef: Vec<f64>) -> Self {
+        Self(Repr::Polynomial(coef))
+    }
+
+    pub(super) fn spline(x: Vec<f64>, y: Vec<f64>, coe: Array2<f64>) -> Self {
+        debug_assert_eq!(coe.nrows() + 1, x.len());
+        Self(Repr::Spline { x, y, coe })
+    }
+
+    /// 曲线在 `x` 处的值.
+    ///
+    /// 多项式使用 Horner 法求值; 三次样条先二分查找 `x` 所在的段, 再对该段求值.
+    /// 若 `x` 位于样条节点的区间以外, 则以首段或末段的三次多项式外推.
+    pub fn eval(&self, x: f64) -> f64 {
+        match &self.0 {
+            Repr::Polynomial(coef) => coef.iter().rev().fold(0.0, |acc, &c| acc * x + c),
+            Repr::Spline { x: knots, y, coe } => {
+                let ascending = knots[0] < knots[1];
+                let after = knots.partition_point(|&k| if ascending { k <= x } else { k >= x });
+                let i = after.clamp(1, knots.len() - 1) - 1;
+                let t = x - knots[i];
+                y[i] + t * (coe[(i, 0)] + t * (coe[(i, 1)] + t * coe[(i, 2)]))
+            }
+        }
+    }
+}
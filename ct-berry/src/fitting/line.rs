@@ -0,0 +1,74 @@
+//! 总体最小二乘 (正交回归) 直线拟合.
+
+use crate::Idx2dF;
+
+/// 以总体最小二乘法拟合直线, 返回单位法向量 `(a, b)` 和截距 `c`.
+///
+/// 直线经过质心, 法向量为协方差矩阵较小特征值对应的特征向量.
+/// 对于 2x2 对称矩阵, 主轴方向角可以直接由 `atan2` 求得.
+pub(crate) fn fit_line(points: &[Idx2dF]) -> (f64, f64, f64) {
+    assert!(points.len() >= 2, "拟合直线至少需要 2 个点");
+    let n = points.len() as f64;
+    let (mx, my) = points
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+    let (mx, my) = (mx / n, my / n);
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for &(x, y) in points {
+        let (dx, dy) = (x - mx, y - my);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+    }
+    assert!(sxx + syy > 0.0, "所有点重合, 无法确定直线");
+
+    // 主轴 (较大特征值) 方向.
+    let theta = 0.5 * f64::atan2(2.0 * sxy, sxx - syy);
+    let (mut a, mut b) = (-theta.sin(), theta.cos());
+    // 规范化符号: 第一个非零分量为正.
+    if a < 0.0 || (a == 0.0 && b < 0.0) {
+        (a, b) = (-a, -b);
+    }
+    (a, b, -(a * mx + b * my))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_line((a, b, c): (f64, f64, f64), expected: (f64, f64, f64)) {
+        let (ea, eb, ec) = expected;
+        assert!((a * a + b * b - 1.0).abs() < 1e-12);
+        assert!(
+            (a - ea).abs() < 1e-9 && (b - eb).abs() < 1e-9 && (c - ec).abs() < 1e-9,
+            "{:?} vs {expected:?}",
+            (a, b, c)
+        );
+    }
+
+    #[test]
+    fn test_fit_line_tls() {
+        // y = 2x + 1, 即 2x - y + 1 = 0.
+        let points: Vec<_> = (-5..=5).map(|x| (x as f64, 2.0 * x as f64 + 1.0)).collect();
+        let k = 5f64.sqrt();
+        assert_line(fit_line(&points), (2.0 / k, -1.0 / k, 1.0 / k));
+
+        // 竖直线 x = 3.
+        let points: Vec<_> = (0..10).map(|y| (3.0, y as f64)).collect();
+        assert_line(fit_line(&points), (1.0, 0.0, -3.0));
+
+        // 水平线 y = -2.
+        let points: Vec<_> = (0..10).map(|x| (x as f64, -2.0)).collect();
+        assert_line(fit_line(&points), (0.0, 1.0, 2.0));
+
+        // 对称噪声不影响结果: 点到直线 x + y = 4 的正交偏移相互抵消.
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let points: Vec<_> = (0..10)
+            .flat_map(|i| {
+                let t = i as f64;
+                [0.1, -0.1].map(|off| (2.0 + t * s + off * s, 2.0 - t * s + off * s))
+            })
+            .collect();
+        assert_line(fit_line(&points), (s, s, -4.0 * s));
+    }
+}
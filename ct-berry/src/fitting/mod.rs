@@ -2,15 +2,25 @@
 //!
 //! 给定一系列点 `(x, y)`, 该模块可以拟合出一条曲线.
 
+use crate::Idx2dF;
 use ndarray::ArrayView1;
 
+mod bezier;
+mod chebyshev;
 mod cubic_spline;
+mod handle;
+mod line;
+mod parametric;
 mod polynomial;
 
+pub use handle::FittedCurve;
+pub use polynomial::PolyFitter;
+
 type VecPair<T> = (Vec<T>, Vec<T>);
 
 /// 曲线类型.
 #[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
 pub enum CurveType {
     /// 多项式.
     Polynomial {
@@ -18,8 +28,25 @@ pub enum CurveType {
         degree: u32,
     },
 
+    /// 直线 (普通最小二乘法). 结果与 `Polynomial { degree: 1 }` 相同,
+    /// 但使用闭式解, 不需要矩阵求逆. 见 [`linear_f64`].
+    Linear,
+
     /// 三次样条曲线.
     CubicSpline,
+
+    /// 分段三次 Bézier 曲线. 见 [`bezier_f64`].
+    Bezier {
+        /// 曲线段数.
+        segments: u32,
+    },
+
+    /// Chebyshev 基下的多项式. 与 `Polynomial` 拟合的是同一类曲线,
+    /// 但数值上更稳定, 适合较高的次数. 见 [`chebyshev_f64`].
+    Chebyshev {
+        /// 多项式的次数.
+        degree: u32,
+    },
 }
 
 // Q: 用宏替代?
@@ -51,6 +78,44 @@ pub fn polynomial_f64(
     polynomial::PolyImp::<f64>::new(x.view(), y.view(), degree, points).make_curve()
 }
 
+/// 同 [`polynomial_f64`], 但同时返回每个拟合点处置信水平为 `confidence`
+/// (如 `0.95`) 的普通最小二乘预测区间 `(下界, 上界)`.
+///
+/// 区间宽度由残差方差和该点的杠杆值 (Vandermonde 矩阵意义下) 共同决定:
+/// 输入点越稀疏的区域 (以及区间以外的外推区域), 区间越宽, 拟合越不可信.
+///
+/// 除 [`polynomial_f64`] 的要求以外, 输入点数必须多于 `degree + 1`,
+/// `confidence` 必须位于 `(0, 1)` 之间, 否则程序 panic.
+pub fn polynomial_f64_with_ci(
+    x: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    degree: u32,
+    points: u32,
+    confidence: f64,
+) -> (VecPair<f64>, Vec<(f64, f64)>) {
+    polynomial::PolyImp::<f64>::new(x.view(), y.view(), degree, points)
+        .make_curve_with_ci(confidence)
+}
+
+/// 拟合三次样条曲线, 返回可以在任意位置求值的 [`FittedCurve`].
+///
+/// 曲线与 [`cubic_spline_f64`] 相同, 但不做加密采样. 参数要求同 [`cubic_spline_f64`]
+/// (不含 `k`), 不满足时程序 panic.
+pub fn fit_cubic_spline_handle(x: ArrayView1<f64>, y: ArrayView1<f64>) -> FittedCurve {
+    // `k` 不影响样条系数, 取最小合法值即可.
+    let coe = cubic_spline::CubicSplineImp::<f64>::new(x.view(), y.view(), 10).coefficients();
+    FittedCurve::spline(x.to_vec(), y.to_vec(), coe)
+}
+
+/// 基于最小二乘法拟合 n 次多项式曲线, 返回可以在任意位置求值的 [`FittedCurve`].
+///
+/// 曲线与 [`polynomial_f64`] 相同, 但不做采样. 参数要求同 [`polynomial_f64`]
+/// (不含 `points`), 不满足时程序 panic.
+pub fn fit_polynomial_handle(x: ArrayView1<f64>, y: ArrayView1<f64>, degree: u32) -> FittedCurve {
+    let coef = polynomial::PolyImp::<f64>::new(x.view(), y.view(), degree, 3).coefficients();
+    FittedCurve::polynomial(coef)
+}
+
 /// 基于最小二乘法拟合 n 次多项式曲线.
 ///
 /// `x` 是自变量数组, `y` 是对应函数值, `degree` 是多项式次数 (最小为 1).
@@ -63,3 +128,328 @@ pub fn polynomial_f32(
 ) -> VecPair<f32> {
     polynomial::PolyImp::<f32>::new(x.view(), y.view(), degree, points).make_curve()
 }
+
+/// 以 Chebyshev 多项式为基, 基于最小二乘法拟合 n 次多项式曲线.
+///
+/// 单项式基 `1, x, x², ...` 在次数较高 (如大于 4) 或 `x` 的取值较大 (如像素坐标) 时
+/// 严重病态, 拟合结果可能完全失真. 该函数先把 `x` 线性映射到 `[-1, 1]`,
+/// 再以近似正交的 Chebyshev 多项式 `T_0, T_1, ..., T_degree` 为基求解,
+/// 条件数远小于 [`polynomial_f64`].
+///
+/// `x` 是自变量数组 (不能全部相同), `y` 是对应函数值, `degree` 是多项式次数 (最小为 1).
+/// 曲线在 `x` 的区间内等距采样 `points` 个点 (`points` >= 3), 首尾两点恰为区间端点.
+/// 若不同的 `x` 值少于 `degree + 1` 个, 则程序 panic; 见 [`try_chebyshev_f64`].
+pub fn chebyshev_f64(
+    x: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    degree: u32,
+    points: u32,
+) -> VecPair<f64> {
+    try_chebyshev_f64(x, y, degree, points).expect("不同的 x 值不足以确定多项式")
+}
+
+/// 同 [`chebyshev_f64`], 但在不同的 `x` 值少于 `degree + 1` 个时返回 `None`, 而不是 panic.
+pub fn try_chebyshev_f64(
+    x: ArrayView1<f64>,
+    y: ArrayView1<f64>,
+    degree: u32,
+    points: u32,
+) -> Option<VecPair<f64>> {
+    chebyshev::ChebImp::new(x.view(), y.view(), degree, points).make_curve()
+}
+
+/// 基于普通最小二乘法拟合直线.
+///
+/// 参数含义及返回值同 [`polynomial_f64`] (其中 `degree` 为 1), 但使用闭式解
+/// 直接求出斜率和截距, 避免了构造 Vandermonde 矩阵及求逆的开销.
+/// 若所有 `x` 都相同, 则程序 panic.
+pub fn linear_f64(x: ArrayView1<f64>, y: ArrayView1<f64>, points: u32) -> VecPair<f64> {
+    polynomial::PolyImp::<f64>::new(x.view(), y.view(), 1, points).make_line()
+}
+
+/// 以累计弦长 `t` 为参数拟合平面曲线.
+///
+/// 与 [`polynomial_f64`], [`cubic_spline_f64`] 把 `y` 视为 `x` 的函数不同,
+/// 该函数分别拟合 `x(t)` 和 `y(t)`, 因此可以处理 "折返" 的轮廓 (同一个 `x`
+/// 对应多个 `y`), 甚至首尾相连的闭合轮廓. 与前一点重合的点会被忽略.
+///
+/// `samples` 的含义与 `curve` 对应的底层函数一致: 对于多项式 (及直线, Chebyshev 基多项式),
+/// 它是等距采样的
+/// `t` 的个数 (`samples` >= 3); 对于三次样条, 它是相邻两点之间的拟合点数量
+/// (`samples` >= 10); 对于 Bézier 曲线 (其本身即是参数曲线), 该函数等价于
+/// [`bezier_f64`], `samples` 是每段曲线的采样点数.
+///
+/// 返回值为按 `t` 递增排列的拟合点 `(x, y)`.
+pub fn fit_parametric_f64(points: &[Idx2dF], curve: CurveType, samples: u32) -> VecPair<f64> {
+    parametric::fit_parametric(points, curve, samples)
+}
+
+/// 以 `segments` 段三次 Bézier 曲线拟合平面曲线 `points`.
+///
+/// 曲线以累计弦长为参数 (与前一点重合的点会被忽略), 参数区间被等分为 `segments` 段.
+/// 曲线经过首尾两点, 其余所有控制点由最小二乘法估计,
+/// 并通过 Newton 法迭代修正每个点对应的曲线参数.
+/// 拟合至少需要 `3 * segments + 1` 个不重合的点, 且每一段中都应有足够的点,
+/// 否则程序 panic.
+///
+/// 相邻两段共享端点, 因此曲线整体是 C0 连续的; 但算法不约束连接处的切线,
+/// 所以曲线一般不是 C1 连续的. 对于光滑的输入点, 连接处的切线通常近似连续.
+///
+/// 每段曲线按参数等距采样 `samples_per_segment` 个点, 最后补上终点,
+/// 共返回 `segments * samples_per_segment + 1` 个点 `(x, y)`.
+pub fn bezier_f64(points: &[Idx2dF], segments: u32, samples_per_segment: u32) -> VecPair<f64> {
+    bezier::BezierImp::fit(points, segments).make_curve(samples_per_segment)
+}
+
+/// 同 [`bezier_f64`], 但在 `segments` 为 0, 不重合的点少于 `3 * segments + 1` 个,
+/// 或某一段中的点不足以确定其控制点时返回 `None`, 而不是 panic.
+pub fn try_bezier_f64(
+    points: &[Idx2dF],
+    segments: u32,
+    samples_per_segment: u32,
+) -> Option<VecPair<f64>> {
+    bezier::BezierImp::try_fit(points, segments).map(|b| b.make_curve(samples_per_segment))
+}
+
+/// 以总体最小二乘法 (正交回归) 拟合直线, 可用于估计点集的主轴方向.
+///
+/// 与普通最小二乘法最小化竖直方向的残差不同, 该方法最小化点到直线的垂直距离之和,
+/// 因此对竖直的直线同样适用. 拟合的直线经过点集的质心, 其法向量为点集协方差矩阵
+/// 较小特征值对应的特征向量.
+///
+/// 返回值 `(a, b, c)` 表示直线 `ax + by + c = 0`, 其中 `a^2 + b^2 = 1`,
+/// 且 `(a, b)` 中第一个非零分量为正. 若点数少于 2 或所有点重合, 则程序 panic.
+pub fn fit_line_tls(points: &[Idx2dF]) -> (f64, f64, f64) {
+    line::fit_line(points)
+}
+
+/// [`residuals_f64`] 计算三次样条残差时, 相邻两点之间的加密点数量.
+const RESIDUAL_SPLINE_K: u32 = 10;
+
+/// [`residuals_f64`] 计算 Bézier 曲线残差时, 每段曲线的采样点数.
+const RESIDUAL_BEZIER_SAMPLES: u32 = 200;
+
+/// 以 `curve` 拟合 `(x, y)`, 返回每个输入点处的带符号竖直残差 (观测值减去拟合值),
+/// 用于诊断拟合在哪里偏离最大 (如结节状的凸起处).
+///
+/// 各曲线类型的残差定义如下:
+///
+/// - 多项式 (及直线, Chebyshev 基多项式): `y - p(x)`. 对于良好的最小二乘拟合, 残差之和接近 0;
+/// - 三次样条: 插值样条经过每个点, 残差恒为 0 而没有诊断意义.
+///   因此改为返回每个点的值与两侧相邻加密点 (相邻两点之间取 10 个) 上样条值的平均之差,
+///   该值反映曲线在该点附近的局部弯曲程度. 自然样条在首尾两点的二阶导数为 0, 残差固定为 0;
+/// - Bézier 曲线: 该曲线是参数曲线, 因此取加密曲线上离每个点最近的采样点,
+///   返回两者在 y 方向上的差值.
+///
+/// 参数要求与 [`polynomial_f64`], [`chebyshev_f64`], [`cubic_spline_f64`] 和 [`bezier_f64`] 一致,
+/// 不满足时程序 panic.
+pub fn residuals_f64(x: ArrayView1<f64>, y: ArrayView1<f64>, curve: CurveType) -> Vec<f64> {
+    match curve {
+        // `points` 不影响残差, 取最小合法值即可.
+        CurveType::Polynomial { degree } => {
+            polynomial::PolyImp::<f64>::new(x.view(), y.view(), degree, 3).residuals()
+        }
+        // 闭式解与最小二乘解相同.
+        CurveType::Linear => polynomial::PolyImp::<f64>::new(x.view(), y.view(), 1, 3).residuals(),
+        CurveType::CubicSpline => {
+            cubic_spline::CubicSplineImp::<f64>::new(x.view(), y.view(), RESIDUAL_SPLINE_K)
+                .knot_residuals()
+        }
+        CurveType::Chebyshev { degree } => chebyshev::ChebImp::new(x.view(), y.view(), degree, 3)
+            .residuals()
+            .expect("不同的 x 值不足以确定多项式"),
+        CurveType::Bezier { segments } => {
+            assert_eq!(x.len(), y.len(), "x 值和 y 值必须一一对应");
+            let points: Vec<Idx2dF> = x.iter().copied().zip(y.iter().copied()).collect();
+            bezier::BezierImp::fit(&points, segments).residuals(&points, RESIDUAL_BEZIER_SAMPLES)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array1;
+
+    #[test]
+    fn test_residuals() {
+        // 二次曲线加上对称的扰动, 二次多项式拟合的残差之和应接近 0.
+        let x = Array1::linspace(0.0, 10.0, 41);
+        let noise = |i: usize| if i % 2 == 0 { 0.05 } else { -0.05 };
+        let y = Array1::from_shape_fn(41, |i| 0.3 * x[i] * x[i] - x[i] + 2.0 + noise(i));
+
+        let res = residuals_f64(x.view(), y.view(), CurveType::Polynomial { degree: 2 });
+        assert_eq!(res.len(), 41);
+        assert!(res.iter().sum::<f64>().abs() < 1e-8);
+        assert!(res.iter().all(|r| r.abs() < 0.1), "{res:?}");
+
+        // 在 x = 5 处加入一个凸起, 线性拟合的最大残差出现在凸起处.
+        let mut bumped = Array1::from_shape_fn(41, |i| 0.5 * x[i]);
+        bumped[20] += 3.0;
+        let res = residuals_f64(x.view(), bumped.view(), CurveType::Polynomial { degree: 1 });
+        let worst = (0..41).max_by(|&a, &b| res[a].total_cmp(&res[b])).unwrap();
+        assert_eq!(worst, 20);
+
+        // 三次样条: 直线上无弯曲, 凸起处的局部残差最大且为正.
+        let line = Array1::from_shape_fn(41, |i| 0.5 * x[i]);
+        let res = residuals_f64(x.view(), line.view(), CurveType::CubicSpline);
+        assert!(res.iter().all(|r| r.abs() < 1e-9), "{res:?}");
+        assert_eq!((res[0], res[40]), (0.0, 0.0));
+        let res = residuals_f64(x.view(), bumped.view(), CurveType::CubicSpline);
+        let worst = (0..41)
+            .max_by(|&a, &b| res[a].abs().total_cmp(&res[b].abs()))
+            .unwrap();
+        assert_eq!(worst, 20);
+        assert!(res[20] > 0.0);
+        // 首尾节点残差为 0, 即使曲线在端点附近弯曲.
+        let res = residuals_f64(x.view(), y.view(), CurveType::CubicSpline);
+        assert_eq!((res[0], res[40]), (0.0, 0.0));
+        assert!(res[1..40].iter().all(|r| r.abs() > 0.0));
+
+        // Bézier: 采样自曲线本身的点残差很小.
+        let res = residuals_f64(x.view(), line.view(), CurveType::Bezier { segments: 2 });
+        assert!(res.iter().all(|r| r.abs() < 1e-6), "{res:?}");
+    }
+
+    #[test]
+    fn test_linear_matches_polynomial() {
+        let x = Array1::linspace(-3.0, 7.0, 2000);
+        let y = x.mapv(|v: f64| 1.5 * v - 2.0 + (v * 13.0).sin() * 0.3);
+
+        let (lx, ly) = linear_f64(x.view(), y.view(), 50);
+        let (px, py) = polynomial_f64(x.view(), y.view(), 1, 50);
+        assert_eq!(lx, px);
+        for (l, p) in ly.iter().zip(&py) {
+            assert!((l - p).abs() < 1e-9, "{l} vs {p}");
+        }
+
+        let res = residuals_f64(x.view(), y.view(), CurveType::Linear);
+        let poly = residuals_f64(x.view(), y.view(), CurveType::Polynomial { degree: 1 });
+        assert!(res.iter().zip(&poly).all(|(a, b)| (a - b).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_poly_fitter() {
+        let x = Array1::linspace(0.0, 20.0, 200);
+        let ys: Vec<Array1<f64>> = (0..200)
+            .map(|k| x.mapv(|v: f64| 0.01 * v.powi(3) - 0.2 * k as f64 * v + (v + k as f64).cos()))
+            .collect();
+        let fitter = PolyFitter::new(x.view(), 3, 40);
+        for y in ys.iter() {
+            let (fx, fy) = fitter.fit(y.view());
+            let (px, py) = polynomial_f64(x.view(), y.view(), 3, 40);
+            assert_eq!(fx, px);
+            for (f, p) in fy.iter().zip(&py) {
+                assert!((f - p).abs() < 1e-6, "{f} vs {p}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_polynomial_ci() {
+        // 输入点集中在 [0, 3] 和 [7, 10], 中间留有空隙.
+        let x: Array1<f64> = (0..=30)
+            .map(|i| i as f64 / 10.0)
+            .chain((70..=100).map(|i| i as f64 / 10.0))
+            .collect();
+        let y = x.mapv(|v| 0.5 * v * v - 2.0 * v + 1.0 + 0.3 * (v * 37.0).sin());
+
+        let ((cx, cy), ci) = polynomial_f64_with_ci(x.view(), y.view(), 2, 11, 0.95);
+        let (px, py) = polynomial_f64(x.view(), y.view(), 2, 11);
+        assert_eq!(cx, px);
+        assert!(cy.iter().zip(&py).all(|(a, b)| (a - b).abs() < 1e-8));
+        assert_eq!(ci.len(), cy.len());
+        for (&y, &(lo, hi)) in cy.iter().zip(&ci) {
+            assert!(lo < y && y < hi);
+            assert!(((y - lo) - (hi - y)).abs() < 1e-9);
+        }
+
+        // 采样点依次为 0, 1, ..., 10. 空隙中央 (x = 5) 的区间比密集区域 (x = 1, 9) 更宽.
+        let width = |i: usize| ci[i].1 - ci[i].0;
+        assert!(width(5) > width(1) && width(5) > width(9), "{ci:?}");
+        // 置信水平越高, 区间越宽.
+        let (_, ci99) = polynomial_f64_with_ci(x.view(), y.view(), 2, 11, 0.99);
+        assert!((0..11).all(|i| ci99[i].1 - ci99[i].0 > width(i)));
+    }
+
+    #[test]
+    fn test_chebyshev_high_degree() {
+        // 像素坐标量级的 x 上的 8 次曲线 (以 [-1, 1] 上的 Chebyshev 多项式构造).
+        let x = Array1::linspace(200.0, 400.0, 60);
+        let truth = |x: f64| {
+            let u = (x - 300.0) / 100.0;
+            let t8 = (8.0 * u.acos()).cos();
+            50.0 * t8 + 20.0 * u.powi(3) - 5.0 * u + 300.0
+        };
+        let y = x.mapv(truth);
+
+        let (cx, cy) = chebyshev_f64(x.view(), y.view(), 8, 101);
+        assert_eq!(cx.len(), 101);
+        assert_eq!((cx[0], cx[100]), (200.0, 400.0));
+        let cheb_err = cx
+            .iter()
+            .zip(&cy)
+            .map(|(&x, &y)| (y - truth(x)).abs())
+            .fold(0.0, f64::max);
+        assert!(cheb_err < 1e-6, "{cheb_err}");
+        let res = residuals_f64(x.view(), y.view(), CurveType::Chebyshev { degree: 8 });
+        assert!(res.iter().all(|r| r.abs() < 1e-6));
+
+        // 同样的数据, 单项式基的法方程严重病态, 拟合结果明显偏离.
+        let (px, py) = polynomial_f64(x.view(), y.view(), 8, 101);
+        let poly_err = px
+            .iter()
+            .zip(&py)
+            .map(|(&x, &y)| (y - truth(x)).abs())
+            .fold(0.0, f64::max);
+        assert!(poly_err > 1.0, "{poly_err}");
+        assert!(poly_err > 1e6 * cheb_err);
+    }
+
+    #[test]
+    fn test_chebyshev_rank_deficient() {
+        // 6 个点但只有 3 个不同的 x: 二次曲线可以确定, 三次及以上不行.
+        let x = Array1::from(vec![0.0, 0.0, 1.0, 1.0, 2.0, 2.0]);
+        let y = x.mapv(|v: f64| v * v - 1.0);
+        let (cx, cy) = try_chebyshev_f64(x.view(), y.view(), 2, 5).unwrap();
+        for (&x, &y) in cx.iter().zip(&cy) {
+            assert!((y - (x * x - 1.0)).abs() < 1e-9);
+        }
+        assert!(try_chebyshev_f64(x.view(), y.view(), 3, 5).is_none());
+        assert!(try_chebyshev_f64(x.view(), y.view(), 8, 5).is_none());
+    }
+
+    #[test]
+    fn test_fitted_curve_eval() {
+        // 三次样条: 在节点处重现输入, 在节点之间与加密采样的结果一致.
+        let x = Array1::from(vec![0.0, 1.0, 2.5, 4.0, 6.0, 7.0]);
+        let y = x.mapv(|v: f64| (v * 0.8).sin() * 3.0 + v);
+        let spline = fit_cubic_spline_handle(x.view(), y.view());
+        for (&kx, &ky) in x.iter().zip(y.iter()) {
+            assert!((spline.eval(kx) - ky).abs() < 1e-12);
+        }
+        let (sx, sy) = cubic_spline_f64(x.view(), y.view(), 10);
+        for (&px, &py) in sx.iter().zip(&sy) {
+            assert!((spline.eval(px) - py).abs() < 1e-9, "{px}");
+        }
+
+        // x 严格递减时同样适用.
+        let rx = x.slice(ndarray::s![..;-1]);
+        let ry = y.slice(ndarray::s![..;-1]);
+        let reversed = fit_cubic_spline_handle(rx, ry);
+        let (sx, sy) = cubic_spline_f64(rx, ry, 10);
+        for (&px, &py) in sx.iter().zip(&sy) {
+            assert!((reversed.eval(px) - py).abs() < 1e-9, "{px}");
+        }
+
+        // 多项式: 与采样结果一致, 且能精确还原生成数据的多项式.
+        let x = Array1::linspace(-2.0, 3.0, 30);
+        let y = x.mapv(|v: f64| 0.5 * v.powi(3) - v * v + 2.0);
+        let poly = fit_polynomial_handle(x.view(), y.view(), 3);
+        let (px, py) = polynomial_f64(x.view(), y.view(), 3, 11);
+        for (&px, &py) in px.iter().zip(&py) {
+            assert!((poly.eval(px) - py).abs() < 1e-9, "{px}");
+        }
+        assert!((poly.eval(10.0) - 402.0).abs() < 1e-6);
+    }
+}
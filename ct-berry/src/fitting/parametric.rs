@@ -0,0 +1,118 @@
+//! 参数曲线.
+//!
+//! 以累计弦长 `t` 为参数, 分别拟合 `x(t)` 和 `y(t)`.
+//! 这样即使轮廓 "折返" (同一个 `x` 对应多个 `y`) 也能被正确表示.
+
+use super::{
+    bezier_f64, chebyshev_f64, cubic_spline_f64, linear_f64, polynomial_f64, CurveType, VecPair,
+};
+use crate::Idx2dF;
+use ndarray::ArrayView1;
+
+/// 计算 `points` 的累计弦长参数, 同时剔除与前一点重合的点 (它们会使 `t` 不严格递增).
+///
+/// 返回值为 `(t, x, y)`, 三者长度相同, 且 `t[0] == 0.0`.
+pub(crate) fn chord_length_params(points: &[Idx2dF]) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut t = Vec::with_capacity(points.len());
+    let mut x = Vec::with_capacity(points.len());
+    let mut y = Vec::with_capacity(points.len());
+
+    let mut acc = 0.0;
+    for &(px, py) in points {
+        if let (Some(&lx), Some(&ly)) = (x.last(), y.last()) {
+            let d = f64::hypot(px - lx, py - ly);
+            if d == 0.0 {
+                continue;
+            }
+            acc += d;
+        }
+        t.push(acc);
+        x.push(px);
+        y.push(py);
+    }
+    (t, x, y)
+}
+
+/// 见 [`super::fit_parametric_f64`].
+pub(crate) fn fit_parametric(points: &[Idx2dF], curve: CurveType, samples: u32) -> VecPair<f64> {
+    let (t, x, y) = chord_length_params(points);
+    assert!(t.len() >= 2, "至少需要拟合两个不重合的点");
+
+    let t = ArrayView1::from(t.as_slice());
+    let x = ArrayView1::from(x.as_slice());
+    let y = ArrayView1::from(y.as_slice());
+
+    // 两次拟合使用相同的 `t` 采样点, 因此结果可以直接按下标配对.
+    let (fx, fy) = match curve {
+        CurveType::Polynomial { degree } => (
+            polynomial_f64(t, x, degree, samples).1,
+            polynomial_f64(t, y, degree, samples).1,
+        ),
+        CurveType::Linear => (linear_f64(t, x, samples).1, linear_f64(t, y, samples).1),
+        CurveType::Chebyshev { degree } => (
+            chebyshev_f64(t, x, degree, samples).1,
+            chebyshev_f64(t, y, degree, samples).1,
+        ),
+        CurveType::CubicSpline => (
+            cubic_spline_f64(t, x, samples).1,
+            cubic_spline_f64(t, y, samples).1,
+        ),
+        // Bézier 曲线本身即是以弦长为参数拟合的.
+        CurveType::Bezier { segments } => bezier_f64(points, segments, samples),
+    };
+    debug_assert_eq!(fx.len(), fy.len());
+    (fx, fy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// 半径为 `r` 的圆上均匀分布的 `n` 个点 (首尾相连).
+    fn circle(r: f64, n: usize) -> Vec<Idx2dF> {
+        (0..=n)
+            .map(|i| {
+                let a = 2.0 * PI * i as f64 / n as f64;
+                (r * a.cos(), r * a.sin())
+            })
+            .collect()
+    }
+
+    fn max_radial_error(x: &[f64], y: &[f64], r: f64) -> f64 {
+        x.iter()
+            .zip(y)
+            .map(|(x, y)| (f64::hypot(*x, *y) - r).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_chord_length_params_skip_duplicates() {
+        let (t, x, _) = chord_length_params(&[(0.0, 0.0), (0.0, 0.0), (3.0, 4.0), (3.0, 5.0)]);
+        assert_eq!(t, vec![0.0, 5.0, 6.0]);
+        assert_eq!(x, vec![0.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn test_parametric_circle() {
+        let pts = circle(10.0, 24);
+
+        let (x, y) = fit_parametric(&pts, CurveType::CubicSpline, 10);
+        assert!(x.len() > pts.len());
+        assert!(max_radial_error(&x, &y, 10.0) < 0.1);
+
+        let (x, y) = fit_parametric(&pts, CurveType::Polynomial { degree: 8 }, 200);
+        assert!(max_radial_error(&x, &y, 10.0) < 0.5);
+
+        // 以 x 为自变量的拟合无法表示圆: 同一个 x 对应上下两个 y.
+        let vx: Vec<f64> = pts.iter().map(|p| p.0).collect();
+        let vy: Vec<f64> = pts.iter().map(|p| p.1).collect();
+        let (fx, fy) = polynomial_f64(
+            ArrayView1::from(vx.as_slice()),
+            ArrayView1::from(vy.as_slice()),
+            8,
+            200,
+        );
+        assert!(max_radial_error(&fx, &fy, 10.0) > 5.0);
+    }
+}
@@ -2,6 +2,7 @@
 
 // ref: https://blog.csdn.net/u012494154/article/details/112519550
 
+use super::VecPair;
 use ndarray::{Array1, Array2, ArrayView1};
 use ndarray_linalg::Inverse;
 
@@ -61,20 +62,11 @@ macro_rules! impl_poly_imp {
             }
 
             pub fn make_curve(&self) -> (Vec<$fp>, Vec<$fp>) {
-                let v_mat = self.vandermonde();
-                let v_mat_t = v_mat.t();
-
-                let theta = v_mat
-                    .t()
-                    .dot(&v_mat)
-                    .inv()
-                    .unwrap()
-                    .dot(&v_mat_t)
-                    .dot(&self.y);
-
-                debug_assert_eq!(theta.len(), (self.degree + 1) as usize);
+                self.sample(&self.fit())
+            }
 
-                let poly = Polynomial(theta);
+            /// 在区间内等距地对 `poly` 采样.
+            fn sample(&self, poly: &Polynomial<$fp>) -> (Vec<$fp>, Vec<$fp>) {
                 let step = self.step();
                 let mut input = self.minmax.0;
                 let mut ans_x = Vec::with_capacity(self.points as usize);
@@ -91,6 +83,23 @@ macro_rules! impl_poly_imp {
                 (ans_x, ans_y)
             }
 
+            /// 最小二乘法求解多项式系数.
+            fn fit(&self) -> Polynomial<$fp> {
+                let v_mat = self.vandermonde();
+                let v_mat_t = v_mat.t();
+
+                let theta = v_mat
+                    .t()
+                    .dot(&v_mat)
+                    .inv()
+                    .unwrap()
+                    .dot(&v_mat_t)
+                    .dot(&self.y);
+
+                debug_assert_eq!(theta.len(), (self.degree + 1) as usize);
+                Polynomial(theta)
+            }
+
             fn vandermonde(&self) -> Array2<$fp> {
                 // shape: (m, n); m = x.len(), n = self.degree + 1
                 Array2::<$fp>::from_shape_fn((self.x.len(), self.degree as usize + 1), |(m, n)| {
@@ -122,3 +131,326 @@ macro_rules! impl_poly_imp {
 
 impl_poly_imp!(f32);
 impl_poly_imp!(f64);
+
+impl PolyImp<'_, f64> {
+    /// 以闭式解求普通最小二乘直线 (要求 `degree == 1`), 其采样方式与 `make_curve` 完全一致.
+    ///
+    /// 与 `make_curve` 相比, 该方法不需要构造 Vandermonde 矩阵及求逆.
+    /// 若所有 `x` 都相同, 则程序 panic.
+    pub fn make_line(&self) -> (Vec<f64>, Vec<f64>) {
+        assert_eq!(self.degree, 1, "直线拟合的次数必须为 1");
+        let n = self.x.len() as f64;
+        let mean_x = self.x.sum() / n;
+        let mean_y = self.y.sum() / n;
+        let (mut sxx, mut sxy) = (0.0, 0.0);
+        for (&x, &y) in self.x.iter().zip(self.y.iter()) {
+            let dx = x - mean_x;
+            sxx += dx * dx;
+            sxy += dx * (y - mean_y);
+        }
+        assert!(sxx > 0.0, "所有 x 值都相同, 无法拟合直线");
+        let slope = sxy / sxx;
+        let poly = Polynomial(Array1::from(vec![mean_y - slope * mean_x, slope]));
+        self.sample(&poly)
+    }
+
+    /// 最小二乘多项式的系数, 按次数升序排列.
+    pub fn coefficients(&self) -> Vec<f64> {
+        self.fit().0.to_vec()
+    }
+
+    /// 每个输入点处的带符号竖直残差 `y - p(x)`.
+    pub fn residuals(&self) -> Vec<f64> {
+        let poly = self.fit();
+        self.x
+            .iter()
+            .zip(self.y.iter())
+            .map(|(&x, &y)| y - poly.eval(x))
+            .collect()
+    }
+}
+
+impl PolyImp<'_, f64> {
+    /// 同 `make_curve`, 但同时返回每个采样点处置信水平为 `confidence` 的预测区间
+    /// `(下界, 上界)`.
+    ///
+    /// 区间为 `ŷ ± t * s * sqrt(1 + x₀ᵀ(VᵀV)⁻¹x₀)`, 其中 `s²` 是残差平方和除以自由度
+    /// `n - degree - 1`, `t` 是该自由度下 Student t 分布的双侧分位数,
+    /// `x₀ = (1, x, x², ...)` 是采样点对应的 Vandermonde 行向量.
+    pub fn make_curve_with_ci(&self, confidence: f64) -> (VecPair<f64>, Vec<(f64, f64)>) {
+        assert!(
+            0.0 < confidence && confidence < 1.0,
+            "置信水平必须位于 (0, 1) 之间"
+        );
+        let (n, p) = (self.x.len(), self.degree as usize + 1);
+        assert!(n > p, "计算预测区间需要多于 `degree + 1` 个点");
+
+        let v_mat = self.vandermonde();
+        let cov = v_mat.t().dot(&v_mat).inv().unwrap();
+        let poly = Polynomial(cov.dot(&v_mat.t()).dot(&self.y));
+
+        let rss: f64 = self
+            .x
+            .iter()
+            .zip(self.y.iter())
+            .map(|(&x, &y)| (y - poly.eval(x)).powi(2))
+            .sum();
+        let dof = (n - p) as f64;
+        let s = (rss / dof).sqrt();
+        let t = t_quantile(0.5 + confidence / 2.0, dof);
+
+        let (xs, ys) = self.sample(&poly);
+        let bounds = xs
+            .iter()
+            .zip(ys.iter())
+            .map(|(&x0, &y0)| {
+                let row = Array1::from_shape_fn(p, |k| x0.powi(k as i32));
+                let leverage = row.dot(&cov.dot(&row));
+                let half = t * s * (1.0 + leverage).sqrt();
+                (y0 - half, y0 + half)
+            })
+            .collect();
+        ((xs, ys), bounds)
+    }
+}
+
+/// 标准正态分布的分位数 (Acklam 有理逼近, 相对误差约 `1e-9`). `p` 位于 `(0, 1)` 之间.
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.383_577_518_672_69e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// 自由度为 `dof` 的 Student t 分布的分位数. `p` 位于 `(0, 1)` 之间.
+///
+/// 自由度为 1 和 2 时使用闭式解; 否则先以正态分位数为基础的 Cornish-Fisher 展开
+/// (Abramowitz & Stegun 26.7.5) 给出初值, 再对 t 分布的分布函数做 Newton 迭代,
+/// 结果的相对误差在 `1e-10` 以内.
+fn t_quantile(p: f64, dof: f64) -> f64 {
+    if dof == 1.0 {
+        return (std::f64::consts::PI * (p - 0.5)).tan();
+    }
+    if dof == 2.0 {
+        return (2.0 * p - 1.0) / (2.0 * p * (1.0 - p)).sqrt();
+    }
+    if p < 0.5 {
+        return -t_quantile(1.0 - p, dof);
+    }
+
+    let z = normal_quantile(p);
+    let (z2, z3) = (z * z, z * z * z);
+    let (z5, z7, z9) = (z3 * z2, z3 * z2 * z2, z3 * z3 * z3);
+    let mut t = z
+        + (z3 + z) / (4.0 * dof)
+        + (5.0 * z5 + 16.0 * z3 + 3.0 * z) / (96.0 * dof.powi(2))
+        + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * z) / (384.0 * dof.powi(3))
+        + (79.0 * z9 + 776.0 * z7 + 1482.0 * z5 - 1920.0 * z3 - 945.0 * z)
+            / (92160.0 * dof.powi(4));
+
+    // 在上尾概率 `1 - F(t)` 上迭代, 避免 `p` 接近 1 时的相消误差.
+    // `t >= 0` 时分布函数是凹的, 从初值出发的 Newton 迭代单调收敛.
+    let (a, b) = (dof / 2.0, 0.5);
+    let ln_norm = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) - 0.5 * dof.ln();
+    for _ in 0..50 {
+        let upper = 0.5 * inc_beta(dof / (dof + t * t), a, b);
+        let density = (ln_norm - (dof + 1.0) / 2.0 * (t * t / dof).ln_1p()).exp();
+        let next = (t + (upper - (1.0 - p)) / density).max(0.0);
+        let converged = (next - t).abs() <= 1e-12 * t.max(1.0);
+        t = next;
+        if converged {
+            break;
+        }
+    }
+    t
+}
+
+/// `ln Γ(x)`, `x > 0`. Lanczos 近似 (g = 7, n = 9).
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COE: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // 反射公式.
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let sum = COE[1..]
+        .iter()
+        .enumerate()
+        .fold(COE[0], |acc, (i, &c)| acc + c / (x + i as f64 + 1.0));
+    let t = x + G + 0.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// 正则化不完全 Beta 函数 `I_x(a, b)`.
+fn inc_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = a * x.ln() + b * (-x).ln_1p() + ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b);
+    // 连分式在 `x < (a + 1) / (a + b + 2)` 时收敛较快, 否则利用对称性.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_cf(x, a, b) / a
+    } else {
+        1.0 - ln_front.exp() * beta_cf(1.0 - x, b, a) / b
+    }
+}
+
+/// `I_x(a, b)` 的连分式部分, 以修正的 Lentz 方法求值.
+fn beta_cf(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let guard = |v: f64| if v.abs() < TINY { TINY } else { v };
+    let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+    let mut c = 1.0;
+    let mut d = 1.0 / guard(1.0 - qab * x / qap);
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        // 偶数项.
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 / guard(1.0 + aa * d);
+        c = guard(1.0 + aa / c);
+        h *= d * c;
+        // 奇数项.
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 / guard(1.0 + aa * d);
+        c = guard(1.0 + aa / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    h
+}
+
+/// 对固定的自变量 `x` 和多项式次数, 预先计算 Vandermonde 矩阵 `V` 的伪逆
+/// `(VᵀV)⁻¹Vᵀ` 的多项式拟合器.
+///
+/// 当许多组 `y` 共享同一组 `x` 时 (如批量计算 LSN 时, 各段曲线的采样位置相同),
+/// 每次拟合只需一次矩阵-向量乘法, 而不必重复构造矩阵并求逆.
+/// 拟合结果与 [`super::polynomial_f64`] 一致.
+#[derive(Debug, Clone)]
+pub struct PolyFitter {
+    x: Array1<f64>,
+    pinv: Array2<f64>,
+    degree: u32,
+    points: u32,
+}
+
+impl PolyFitter {
+    /// 为自变量 `x` 构造 `degree` 次多项式拟合器, 拟合曲线会在区间内等距采样
+    /// `points` 个点. 参数要求与 [`super::polynomial_f64`] 一致, 不满足时程序 panic.
+    pub fn new(x: ArrayView1<f64>, degree: u32, points: u32) -> Self {
+        // 仅用于校验参数及构造 Vandermonde 矩阵.
+        let v_mat = PolyImp::<f64>::new(x, x, degree, points).vandermonde();
+        let pinv = v_mat.t().dot(&v_mat).inv().unwrap().dot(&v_mat.t());
+        Self {
+            x: x.to_owned(),
+            pinv,
+            degree,
+            points,
+        }
+    }
+
+    /// 拟合一组函数值 `y`, 返回值同 [`super::polynomial_f64`].
+    ///
+    /// 若 `y` 的长度与 `x` 不一致, 则程序 panic.
+    pub fn fit(&self, y: ArrayView1<f64>) -> (Vec<f64>, Vec<f64>) {
+        let imp = PolyImp::<f64>::new(self.x.view(), y.view(), self.degree, self.points);
+        imp.sample(&Polynomial(self.pinv.dot(&y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_t_quantile() {
+        assert!(normal_quantile(0.5).abs() < 1e-12);
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-6);
+        // 查表值.
+        for (p, dof, expected) in [
+            (0.975, 1.0, 12.706205),
+            (0.975, 2.0, 4.302653),
+            (0.975, 3.0, 3.182446),
+            (0.995, 3.0, 5.840909),
+            (0.999, 3.0, 10.214532),
+            (0.9995, 3.0, 12.923979),
+            (0.999, 4.0, 7.173182),
+            (0.995, 5.0, 4.032143),
+            (0.9995, 5.0, 6.868827),
+            (0.975, 10.0, 2.228139),
+            (0.95, 30.0, 1.697261),
+            (0.6, 3.0, 0.276671),
+        ] {
+            let t = t_quantile(p, dof);
+            assert!(
+                (t - expected).abs() < 1e-5,
+                "t({p}, {dof}) = {t}, expected {expected}"
+            );
+            // 对称性.
+            assert!((t_quantile(1.0 - p, dof) + t).abs() < 1e-9);
+        }
+        assert!((ln_gamma(0.5) - std::f64::consts::PI.sqrt().ln()).abs() < 1e-12);
+        assert!((ln_gamma(10.0) - 362880f64.ln()).abs() < 1e-12);
+    }
+}
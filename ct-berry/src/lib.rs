@@ -122,10 +122,13 @@ type Areas2d = Vec<Area2d>;
 mod data;
 
 pub use data::{
-    CompactLabelSlice, CtData3d, CtLabel, CtScan, CtWindow, ImgWriteRaw, ImgWriteVis, LabelSlice,
-    LabelSliceMut, NiftiHeaderAttr, OwnedLabelSlice, OwnedScanSlice, ScanSlice, ScanSliceMut,
+    blend_windows, CenterTiebreak, CompactLabelSlice, ComponentStat, CompressionBackend, CtData3d,
+    CtLabel, CtScan, CtWindow, FakeError, HuStats, ImgWriteRaw, ImgWriteVis, LabelSlice,
+    LabelSliceMut, LiverAttenuation, Mesh, NiftiHeaderAttr, OwnedLabelSlice, OwnedScanSlice,
+    ScanSlice, ScanSliceMut, TumorBurden,
 };
 
+pub use data::mmap;
 pub use data::sector;
 
 pub mod consts;
@@ -13,4 +13,14 @@ pub enum CalcError {
     ///
     /// 第一个参数代表目前已有的点, 第二个参数代表实际拟合需要的最少点数.
     TooFewSamples(u32, u32),
+
+    /// 切片上没有肝脏像素, 无法定位肝左外区.
+    NoLiver,
+
+    /// 拟合曲线类型的参数非法, 如 Bézier 曲线的段数为 0.
+    InvalidCurveType,
+
+    /// 拟合所需的线性方程组无法求解, 如采样点分布过于不均,
+    /// 使得部分 Bézier 曲线段中没有足够的点.
+    FitFailed,
 }
@@ -7,7 +7,7 @@ mod sample;
 use points::RawSurface;
 pub use points::SampledCurve;
 
-pub use sample::{SampleSpec, Spacing};
+pub use sample::{SampleRule, SampleSpec, Spacing};
 
 pub use error::CalcError;
 
@@ -1,6 +1,8 @@
 //! 图像坐标操作.
 
-use crate::fitting::{cubic_spline_f64, polynomial_f64, CurveType};
+use crate::fitting::{
+    cubic_spline_f64, linear_f64, polynomial_f64, try_bezier_f64, try_chebyshev_f64, CurveType,
+};
 use crate::lsn::{CalcError, CalcResult};
 use crate::{Idx2d, Idx2dF};
 use itertools::izip;
@@ -36,6 +38,12 @@ impl<'a> RawSurface<'a> {
         self.points
     }
 
+    /// 水平切片像素分辨率 (单位: 毫米).
+    #[inline]
+    pub fn dim(&self) -> f64 {
+        self.dim
+    }
+
     /// 从一组 `(h, w)` 构建出 `([x], [y])`.
     pub fn as_xy(&self, section: &[Idx2d]) -> (Vec<f64>, Vec<f64>) {
         let mut vx = Vec::with_capacity(section.len());
@@ -69,6 +77,36 @@ impl<'a> RawSurface<'a> {
                 .map(|w| points_distance(w[0], w[1]))
                 .sum::<f64>()
     }
+
+    /// 估计每个点处的局部曲率 (单位: 弧度/毫米, 非负).
+    ///
+    /// 取前后相距 `half_window` 个点的两个点 `a`, `b` (在端点处截断),
+    /// 以向量 `a -> p` 与 `p -> b` 的转角除以两段弦长的平均值作为点 `p` 处的曲率.
+    /// 像素轮廓呈锯齿状, 窗口越大对锯齿越不敏感, 但也会平滑掉更小的结节.
+    /// 若 `a` 或 `b` 与 `p` 重合 (端点处), 则曲率为 0.
+    pub fn local_curvatures(&self, half_window: usize) -> Vec<f64> {
+        let n = self.points.len();
+        let pos = |i: usize| {
+            let (h, w) = self.points[i];
+            (h as f64, w as f64)
+        };
+        (0..n)
+            .map(|i| {
+                let (a, p, b) = (
+                    pos(i.saturating_sub(half_window)),
+                    pos(i),
+                    pos((i + half_window).min(n - 1)),
+                );
+                let (u, v) = ((p.0 - a.0, p.1 - a.1), (b.0 - p.0, b.1 - p.1));
+                let (lu, lv) = (u.0.hypot(u.1), v.0.hypot(v.1));
+                if lu == 0.0 || lv == 0.0 {
+                    return 0.0;
+                }
+                let angle = (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1).abs();
+                angle / ((lu + lv) / 2.0 * self.dim)
+            })
+            .collect()
+    }
 }
 
 /// 计算两个点的欧几里得距离. 如果这两个点不以 8-邻接则 panic.
@@ -192,6 +230,38 @@ impl SampledCurve {
         self.fit_y.as_slice()
     }
 
+    /// 按顺序把多个 section 的曲线合并为一条, 以便在整个肝表面上计算一次 [`Self::lsn`].
+    ///
+    /// 肝表面点, 采样点和拟合点分别按顺序首尾相接. 相邻的 section 在边界处共享一个
+    /// 表面像素 (前一段的终点即后一段的起点), 这种重复的肝表面点和采样点只保留一个;
+    /// 拟合点则全部保留.
+    ///
+    /// # 注意
+    ///
+    /// 拟合是在每个 section 上独立完成的, 合并后的拟合曲线在 section 边界处可能不连续.
+    /// 因此合并后的 LSN 近似于各 section 的 LSN 按肝表面点数加权的平均
+    /// (边界附近的点可能离相邻 section 的拟合点更近), 而不是在整个表面上
+    /// 重新拟合一条曲线得到的结果.
+    pub fn concat(curves: &[SampledCurve]) -> SampledCurve {
+        fn extend(xs: &mut Vec<f64>, ys: &mut Vec<f64>, new_x: &[f64], new_y: &[f64]) {
+            let skip = match (xs.last(), ys.last(), new_x.first(), new_y.first()) {
+                (Some(lx), Some(ly), Some(nx), Some(ny)) => (lx == nx && ly == ny) as usize,
+                _ => 0,
+            };
+            xs.extend_from_slice(&new_x[skip..]);
+            ys.extend_from_slice(&new_y[skip..]);
+        }
+
+        let mut ans = SampledCurve::new();
+        for c in curves {
+            extend(&mut ans.liver_x, &mut ans.liver_y, &c.liver_x, &c.liver_y);
+            extend(&mut ans.samp_x, &mut ans.samp_y, &c.samp_x, &c.samp_y);
+            ans.fit_x.extend_from_slice(&c.fit_x);
+            ans.fit_y.extend_from_slice(&c.fit_y);
+        }
+        ans
+    }
+
     /// 清理多余占用的空间.
     pub(crate) fn shrink_to_fit(&mut self) {
         self.samp_x.shrink_to_fit();
@@ -276,7 +346,34 @@ impl SampledCurve {
                 }
                 polynomial_f64(x_view, y_view, degree, points)
             }
+            CurveType::Linear => linear_f64(x_view, y_view, points),
+            CurveType::Chebyshev { degree } => {
+                // 只有不同的 x 值才能约束多项式.
+                let mut xs = self.samp_x.clone();
+                xs.sort_by(f64::total_cmp);
+                xs.dedup();
+                let distinct = xs.len() as u32;
+                if degree >= distinct {
+                    return Err(CalcError::TooFewSamples(distinct, degree + 1));
+                }
+                try_chebyshev_f64(x_view, y_view, degree, points).ok_or(CalcError::FitFailed)?
+            }
             CurveType::CubicSpline => cubic_spline_f64(x_view, y_view, points),
+            CurveType::Bezier { segments } => {
+                if segments == 0 {
+                    return Err(CalcError::InvalidCurveType);
+                }
+                let samples: Vec<Idx2dF> =
+                    Self::point_f64_iter(&self.samp_x, &self.samp_y).collect();
+                // 与前一点重合的采样点不参与拟合.
+                let distinct = 1 + samples.windows(2).filter(|w| w[0] != w[1]).count() as u32;
+                let need = 3 * segments + 1;
+                if distinct < need {
+                    return Err(CalcError::TooFewSamples(distinct, need));
+                }
+                try_bezier_f64(&samples, segments, (points / segments).max(1))
+                    .ok_or(CalcError::FitFailed)?
+            }
         };
 
         mem::swap(&mut self.fit_x, &mut fit_x);
@@ -302,8 +399,201 @@ impl SampledCurve {
         lsn / self.liver_len() as f64
     }
 
+    /// 把肝表面、拟合曲线和采样点导出为宽 `width`, 高 `height` 的 SVG 文档.
+    ///
+    /// 肝表面 (`liver_*`) 和拟合曲线 (`fit_*`) 各绘制为一条折线, 采样点 (`samp_*`)
+    /// 绘制为圆点. 坐标被等比例缩放到画布上 (四周留白), 且 y 轴朝上,
+    /// 与 `(x, y)` 坐标系一致. 为空的部分不会被绘制.
+    ///
+    /// 若 `width` 或 `height` 为 0, 则程序 panic.
+    pub fn to_svg(&self, width: u32, height: u32) -> String {
+        use std::fmt::Write;
+
+        assert!(width > 0 && height > 0, "画布尺寸不能为 0");
+        const MARGIN: f64 = 10.0;
+
+        let all = || {
+            Self::point_f64_iter(&self.liver_x, &self.liver_y)
+                .chain(Self::point_f64_iter(&self.fit_x, &self.fit_y))
+                .chain(Self::point_f64_iter(&self.samp_x, &self.samp_y))
+        };
+        let (mut x0, mut x1, mut y0, mut y1) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for (x, y) in all() {
+            (x0, x1, y0, y1) = (x0.min(x), x1.max(x), y0.min(y), y1.max(y));
+        }
+
+        // 等比例缩放, 并使图形位于画布中央.
+        let (w, h) = (width as f64, height as f64);
+        let (span_x, span_y) = ((x1 - x0).max(1e-9), (y1 - y0).max(1e-9));
+        let scale = ((w - 2.0 * MARGIN) / span_x)
+            .min((h - 2.0 * MARGIN) / span_y)
+            .max(0.0);
+        let (off_x, off_y) = ((w - span_x * scale) / 2.0, (h - span_y * scale) / 2.0);
+        let tr = |(x, y): Idx2dF| (off_x + (x - x0) * scale, h - off_y - (y - y0) * scale);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\">\n"
+        );
+        let mut polyline = |xs: &[f64], ys: &[f64], color: &str| {
+            if xs.is_empty() {
+                return;
+            }
+            let points = Self::point_f64_iter(xs, ys)
+                .map(|p| {
+                    let (px, py) = tr(p);
+                    format!("{px:.2},{py:.2}")
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                svg,
+                "  <polyline points=\"{points}\" fill=\"none\" stroke=\"{color}\" stroke-width=\"1.5\"/>"
+            )
+            .unwrap();
+        };
+        polyline(&self.liver_x, &self.liver_y, "black");
+        polyline(&self.fit_x, &self.fit_y, "red");
+        for p in Self::point_f64_iter(&self.samp_x, &self.samp_y) {
+            let (cx, cy) = tr(p);
+            writeln!(
+                svg,
+                "  <circle cx=\"{cx:.2}\" cy=\"{cy:.2}\" r=\"2.5\" fill=\"blue\"/>"
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
     #[inline]
     fn point_f64_iter<'a>(x: &'a [f64], y: &'a [f64]) -> impl Iterator<Item = Idx2dF> + 'a {
         izip!(x.iter().copied(), y.iter().copied())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampled_curve_to_svg() {
+        let mut curve = SampledCurve::new();
+        curve.liver_x = (0..20).map(|i| i as f64).collect();
+        curve.liver_y = curve.liver_x.iter().map(|x| (x / 3.0).sin()).collect();
+        curve.samp_x = vec![0.0, 5.0, 10.0, 15.0, 19.0];
+        curve.samp_y = curve.samp_x.iter().map(|x| (x / 3.0).sin()).collect();
+        curve.fit_x = (0..=40).map(|i| i as f64 / 2.0).collect();
+        curve.fit_y = curve.fit_x.iter().map(|x| (x / 3.0).sin() * 0.9).collect();
+
+        let svg = curve.to_svg(400, 300);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<svg").count(), 1);
+        assert_eq!(svg.matches("<polyline ").count(), 2);
+        assert_eq!(svg.matches("<circle ").count(), 5);
+        // 每个元素都是自闭合的.
+        assert_eq!(svg.matches("/>").count(), 7);
+
+        // 所有坐标都位于画布内.
+        for attr in ["cx=\"", "cy=\""] {
+            for part in svg.split(attr).skip(1) {
+                let v: f64 = part.split('"').next().unwrap().parse().unwrap();
+                assert!((0.0..=400.0).contains(&v));
+            }
+        }
+
+        // 空曲线只有画布.
+        let empty = SampledCurve::new().to_svg(10, 10);
+        assert_eq!(empty.matches("<polyline").count(), 0);
+        assert_eq!(empty.matches("<circle").count(), 0);
+    }
+
+    #[test]
+    fn test_sampled_curve_concat() {
+        let part = |xs: std::ops::Range<i32>| {
+            let mut curve = SampledCurve::new();
+            curve.liver_x = xs.map(f64::from).collect();
+            curve.liver_y = curve.liver_x.iter().map(|x| x * 0.5).collect();
+            curve.samp_x = vec![curve.liver_x[0], *curve.liver_x.last().unwrap()];
+            curve.samp_y = curve.samp_x.iter().map(|x| x * 0.5).collect();
+            curve.fit_x = curve.liver_x.clone();
+            curve.fit_y = curve.liver_y.clone();
+            curve
+        };
+
+        // 互不重叠的 section.
+        let parts = [part(0..5), part(5..12), part(12..20)];
+        let whole = SampledCurve::concat(&parts);
+        assert_eq!(
+            whole.liver_len(),
+            parts.iter().map(SampledCurve::liver_len).sum::<usize>()
+        );
+        assert_eq!(whole.liver_x(), (0..20).map(f64::from).collect::<Vec<_>>());
+        assert_eq!(whole.sample_len(), 6);
+        assert_eq!(whole.fit_len(), 20);
+        assert_eq!(whole.lsn(), 0.0);
+
+        // 共享边界点的 section: 边界点只保留一个.
+        let parts = [part(0..6), part(5..13), part(12..20)];
+        let whole = SampledCurve::concat(&parts);
+        assert_eq!(whole.liver_x(), (0..20).map(f64::from).collect::<Vec<_>>());
+        assert_eq!(whole.sample_len(), 4);
+        assert_eq!(whole.fit_len(), 22);
+
+        assert_eq!(SampledCurve::concat(&[]).liver_len(), 0);
+    }
+
+    #[test]
+    fn test_fit_bezier_errors() {
+        let curve = |samp: Vec<(f64, f64)>| {
+            let mut curve = SampledCurve::new();
+            curve.liver_x = (0..=20).map(f64::from).collect();
+            curve.liver_y = vec![0.0; 21];
+            (curve.samp_x, curve.samp_y) = samp.into_iter().unzip();
+            curve
+        };
+        let line: Vec<_> = (0..=20).map(|i| (f64::from(i), 0.0)).collect();
+
+        let mut c = curve(line.clone());
+        assert!(matches!(
+            c.fit(CurveType::Bezier { segments: 0 }, 10, 1.0),
+            Err(CalcError::InvalidCurveType)
+        ));
+        c.fit(CurveType::Bezier { segments: 2 }, 10, 1.0).unwrap();
+        assert!(c.fit_len() > 0);
+
+        // 7 个采样点, 但其中只有 4 个不重合: 不足以拟合两段曲线.
+        let dup = [0.0, 0.0, 5.0, 5.0, 10.0, 10.0, 20.0].map(|x| (x, 0.0));
+        assert!(matches!(
+            curve(dup.to_vec()).fit(CurveType::Bezier { segments: 2 }, 10, 1.0),
+            Err(CalcError::TooFewSamples(4, 7))
+        ));
+
+        // 点数足够, 但中间一段没有任何点, 其控制点无法确定.
+        let mut gap: Vec<_> = (0..=9).map(|i| (f64::from(i), 0.0)).collect();
+        gap.push((100.0, 0.0));
+        assert!(matches!(
+            curve(gap).fit(CurveType::Bezier { segments: 3 }, 10, 1.0),
+            Err(CalcError::FitFailed)
+        ));
+    }
+
+    #[test]
+    fn test_fit_chebyshev_distinct_x() {
+        let mut curve = SampledCurve::new();
+        curve.liver_x = (0..=20).map(f64::from).collect();
+        curve.liver_y = vec![0.0; 21];
+        // 5 个采样点, 但只有 3 个不同的 x.
+        curve.samp_x = vec![0.0, 0.0, 10.0, 20.0, 20.0];
+        curve.samp_y = vec![0.0, 1.0, 2.0, 3.0, 4.0];
+        assert!(matches!(
+            curve.fit(CurveType::Chebyshev { degree: 3 }, 10, 1.0),
+            Err(CalcError::TooFewSamples(3, 4))
+        ));
+        curve
+            .fit(CurveType::Chebyshev { degree: 2 }, 10, 1.0)
+            .unwrap();
+        assert!(curve.fit_len() > 0);
+    }
+}
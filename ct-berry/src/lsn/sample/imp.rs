@@ -104,7 +104,7 @@ pub fn num_cont_eqd(surface: RawSurface, slt: f64, num: u8, dist: f64) -> R {
     let length = surface.mm_length();
 
     // 曲线不够长就直接返回错误.
-    if num as f64 * slt < length {
+    if length < num as f64 * slt {
         return Err(CalcError::LengthTooShort);
     }
 
@@ -130,7 +130,7 @@ pub fn num_cont_eqd(surface: RawSurface, slt: f64, num: u8, dist: f64) -> R {
             acc_len += cur_len;
 
             // 最后一对?
-            if index + 1 == points.len() {
+            if index + 2 == points.len() {
                 assert!(acc_len >= len_threshold);
 
                 // 判断是否到了下一个采样点
@@ -193,7 +193,7 @@ pub fn num_cont_every(surface: RawSurface, slt: f64, num: u8, pn: u32) -> R {
     // 个表面像素取一次样. 模仿 (1) 即可.
 
     let length = surface.mm_length();
-    if num as f64 * slt < length {
+    if length < num as f64 * slt {
         return Err(CalcError::LengthTooShort);
     }
     let len_threshold = length / (num as f64);
@@ -209,7 +209,7 @@ pub fn num_cont_every(surface: RawSurface, slt: f64, num: u8, pn: u32) -> R {
 
         if acc.num_finished() + 1 == num {
             acc_len += cur_len;
-            if index + 1 == points.len() {
+            if index + 2 == points.len() {
                 assert!(acc_len >= len_threshold);
                 if acc_n + 1 == pn {
                     acc.add_sampled(surface.hwu2xy(*p2));
@@ -239,7 +239,7 @@ pub fn num_cont_every(surface: RawSurface, slt: f64, num: u8, pn: u32) -> R {
             if acc_len < slt {
                 return Err(CalcError::LengthTooShort);
             }
-            acc.add_surface(surface.as_xy(&points[last_idx..]));
+            acc.add_surface(surface.as_xy(&points[last_idx..=index]));
             assert!(acc.finish_one()? < num);
 
             acc_len = cur_len;
@@ -262,7 +262,7 @@ pub fn num_cont_fixed(surface: RawSurface, slt: f64, num: u8, pn: u32) -> R {
     // 模仿 (1) 即可.
 
     let length = surface.mm_length();
-    if num as f64 * slt < length {
+    if length < num as f64 * slt {
         return Err(CalcError::LengthTooShort);
     }
     let len_threshold = length / (num as f64);
@@ -387,3 +387,157 @@ pub fn len_max_every(_surface: RawSurface, _snt: u8, _length: f64, _pn: u32) ->
 pub fn len_max_fixed(_surface: RawSurface, _snt: u8, _length: f64, _pn: u32) -> R {
     unimplemented!()
 }
+
+/// 自适应采样时估计局部曲率所用的半窗口 (点数).
+const CURVATURE_HALF_WINDOW: usize = 3;
+
+// 19
+pub fn num_cont_adaptive(surface: RawSurface, slt: f64, num: u8, base: f64, cw: f64) -> R {
+    // `num` 个无间隙连续 section, 每个 section 长度至少为 `slt`, 且按局部曲率变距采样.
+    // 模仿 (1), 但以 "局部采样间距" 为单位累计距离: 每走过一个局部采样间距就取一次样.
+
+    let length = surface.mm_length();
+    if length < num as f64 * slt {
+        return Err(CalcError::LengthTooShort);
+    }
+    let len_threshold = length / (num as f64);
+    debug_assert!(len_threshold > slt);
+
+    let curvatures = surface.local_curvatures(CURVATURE_HALF_WINDOW);
+    // 从 `points[index]` 走到 `points[index + 1]` 相当于多少个局部采样间距.
+    let steps = |index: usize, cur_len: f64| cur_len * (1.0 + cw * curvatures[index + 1]) / base;
+
+    // (累计采样间距个数, 累计 section 长度, 上一次下标)
+    let (mut acc_step, mut acc_len, mut last_idx) = (0.0, 0.0, 0usize);
+
+    let mut acc = SampledCurvesAcc::new();
+    let points = surface.points();
+
+    for (index, wnd) in points.windows(2).enumerate() {
+        let [p1, p2] = wnd else { unreachable!() };
+        let cur_len = points_distance(*p1, *p2);
+        let cur_step = steps(index, cur_len * surface.dim());
+
+        if acc.num_finished() + 1 == num {
+            acc_len += cur_len;
+            if index + 2 == points.len() {
+                assert!(acc_len >= len_threshold);
+                if acc_step + cur_step >= 1.0 {
+                    acc.add_sampled(surface.hwu2xy(*p2));
+                }
+                acc.add_surface(surface.as_xy(&points[last_idx..]));
+                acc.finish_one()?;
+                break;
+            }
+            if acc_step + cur_step >= 1.0 {
+                acc.add_sampled(surface.hwu2xy(*p2));
+                acc_step = (acc_step + cur_step) % 1.0;
+            } else {
+                acc_step += cur_step;
+            }
+            continue;
+        }
+
+        let mut p2_sample_point = false;
+        if acc_step + cur_step >= 1.0 {
+            acc_step = (acc_step + cur_step) % 1.0;
+            p2_sample_point = true;
+        } else {
+            acc_step += cur_step;
+        }
+
+        if acc_len + cur_len >= len_threshold {
+            if acc_len < slt {
+                return Err(CalcError::LengthTooShort);
+            }
+            acc.add_surface(surface.as_xy(&points[last_idx..=index]));
+            assert!(acc.finish_one()? < num);
+
+            acc_len = cur_len;
+            acc_step = cur_step % 1.0;
+            last_idx = index;
+        } else {
+            acc_len += cur_len;
+            if p2_sample_point {
+                acc.add_sampled(surface.hwu2xy(*p2));
+            }
+        }
+    }
+    assert_eq!(acc.num_finished(), num);
+    Ok(acc.consume())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 沿 `h = 20` 的水平轮廓, 共 `n` 个点, 长度为 `n - 1` 毫米.
+    fn line(n: usize) -> Vec<Idx2d> {
+        (0..n).map(|w| (20, w)).collect()
+    }
+
+    /// 各 section 首尾相接, 且恰好覆盖整条长度为 `n - 1` 的轮廓.
+    fn assert_contiguous(curves: &[SampledCurve], n: usize) {
+        assert_eq!(curves[0].liver_x().first(), Some(&0.0));
+        let last = curves.last().unwrap();
+        assert_eq!(last.liver_x().last(), Some(&((n - 1) as f64)));
+        for pair in curves.windows(2) {
+            assert_eq!(pair[0].liver_x().last(), pair[1].liver_x().first());
+        }
+    }
+
+    #[test]
+    fn test_num_cont_eqd() {
+        let points = line(101);
+        let surface = || RawSurface::new(&points, 64, 1.0);
+        let curves = num_cont_eqd(surface(), 10.0, 3, 5.0).unwrap();
+        assert_eq!(curves.len(), 3);
+        assert_contiguous(&curves, 101);
+        assert!(curves.iter().all(|c| c.sample_len() >= 2));
+
+        // 总长度不足 `num * slt`.
+        let points = line(21);
+        let surface = RawSurface::new(&points, 64, 1.0);
+        assert!(matches!(
+            num_cont_eqd(surface, 10.0, 3, 5.0),
+            Err(CalcError::LengthTooShort)
+        ));
+    }
+
+    #[test]
+    fn test_num_cont_every() {
+        let points = line(101);
+        let surface = || RawSurface::new(&points, 64, 1.0);
+        let curves = num_cont_every(surface(), 10.0, 3, 4).unwrap();
+        assert_eq!(curves.len(), 3);
+        assert_contiguous(&curves, 101);
+        // 除最后一个外, 每个 section 只包含到分界点为止的轮廓.
+        for c in &curves[..2] {
+            assert!(c.liver_len() < 40, "{}", c.liver_len());
+        }
+
+        let points = line(21);
+        let surface = RawSurface::new(&points, 64, 1.0);
+        assert!(matches!(
+            num_cont_every(surface, 10.0, 3, 4),
+            Err(CalcError::LengthTooShort)
+        ));
+    }
+
+    #[test]
+    fn test_num_cont_fixed() {
+        let points = line(101);
+        let surface = || RawSurface::new(&points, 64, 1.0);
+        let curves = num_cont_fixed(surface(), 10.0, 3, 5).unwrap();
+        assert_eq!(curves.len(), 3);
+        assert_contiguous(&curves, 101);
+        assert!(curves.iter().all(|c| c.sample_len() == 5));
+
+        let points = line(21);
+        let surface = RawSurface::new(&points, 64, 1.0);
+        assert!(matches!(
+            num_cont_fixed(surface, 10.0, 3, 5),
+            Err(CalcError::LengthTooShort)
+        ));
+    }
+}
@@ -33,6 +33,20 @@ pub enum SampleRule {
 
     /// 采样固定个数的点.
     FixedPoints(u32),
+
+    /// 按局部曲率自适应的变距采样: 曲率越大的地方 (如结节处) 采样越密.
+    ///
+    /// 曲率为 `κ` (弧度/毫米) 处的采样间距约为 `base / (1 + curvature_weight * κ)` (毫米).
+    /// `base` 是平直处的采样间距; `curvature_weight` (毫米) 越大, 弯曲处的采样越密,
+    /// 为 0 时退化为 `EqualDistance(base)`. 例如 `curvature_weight = 5.0` 时,
+    /// 曲率为 0.2 弧度/毫米的结节处的采样密度是平直处的两倍.
+    AdaptiveDensity {
+        /// 平直处的采样间距 (单位: 毫米).
+        base: f64,
+
+        /// 曲率对采样密度的放大系数 (单位: 毫米).
+        curvature_weight: f64,
+    },
 }
 
 /// 基于 section 数量的规则.
@@ -88,7 +102,8 @@ impl SampleSpec {
     /// 要拟合的曲线类型为 `curve_type`,
     /// 拟合曲线中每毫米的采样点个数为 `sample_per_mm`.
     ///
-    /// 如果存在非法参数, 则程序 panic.
+    /// 如果存在非法参数, 则程序 panic. [`SampleRule::AdaptiveDensity`] 目前只支持
+    /// [`Spacing::Contiguous`], 与其它 `spacing` 组合同样视为非法参数.
     pub fn with_fixed_num(
         spacing: Spacing,
         section_num: u8,
@@ -102,6 +117,7 @@ impl SampleSpec {
             section_length_threshold,
             section_num,
             rule,
+            matches!(spacing, Spacing::Contiguous),
             dim,
             sample_per_mm,
         );
@@ -128,7 +144,8 @@ impl SampleSpec {
     /// 要拟合的曲线类型为 `curve_type`,
     /// 拟合曲线中每毫米的采样点个数为 `sample_per_mm`.
     ///
-    /// 如果存在非法参数, 则程序 panic.
+    /// 如果存在非法参数, 则程序 panic. 该规则目前不支持 [`SampleRule::AdaptiveDensity`],
+    /// 传入时同样视为非法参数.
     pub fn with_fixed_length(
         spacing: Spacing,
         section_length: f64,
@@ -142,6 +159,7 @@ impl SampleSpec {
             section_length,
             section_num_threshold,
             rule,
+            false,
             dim,
             sample_per_mm,
         );
@@ -159,8 +177,16 @@ impl SampleSpec {
         }
     }
 
+    /// `adaptive` 表示当前的间隔规则是否支持 [`SampleRule::AdaptiveDensity`].
     #[inline]
-    fn assert_args(section_length: f64, section_num: u8, rule: SampleRule, dim: f64, spm: u32) {
+    fn assert_args(
+        section_length: f64,
+        section_num: u8,
+        rule: SampleRule,
+        adaptive: bool,
+        dim: f64,
+        spm: u32,
+    ) {
         assert!(section_length > 0.0);
         assert_ne!(section_num, 0); // 根据医学研究, 一般不应小于 3
         match rule {
@@ -173,6 +199,17 @@ impl SampleSpec {
             SampleRule::FixedPoints(num) => {
                 assert!(num > 1);
             }
+            SampleRule::AdaptiveDensity {
+                base,
+                curvature_weight,
+            } => {
+                assert!(
+                    adaptive,
+                    "自适应采样目前只支持基于 section 数量的 Contiguous 间隔"
+                );
+                assert!(base > 0.0 && base < section_length);
+                assert!(curvature_weight >= 0.0);
+            }
         }
         assert!(dim > 0.0);
         assert!((1..=10000).contains(&spm));
@@ -381,6 +418,166 @@ impl SampleSpec {
             ) => {
                 imp::len_max_fixed(surface, snt, length, pn) // 18
             }
+            (
+                Either::Left(NumBased {
+                    section_length_threshold: slt,
+                    num,
+                }),
+                Spacing::Contiguous,
+                SampleRule::AdaptiveDensity {
+                    base,
+                    curvature_weight: cw,
+                },
+            ) => {
+                imp::num_cont_adaptive(surface, slt, num, base, cw) // 19
+            }
+            // 构建时已拒绝其它间隔规则下的自适应采样.
+            (_, _, SampleRule::AdaptiveDensity { .. }) => unreachable!(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 沿 `h = 20` 从 `w = 0` 开始的水平轮廓. 若 `bump` 为真, 则在 `w = 40` 处
+    /// 有一个高 6 像素的尖锐三角形凸起 (对角线上升 6 步, 再下降 6 步).
+    fn contour(bump: bool, len_w: usize) -> Vec<Idx2d> {
+        let mut points = vec![];
+        let mut w = 0;
+        while w < len_w {
+            if bump && w == 40 {
+                for i in 0..6 {
+                    points.push((20 - i, w + i));
+                }
+                for i in 0..6 {
+                    points.push((14 + i, w + 6 + i));
+                }
+                w += 12;
+            } else {
+                points.push((20, w));
+                w += 1;
+            }
+        }
+        points
+    }
+
+    fn spec(num: u8, rule: SampleRule) -> SampleSpec {
+        SampleSpec::with_fixed_num(
+            Spacing::Contiguous,
+            num,
+            10.0,
+            rule,
+            1.0,
+            CurveType::Polynomial { degree: 2 },
+            10,
+        )
+    }
+
+    #[test]
+    fn test_adaptive_density() {
+        let bumpy = contour(true, 100);
+        // 与带凸起的轮廓长度 (87 + 12√2 ≈ 104) 相同的平直轮廓.
+        let smooth = contour(false, 105);
+        let length = |p: &[Idx2d]| RawSurface::new(p, 64, 1.0).mm_length();
+        assert!((length(&bumpy) - length(&smooth)).abs() < 1.0);
+
+        let adaptive = spec(
+            1,
+            SampleRule::AdaptiveDensity {
+                base: 4.0,
+                curvature_weight: 10.0,
+            },
+        );
+        // 凸起附近 (x = w ∈ [34, 58]) 的采样点个数.
+        let near_bump = |points: &[Idx2d]| {
+            let curves = adaptive.sample_raw(points, 64).unwrap();
+            assert_eq!(curves.len(), 1);
+            curves[0]
+                .sample_x()
+                .iter()
+                .filter(|x| (34.0..=58.0).contains(*x))
+                .count()
+        };
+        let (n_bumpy, n_smooth) = (near_bump(&bumpy), near_bump(&smooth));
+        assert!(n_bumpy > n_smooth, "{n_bumpy} vs {n_smooth}");
+
+        // 平直处曲率为 0, 与等距采样相同; `curvature_weight` 为 0 时处处与等距采样相同.
+        let eqd = spec(1, SampleRule::EqualDistance(4.0));
+        let unweighted = spec(
+            1,
+            SampleRule::AdaptiveDensity {
+                base: 4.0,
+                curvature_weight: 0.0,
+            },
+        );
+        for points in [&smooth, &bumpy] {
+            let expected = eqd.sample_raw(points, 64).unwrap();
+            let actual = unweighted.sample_raw(points, 64).unwrap();
+            assert_eq!(expected[0].sample_x(), actual[0].sample_x());
+            assert_eq!(expected[0].sample_y(), actual[0].sample_y());
+        }
+        let expected = eqd.sample_raw(&smooth, 64).unwrap();
+        let actual = adaptive.sample_raw(&smooth, 64).unwrap();
+        assert_eq!(expected[0].sample_x(), actual[0].sample_x());
+    }
+
+    #[test]
+    fn test_adaptive_density_sections() {
+        let bumpy = contour(true, 100);
+        let rule = SampleRule::AdaptiveDensity {
+            base: 4.0,
+            curvature_weight: 10.0,
+        };
+        let curves = spec(3, rule).sample_raw(&bumpy, 64).unwrap();
+        assert_eq!(curves.len(), 3);
+        // 各 section 首尾相接: 前一个 section 的最后一个轮廓点是后一个的第一个.
+        assert_eq!(curves[0].liver_x().first(), Some(&0.0));
+        assert_eq!(curves[2].liver_x().last(), Some(&99.0));
+        for pair in curves.windows(2) {
+            assert_eq!(pair[0].liver_x().last(), pair[1].liver_x().first());
+            assert_eq!(pair[0].liver_y().last(), pair[1].liver_y().first());
+        }
+        // 凸起 (w ∈ [40, 52]) 位于第二个 section, 其采样比平直的第一个 section 密.
+        let density = |c: &SampledCurve| c.sample_len() as f64 / c.liver_len() as f64;
+        assert!(density(&curves[1]) > density(&curves[0]));
+
+        // `curvature_weight` 为 0 时, 跨 section 的余量处理也与等距采样相同.
+        let expected = spec(3, SampleRule::EqualDistance(4.0))
+            .sample_raw(&bumpy, 64)
+            .unwrap();
+        let rule = SampleRule::AdaptiveDensity {
+            base: 4.0,
+            curvature_weight: 0.0,
+        };
+        let actual = spec(3, rule).sample_raw(&bumpy, 64).unwrap();
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert_eq!(e.sample_x(), a.sample_x());
+            assert_eq!(e.sample_y(), a.sample_y());
+            assert_eq!(e.liver_x(), a.liver_x());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_adaptive_density_fixed_spacing() {
+        let rule = SampleRule::AdaptiveDensity {
+            base: 4.0,
+            curvature_weight: 10.0,
+        };
+        let curve = CurveType::Polynomial { degree: 2 };
+        SampleSpec::with_fixed_num(Spacing::Fixed(5.0), 3, 10.0, rule, 1.0, curve, 10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_adaptive_density_len_based() {
+        let rule = SampleRule::AdaptiveDensity {
+            base: 4.0,
+            curvature_weight: 10.0,
+        };
+        let curve = CurveType::Polynomial { degree: 2 };
+        SampleSpec::with_fixed_length(Spacing::Contiguous, 20.0, 3, rule, 1.0, curve, 10);
+    }
+}
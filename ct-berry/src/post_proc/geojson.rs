@@ -0,0 +1,135 @@
+//! 以 GeoJSON 格式导出肝脏轮廓.
+
+use super::{polygon_area, signed_polygon_area, simplify_polygon};
+use crate::{CtData3d, Idx2d};
+use std::fmt::Write;
+
+/// 导出 GeoJSON 前化简轮廓所用的容差 (像素).
+const GEOJSON_SIMPLIFY_EPSILON: f64 = 1.0;
+
+impl CtData3d {
+    /// 将每张含有肝脏 (或肿瘤) 的水平切片的肝脏轮廓导出为 GeoJSON `FeatureCollection`
+    /// 字符串, 便于载入 GIS 风格的查看器或网页地图.
+    ///
+    /// 每张切片先以 `mulberry` 追踪最大肝脏区域的轮廓, 再以 [`simplify_polygon`]
+    /// (容差为 1 像素) 化简, 得到一个 `Polygon` 要素. 要素按 z 升序排列,
+    /// `properties` 包含切片索引 `z` 以及化简后多边形的面积 `area` (平方像素).
+    ///
+    /// 坐标以像素为单位, 每个位置为 `[宽, 高]`, 即 `x` 对应宽度方向, `y` 对应高度方向.
+    /// 按照 RFC 7946, 外环首尾顶点相同, 且在 `(x, y)` 坐标系中为逆时针方向.
+    /// 化简后不足 3 个顶点的切片被忽略.
+    pub fn liver_contours_geojson(&self) -> String {
+        let mut out = String::from(r#"{"type":"FeatureCollection","features":["#);
+        let mut first = true;
+        for (z, _, label) in self.iter_liver_slices() {
+            let mut slice = label.to_owned();
+            let contour = slice.as_mutable().mulberry();
+            let mut ring = simplify_polygon(&contour, GEOJSON_SIMPLIFY_EPSILON);
+            if ring.len() < 3 {
+                continue;
+            }
+            // 以 (高, 宽) 计算的有向面积与以 (x, y) = (宽, 高) 计算的有向面积符号相反.
+            if signed_polygon_area(&ring) > 0.0 {
+                ring.reverse();
+            }
+
+            if !first {
+                out.push(',');
+            }
+            first = false;
+            write_feature(&mut out, z, &ring);
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+/// 将一个多边形要素追加到 `out`.
+fn write_feature(out: &mut String, z: usize, ring: &[Idx2d]) {
+    out.push_str(r#"{"type":"Feature","geometry":{"type":"Polygon","coordinates":[["#);
+    for (i, &(h, w)) in ring.iter().chain(ring.first()).enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        // 写入 `String` 不会失败.
+        write!(out, "[{w},{h}]").unwrap();
+    }
+    write!(
+        out,
+        r#"]]}},"properties":{{"z":{z},"area":{}}}}}"#,
+        polygon_area(ring)
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::consts::gray::*;
+    use crate::CtData3d;
+    use ndarray::Array3;
+    use serde_json::Value;
+
+    #[test]
+    fn test_liver_contours_geojson() {
+        // [w, h, z] = [40, 40, 4]: 第 1 层为半径 10 的肝脏, 第 2 层为半径 15 的肝脏 (含肿瘤),
+        // 其余层为空.
+        let mut label = Array3::<u8>::zeros((40, 40, 4));
+        for ((w, h, z), p) in label.indexed_iter_mut() {
+            let d = (h as f64 - 20.0).hypot(w as f64 - 20.0);
+            *p = match z {
+                1 if d <= 10.0 => LITS_LIVER,
+                2 if d <= 3.0 => LITS_TUMOR,
+                2 if d <= 15.0 => LITS_LIVER,
+                _ => LITS_BACKGROUND,
+            };
+        }
+        let scan = Array3::<f32>::zeros((40, 40, 4));
+        let ct = CtData3d::fake(scan, label, [1.0; 3], 0, [0.0; 3]);
+
+        let json: Value = serde_json::from_str(&ct.liver_contours_geojson()).unwrap();
+        assert_eq!(json["type"], "FeatureCollection");
+        let features = json["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+
+        for (feature, (z, r)) in features.iter().zip([(1, 10.0), (2, 15.0)]) {
+            assert_eq!(feature["type"], "Feature");
+            assert_eq!(feature["properties"]["z"], z);
+            let area = feature["properties"]["area"].as_f64().unwrap();
+            let expected = std::f64::consts::PI * r * r;
+            assert!(
+                (area - expected).abs() < 0.2 * expected,
+                "{area} vs {expected}"
+            );
+
+            let geometry = &feature["geometry"];
+            assert_eq!(geometry["type"], "Polygon");
+            let rings = geometry["coordinates"].as_array().unwrap();
+            assert_eq!(rings.len(), 1);
+            let ring: Vec<(f64, f64)> = rings[0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| (p[0].as_f64().unwrap(), p[1].as_f64().unwrap()))
+                .collect();
+            assert!(ring.len() >= 4);
+            assert_eq!(ring.first(), ring.last());
+            // 外环在 (x, y) 坐标系中为逆时针.
+            let twice: f64 = ring
+                .windows(2)
+                .map(|s| s[0].0 * s[1].1 - s[1].0 * s[0].1)
+                .sum();
+            assert!(twice > 0.0);
+        }
+
+        // 没有肝脏时输出空集合.
+        let empty = CtData3d::fake(
+            Array3::zeros((8, 8, 2)),
+            Array3::zeros((8, 8, 2)),
+            [1.0; 3],
+            0,
+            [0.0; 3],
+        );
+        let json: Value = serde_json::from_str(&empty.liver_contours_geojson()).unwrap();
+        assert_eq!(json["features"].as_array().unwrap().len(), 0);
+    }
+}
@@ -14,22 +14,45 @@ use crate::Idx2d;
 /// 2. 顺时针的概念同时适用于图像坐标系和自然坐标系, 无二义性.
 /// 3. 图形必须是简单多边形, 不能有相交边, 也不能在同一条直线上,
 ///   否则结果无意义.
-/// 4. 函数可能会计算溢出,
-///   因此请考虑让数据规模和坐标的绝对值大小处于合理范围内.
+/// 4. 最后一个顶点会自动与第一个顶点相连, 是否重复首个顶点不影响结果.
 pub fn is_clockwise_polygon(circle: &[Idx2d]) -> bool {
     assert!(circle.len() > 2);
+    signed_polygon_area(circle) < 0.0
+}
+
+/// 使用鞋带公式计算首尾相连的简单多边形的有向面积.
+/// 多边形为顺时针时结果为负, 逆时针时结果为正.
+///
+/// `circle` 的最后一个顶点会自动与第一个顶点相连, 因此无需重复首个顶点
+/// (重复也不影响结果). 少于 3 个顶点时返回 `0.0`.
+///
+/// 中间结果以 `i128` 累加, 不会溢出.
+pub fn signed_polygon_area(circle: &[Idx2d]) -> f64 {
+    let (Some(&first), Some(&last)) = (circle.first(), circle.last()) else {
+        return 0.0;
+    };
 
     #[inline]
-    fn shoelace(wnd: &[Idx2d]) -> f64 {
-        let &[(x1, y1), (x2, y2)] = wnd else {
-            unreachable!()
-        };
-        let (x1, y1, x2, y2) = (x1 as i64, y1 as i64, x2 as i64, y2 as i64);
-        (x1 * y2 - x2 * y1) as f64
+    fn cross((x1, y1): Idx2d, (x2, y2): Idx2d) -> i128 {
+        let (x1, y1, x2, y2) = (x1 as i128, y1 as i128, x2 as i128, y2 as i128);
+        x1 * y2 - x2 * y1
     }
 
-    // 鞋带公式
-    circle.windows(2).map(shoelace).sum::<f64>() < 0.0
+    let twice: i128 = circle
+        .windows(2)
+        .map(|wnd| cross(wnd[0], wnd[1]))
+        .sum::<i128>()
+        + cross(last, first);
+    twice as f64 / 2.0
+}
+
+/// 使用鞋带公式计算首尾相连的简单多边形所围成的面积, 以像素为单位.
+/// 详见 [`signed_polygon_area`].
+///
+/// 对于光滑的边界, 该值比直接统计像素个数更准确.
+#[inline]
+pub fn polygon_area(circle: &[Idx2d]) -> f64 {
+    signed_polygon_area(circle).abs()
 }
 
 /// 从 8-相邻且首尾相连的 `circle_surface` 索引组中提取出位于
@@ -136,3 +159,42 @@ impl RingState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon_area() {
+        // 直角三角形, 两条直角边分别为 3 和 4.
+        let triangle = [(0, 0), (0, 4), (3, 0)];
+        assert_eq!(signed_polygon_area(&triangle), -6.0);
+        assert_eq!(polygon_area(&triangle), 6.0);
+        assert!(is_clockwise_polygon(&triangle));
+
+        // 逆序后方向相反, 面积不变.
+        let rev: Vec<_> = triangle.iter().rev().copied().collect();
+        assert_eq!(signed_polygon_area(&rev), 6.0);
+        assert!(!is_clockwise_polygon(&rev));
+
+        // 5 * 7 矩形; 是否重复首个顶点不影响结果.
+        let mut rect = vec![(10, 10), (10, 17), (15, 17), (15, 10)];
+        assert_eq!(polygon_area(&rect), 35.0);
+        rect.push((10, 10));
+        assert_eq!(polygon_area(&rect), 35.0);
+
+        // 远离原点的顺时针三角形, 无论从哪个顶点开始, 是否重复首个顶点, 方向都不变.
+        // 缺少闭合边时, 从 (103, 100) 开始的结果会错判为逆时针.
+        let far = [(100, 100), (100, 104), (103, 100)];
+        for start in 0..3 {
+            let mut poly: Vec<_> = (0..3).map(|i| far[(start + i) % 3]).collect();
+            assert!(is_clockwise_polygon(&poly), "{poly:?}");
+            poly.push(poly[0]);
+            assert!(is_clockwise_polygon(&poly), "{poly:?}");
+            assert_eq!(signed_polygon_area(&poly), -6.0);
+        }
+
+        assert_eq!(polygon_area(&[(1, 1), (2, 2)]), 0.0);
+        assert_eq!(polygon_area(&[]), 0.0);
+    }
+}
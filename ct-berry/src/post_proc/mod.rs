@@ -1,8 +1,18 @@
 //! 后处理流程集合.
 
+mod geojson;
 mod locate_lls;
+mod pipeline;
 mod refine;
+mod simplify;
 
-pub use locate_lls::{is_clockwise_polygon, locate_lls};
+pub use locate_lls::{is_clockwise_polygon, locate_lls, polygon_area, signed_polygon_area};
 
-pub use refine::{refine_surface, refine_surface0, HuThreshold, Modified, Refined};
+pub use pipeline::SurfacePipeline;
+
+pub use refine::{
+    refine_surface, refine_surface0, refine_surface_constrained, refine_surface_iterative,
+    refine_surface_with_visited, HuThreshold, Modified, Refined,
+};
+
+pub use simplify::simplify_polygon;
@@ -0,0 +1,292 @@
+//! 单张水平切片的肝表面结节评分完整流程.
+//!
+//! 依次执行: 表面优化 (refine) -> 轮廓提取 -> 肝左外区定位 -> 采样拟合 -> LSN 评分.
+
+use super::{locate_lls, refine_surface0, HuThreshold};
+use crate::consts::gray::*;
+use crate::lsn::{CalcError, CalcResult, SampleSpec};
+use crate::sector::LlsSectorPattern;
+use crate::{CtData3d, Idx2d, LabelSliceMut, NiftiHeaderAttr};
+use std::cmp::Reverse;
+
+/// 把各后处理步骤串联起来的 LSN 计算流程.
+///
+/// 对象保存了 refine 参数、LLS 扇区模式和采样规则,
+/// 可以重复地对不同切片调用 [`SurfacePipeline::run`].
+#[derive(Debug, Clone)]
+pub struct SurfacePipeline {
+    /// 扇区模式.
+    pattern: LlsSectorPattern,
+
+    /// 扇区非固定射线相对于固定射线的弧度.
+    offset_arc: f64,
+
+    /// 采样规则.
+    spec: SampleSpec,
+
+    /// refine 的 BFS 步数.
+    bfs_step: u32,
+
+    /// 肝衰减. 为 `None` 时从当前切片的肝脏像素估计.
+    attenuation: Option<f64>,
+
+    /// refine 门限.
+    threshold: HuThreshold,
+}
+
+impl SurfacePipeline {
+    /// 默认的 refine BFS 步数.
+    pub const DEFAULT_BFS_STEP: u32 = 3;
+
+    /// 默认的 refine 门限.
+    pub const DEFAULT_THRESHOLD: HuThreshold = HuThreshold::Centered(50.0);
+
+    /// 以扇区模式 `pattern`, 扇区弧度 `offset_arc` (含义见
+    /// [`LlsSectorPattern::build_from_arc`]) 和采样规则 `spec` 构建流程.
+    ///
+    /// refine 参数取默认值: BFS 步数为 [`Self::DEFAULT_BFS_STEP`],
+    /// 门限为 [`Self::DEFAULT_THRESHOLD`], 肝衰减从切片中估计.
+    ///
+    /// 若 `offset_arc` 不在 `(0.0, 2.0 * PI / 3.0]` 内, 则程序 panic.
+    pub fn new(pattern: LlsSectorPattern, offset_arc: f64, spec: SampleSpec) -> Self {
+        use std::f64::consts::FRAC_PI_3;
+        assert!(
+            0.0 < offset_arc && offset_arc <= 2.0 * FRAC_PI_3,
+            "弧度 `{offset_arc}` 越界"
+        );
+        Self {
+            pattern,
+            offset_arc,
+            spec,
+            bfs_step: Self::DEFAULT_BFS_STEP,
+            attenuation: None,
+            threshold: Self::DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// 设置 refine 的 BFS 步数.
+    #[inline]
+    pub fn bfs_step(mut self, bfs_step: u32) -> Self {
+        self.bfs_step = bfs_step;
+        self
+    }
+
+    /// 设置固定的肝衰减 (CT HU 值).
+    #[inline]
+    pub fn attenuation(mut self, attenuation: f64) -> Self {
+        self.attenuation = Some(attenuation);
+        self
+    }
+
+    /// 设置 refine 门限.
+    #[inline]
+    pub fn threshold(mut self, threshold: HuThreshold) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// 对 `data` 的第 `z` 张水平切片执行完整流程, 返回每个 section 的 LSN 评分.
+    ///
+    /// 流程依次为:
+    ///
+    /// 1. 以肝脏-肿瘤实体的 4-邻域腐蚀中心为圆心构建 LLS 扇区.
+    /// 2. 若未指定肝衰减, 则以切片上所有 `LITS_LIVER` 像素的平均 CT HU 值作为肝衰减.
+    /// 3. 调用 [`refine_surface0`] 优化扇区内的肝脏表面. 该步骤会就地修改 `data.label`.
+    /// 4. 在优化后标签的副本上提取首尾相连的 8-邻域轮廓, 并定位肝左外区.
+    /// 5. 按照采样规则采样、拟合, 并计算每个 section 的 LSN.
+    ///
+    /// # 返回值
+    ///
+    /// - 切片上没有前景或没有肝脏像素时, 返回 `Err(CalcError::NoLiver)`;
+    /// - 肝左外区轮廓不足 3 个像素时, 返回 `Err(CalcError::LengthTooShort)`;
+    /// - 其它错误来自 [`SampleSpec::sample`].
+    ///
+    /// 若 `z` 越界, 则程序 panic.
+    pub fn run(&self, data: &mut CtData3d, z: usize) -> CalcResult<Vec<f64>> {
+        let scan = data.scan.slice_at(z);
+        let mut label = data.label.slice_at_mut(z);
+
+        let center = label.n4_lt_center().ok_or(CalcError::NoLiver)?;
+        let sector = self.pattern.build_from_arc(center, self.offset_arc);
+
+        let attenuation = match self.attenuation {
+            Some(attenuation) => attenuation,
+            None => {
+                let (mut sum, mut count) = (0.0, 0u64);
+                for (pos, &pixel) in label.indexed_iter() {
+                    if is_liver(pixel) {
+                        sum += scan[pos] as f64;
+                        count += 1;
+                    }
+                }
+                if count == 0 {
+                    return Err(CalcError::NoLiver);
+                }
+                sum / count as f64
+            }
+        };
+
+        refine_surface0(
+            scan,
+            LabelSliceMut::new(label.array_view_mut()),
+            self.bfs_step,
+            attenuation,
+            self.threshold,
+            sector,
+        );
+
+        // 轮廓提取会改写标签, 因此在副本上进行.
+        let mut refined = label.to_owned();
+        let surface = refined.as_mutable().mulberry();
+        let lls = locate_lls(&surface, sector);
+        if lls.len() < 3 {
+            return Err(CalcError::LengthTooShort);
+        }
+
+        let curves = self.spec.sample(&lls, label.height())?;
+        Ok(curves.iter().map(|c| c.lsn()).collect())
+    }
+}
+
+impl CtData3d {
+    /// 获取肝脏最大横截面上的肝左外区轮廓.
+    ///
+    /// 流程依次为:
+    ///
+    /// 1. 选取肝脏 (含肿瘤) 像素最多的水平切片. 若有多张, 取 z 最小者;
+    /// 2. 在该切片的副本上进行肝脏唯一化, 并以肝脏-肿瘤实体的 4-邻域腐蚀中心为圆心,
+    ///    按照 header 推导出的扇区模式和 `offset_angle` (含义见
+    ///    [`LlsSectorPattern::build_from_angle`]) 构建 LLS 扇区;
+    /// 3. 以 `mulberry` 算法提取首尾相连的 8-邻域轮廓, 并定位肝左外区.
+    ///
+    /// 返回值为 `(切片索引, 肝左外区轮廓索引)`. 若标注中没有肝脏,
+    /// 或无法从 header 推导出扇区模式, 则返回 `None`. 原数据不会被修改.
+    ///
+    /// 若 `offset_angle` 不在 `(0.0, 120.0]` 内, 则程序 panic.
+    pub fn representative_lls_contour(&self, offset_angle: f64) -> Option<(usize, Vec<Idx2d>)> {
+        let pattern = self.label.lls_sector_pattern().ok()?;
+        let (z, _) = self
+            .label
+            .slice_iter()
+            .map(|s| s.iter().filter(|&&p| is_liver_or_tumor(p)).count())
+            .enumerate()
+            .filter(|&(_, area)| area > 0)
+            .max_by_key(|&(z, area)| (area, Reverse(z)))?;
+
+        let mut slice = self.label.slice_at(z).to_owned();
+        let mut slice = slice.as_mutable();
+        slice.unify_binary();
+        let center = slice.n4_lt_center()?;
+        let sector = pattern.build_from_angle(center, offset_angle);
+        let surface = slice.mulberry();
+        Some((z, locate_lls(&surface, sector)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fitting::CurveType;
+    use crate::lsn::{SampleRule, Spacing};
+    use ndarray::Array3;
+    use std::f64::consts::FRAC_PI_2;
+
+    /// 构造单张切片的合成数据: 以 `(48, 48)` 为圆心, 半径随角度按 `radius` 变化的肝脏.
+    fn synthetic(radius: impl Fn(f64) -> f64) -> CtData3d {
+        let (w, h) = (96, 96);
+        let mut scan = Array3::<f32>::from_elem((w, h, 1), -100.0);
+        let mut label = Array3::<u8>::zeros((w, h, 1));
+        for x in 0..w {
+            for y in 0..h {
+                let (dh, dw) = (y as f64 - 48.0, x as f64 - 48.0);
+                if dh.hypot(dw) <= radius(dw.atan2(dh)) {
+                    scan[(x, y, 0)] = 60.0;
+                    label[(x, y, 0)] = LITS_LIVER;
+                }
+            }
+        }
+        CtData3d::fake(scan, label, [1.0; 3], 0, [0.0; 3])
+    }
+
+    fn pipeline(data: &CtData3d) -> SurfacePipeline {
+        let spec = SampleSpec::with_fixed_num(
+            Spacing::Contiguous,
+            3,
+            10.0,
+            SampleRule::EqualDistance(3.0),
+            1.0,
+            CurveType::Polynomial { degree: 3 },
+            10,
+        );
+        SurfacePipeline::new(data.label.lls_sector_pattern().unwrap(), FRAC_PI_2, spec)
+    }
+
+    #[test]
+    fn test_pipeline_synthetic_slice() {
+        let mut smooth = synthetic(|_| 30.0);
+        let before = smooth.label.count(LITS_LIVER);
+        let lsn_smooth = pipeline(&smooth).run(&mut smooth, 0).unwrap();
+        assert_eq!(lsn_smooth.len(), 3);
+        assert!(
+            lsn_smooth.iter().all(|s| s.is_finite() && *s < 1.0),
+            "{lsn_smooth:?}"
+        );
+        // 扫描与标注一致, refine 几乎不改变标签, 也不会留下轮廓标记.
+        assert!(smooth.label.count(LITS_LIVER).abs_diff(before) < 10);
+        assert_eq!(smooth.label.count(LITS_BOUNDARY), 0);
+
+        // 表面起伏越大, LSN 越高.
+        let mut nodular = synthetic(|arc| 30.0 + 2.0 * (12.0 * arc).sin());
+        let lsn_nodular = pipeline(&nodular).run(&mut nodular, 0).unwrap();
+        assert_eq!(lsn_nodular.len(), 3);
+        let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+        assert!(mean(&lsn_nodular) > mean(&lsn_smooth));
+    }
+
+    #[test]
+    fn test_representative_lls_contour() {
+        // 三张切片, 肝脏半径依次为 10, 20, 15; 第二张切片的肝脏外另有一个小岛.
+        let radii = [10.0, 20.0, 15.0];
+        let label = Array3::from_shape_fn((64, 64, 3), |(w, h, z)| {
+            let d = (h as f64 - 32.0).hypot(w as f64 - 32.0);
+            if d <= radii[z] || (z == 1 && h < 3 && w < 3) {
+                LITS_LIVER
+            } else {
+                LITS_BACKGROUND
+            }
+        });
+        let scan = label.mapv(|p| p as f32 * 60.0);
+        let data = CtData3d::fake(scan, label, [1.0; 3], 0, [0.0; 3]);
+        let before = data.label.clone();
+
+        let (z, lls) = data.representative_lls_contour(90.0).unwrap();
+        assert_eq!(z, 1);
+        assert_eq!(data.label.data(), before.data());
+
+        // 轮廓位于大圆的边缘上, 且落在以 (32, 32) 为中心的扇区内.
+        let sector = data
+            .label
+            .lls_sector_pattern()
+            .unwrap()
+            .build_from_angle((32, 32), 90.0);
+        assert!(lls.len() > 20);
+        for &(h, w) in lls.iter() {
+            let d = (h as f64 - 32.0).hypot(w as f64 - 32.0);
+            assert!((18.5..=20.0).contains(&d), "{:?}: {d}", (h, w));
+            assert!(sector.contains((h, w)));
+        }
+        assert!(lls
+            .windows(2)
+            .all(|p| p[0].0.abs_diff(p[1].0) <= 1 && p[0].1.abs_diff(p[1].1) <= 1));
+
+        let empty = synthetic(|_| -1.0);
+        assert!(empty.representative_lls_contour(90.0).is_none());
+    }
+
+    #[test]
+    fn test_pipeline_without_liver() {
+        let mut empty = synthetic(|_| -1.0);
+        let res = pipeline(&empty).run(&mut empty, 0);
+        assert!(matches!(res, Err(CalcError::NoLiver)));
+    }
+}
@@ -1,8 +1,10 @@
 //! 2D 肝脏 CT 水平切片的边缘优化 (后处理).
 
 use crate::consts::{gray::*, ElemType};
+use crate::data::slice::signed_edt;
 use crate::sector::Sector;
 use crate::{Idx2d, LabelSliceMut, ScanSlice};
+use ndarray::Array2;
 use std::collections::{HashSet, VecDeque};
 
 #[cfg(feature = "serde")]
@@ -41,6 +43,20 @@ pub fn refine_surface<'a>(
     RefineImp::new(scan, label, bfs_step, attenuation, threshold, sector).refine()
 }
 
+/// 同 `refine_surface`, 但同时返回算法实际考察过的所有像素位置, 即第二步 BFS
+/// 遍历到且位于 `sector` 范围内的像素, 便于可视化哪些像素是优化的候选.
+/// 修改明细中的所有位置都属于该集合.
+pub fn refine_surface_with_visited<'a>(
+    scan: ScanSlice<'a>,
+    label: LabelSliceMut<'a>,
+    bfs_step: u32,
+    attenuation: f64,
+    threshold: HuThreshold,
+    sector: Sector,
+) -> (Refined, HashSet<Idx2d>) {
+    RefineImp::new(scan, label, bfs_step, attenuation, threshold, sector).refine_with_visited()
+}
+
 /// 同 `refine_surface`, 但不返回对修改结果的描述.
 pub fn refine_surface0<'a>(
     scan: ScanSlice<'a>,
@@ -53,6 +69,85 @@ pub fn refine_surface0<'a>(
     RefineImp::new(scan, label, bfs_step, attenuation, threshold, sector).refine0();
 }
 
+/// 同 `refine_surface`, 但限制表面的最大位移: 只有到原始前景边界 (像素边缘)
+/// 的距离不超过 `max_displacement` (单位: 毫米) 的像素才会被优化,
+/// 其余像素即使在 `bfs_step` 步以内也保持不变, 以避免不符合解剖结构的跳变.
+///
+/// 距离由原始前景 (肝脏和肿瘤) 的距离变换求得: 前景像素取到最近背景像素的距离,
+/// 背景像素取到最近前景像素的距离, 再减去半个像素, 并乘以像素分辨率 `dim`
+/// (单位: 毫米). 因此紧贴边界的像素的位移为半个像素.
+///
+/// 若 `max_displacement` 为负数或 `dim` 不是正数, 则程序 panic.
+#[allow(clippy::too_many_arguments)]
+pub fn refine_surface_constrained<'a>(
+    scan: ScanSlice<'a>,
+    label: LabelSliceMut<'a>,
+    bfs_step: u32,
+    attenuation: f64,
+    threshold: HuThreshold,
+    sector: Sector,
+    max_displacement: f64,
+    dim: f64,
+) -> Refined {
+    RefineImp::new(scan, label, bfs_step, attenuation, threshold, sector)
+        .with_max_displacement(max_displacement, dim)
+        .refine()
+}
+
+/// 迭代地调用 `refine_surface`, 每轮结束后用当前标签重新估计肝衰减.
+///
+/// 第一轮使用 `initial_attenuation`. 此后每一轮都以上一轮结束时 `sector`
+/// 范围内所有 `LITS_LIVER` 像素的平均 CT HU 值作为新的肝衰减
+/// (若扇区内已没有肝脏像素, 则沿用上一轮的值). 其余参数含义同 `refine_surface`.
+///
+/// 返回每一轮实际使用的肝衰减值, 其长度即实际执行的轮数.
+///
+/// # 收敛性
+///
+/// 每一轮中, 肝脏表面最多移动 `bfs_step` 个像素. 若某一轮没有修改任何像素,
+/// 则标签和肝衰减估计都不会再变化, 算法提前终止 (此时返回值长度小于 `rounds`).
+/// 对于肝脏与周围组织 CT HU 值差异明显的切片, 通常几轮内即可收敛;
+/// 但算法并不保证收敛, 表面可能在两个状态之间振荡, 因此总轮数以 `rounds` 为上限.
+pub fn refine_surface_iterative(
+    scan: ScanSlice<'_>,
+    mut label: LabelSliceMut<'_>,
+    bfs_step: u32,
+    initial_attenuation: f64,
+    threshold: HuThreshold,
+    sector: Sector,
+    rounds: u32,
+) -> Vec<f64> {
+    let mut attenuation = initial_attenuation;
+    let mut history = Vec::with_capacity(rounds as usize);
+    for _ in 0..rounds {
+        history.push(attenuation);
+        let delta = RefineImp::new(
+            ScanSlice::new(scan.data()),
+            LabelSliceMut::new(label.array_view_mut()),
+            bfs_step,
+            attenuation,
+            threshold,
+            sector,
+        )
+        .refine();
+        if delta.is_empty() {
+            break;
+        }
+
+        let (mut sum, mut count) = (0.0, 0u64);
+        for (pos, &pixel) in label.indexed_iter() {
+            if is_liver(pixel) && sector.contains(pos) {
+                sum += scan[pos] as f64;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            attenuation = sum / count as f64;
+        }
+    }
+    history
+}
+
 /// CT HU 值优化门限. 用于决定一个 CT HU
 /// 值应该被优化为前景 (特指肝脏像素) 还是背景.
 /// 由用户负责确保对象的值合法.
@@ -92,7 +187,7 @@ impl HuThreshold {
 }
 
 /// 肝脏表面 refine 的方式.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Modified {
     /// 原先是前景像素, 在 refine 过程中被修改为背景像素.
@@ -144,6 +239,28 @@ impl Refined {
         (self.data, self.how)
     }
 
+    /// 获取所有以 `kind` 方式被修改的索引.
+    pub fn only(&self, kind: Modified) -> Vec<Idx2d> {
+        self.iter()
+            .filter_map(|(pos, how)| (how == kind).then_some(pos))
+            .collect()
+    }
+
+    /// 分别统计以 `Modified::B2F` 和 `Modified::F2B` 方式被修改的像素个数.
+    pub fn counts(&self) -> (usize, usize) {
+        let b2f = self.how.iter().filter(|&&h| h == Modified::B2F).count();
+        (b2f, self.len() - b2f)
+    }
+
+    /// 获取位于 `sector` 扇区内的全部修改, 保持原有顺序.
+    pub fn within_sector(&self, sector: &Sector) -> Refined {
+        let mut ans = Refined::new();
+        for (pos, how) in self.iter().filter(|(pos, _)| sector.contains(*pos)) {
+            ans.push(pos, how);
+        }
+        ans
+    }
+
     /// 内部方法, 初始化.
     #[inline]
     fn new() -> Self {
@@ -169,6 +286,8 @@ struct RefineImp<'a> {
     attenuation: f64,
     threshold: HuThreshold,
     sector: Sector,
+    /// 允许被优化的像素. `None` 代表不限制位移.
+    movable: Option<Array2<bool>>,
 }
 
 impl<'a> RefineImp<'a> {
@@ -189,9 +308,26 @@ impl<'a> RefineImp<'a> {
             attenuation,
             threshold,
             sector,
+            movable: None,
         }
     }
 
+    /// 只允许优化到原始前景边界的距离不超过 `max_displacement` (毫米) 的像素.
+    /// `dim` 是像素分辨率 (毫米).
+    pub fn with_max_displacement(mut self, max_displacement: f64, dim: f64) -> Self {
+        assert!(max_displacement >= 0.0, "最大位移不能为负数");
+        assert!(dim > 0.0, "像素分辨率必须为正数");
+        let fg = self.label.array_view().mapv(is_liver_or_tumor);
+        self.movable = Some(signed_edt(&fg).mapv(|d| (d.abs() - 0.5) * dim <= max_displacement));
+        self
+    }
+
+    /// 判断 `pos` 是否允许被优化.
+    #[inline]
+    fn is_movable(&self, pos: Idx2d) -> bool {
+        self.sector.contains(pos) && self.movable.as_ref().map_or(true, |m| m[pos])
+    }
+
     fn bfs(&self) -> HashSet<Idx2d> {
         // step 1: 按照 4-相邻规则定位所有符合要求的前景/背景像素.
         let mut q = self.get_init_surface();
@@ -228,11 +364,18 @@ impl<'a> RefineImp<'a> {
     /// 运行实际优化, 并返回修改明细.
     #[inline]
     pub fn refine(&mut self) -> Refined {
+        self.refine_with_visited().0
+    }
+
+    /// 运行实际优化, 并返回修改明细和所有被考察过的像素位置.
+    pub fn refine_with_visited(&mut self) -> (Refined, HashSet<Idx2d>) {
         // step 3: 以 `threshold` (HU) 为门限,
         //   对每个上一步收集到的所有像素的位置,
         //   参考 `scan` 的对应位置, 并在需要时进行优化.
         let mut delta = Refined::new();
-        for pos in self.bfs().into_iter().filter(|p| self.sector.contains(*p)) {
+        let mut visited = self.bfs();
+        visited.retain(|p| self.is_movable(*p));
+        for &pos in visited.iter() {
             let ct_hu = self.scan[pos] as f64;
             let orig_pixel = self.label[pos];
             if ElemType::Foreground == self.threshold.eval(self.attenuation, ct_hu) {
@@ -248,7 +391,7 @@ impl<'a> RefineImp<'a> {
             }
         }
 
-        delta
+        (delta, visited)
     }
 
     /// 运行实际优化.
@@ -257,7 +400,9 @@ impl<'a> RefineImp<'a> {
         // step 3: 以 `threshold` (HU) 为门限,
         //   对每个上一步收集到的所有像素的位置,
         //   参考 `scan` 的对应位置, 并在需要时进行优化.
-        for pos in self.bfs().into_iter().filter(|p| self.sector.contains(*p)) {
+        let mut visited = self.bfs();
+        visited.retain(|p| self.is_movable(*p));
+        for pos in visited {
             let ct_hu = self.scan[pos] as f64;
             if ElemType::Foreground == self.threshold.eval(self.attenuation, ct_hu) {
                 self.label[pos] = LITS_LIVER;
@@ -285,3 +430,224 @@ impl<'a> RefineImp<'a> {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array2;
+
+    fn disc(r: f64) -> impl Fn(Idx2d) -> bool {
+        move |(h, w)| f64::hypot(h as f64 - 16.0, w as f64 - 16.0) <= r
+    }
+
+    #[test]
+    fn test_refined_filter() {
+        let mut refined = Refined::new();
+        refined.push((10, 15), Modified::B2F);
+        refined.push((10, 5), Modified::F2B);
+        refined.push((15, 15), Modified::B2F);
+        refined.push((5, 5), Modified::B2F);
+
+        assert_eq!(refined.counts(), (3, 1));
+        assert_eq!(
+            refined.only(Modified::B2F),
+            vec![(10, 15), (15, 15), (5, 5)]
+        );
+        assert_eq!(refined.only(Modified::F2B), vec![(10, 5)]);
+
+        // 以 (10, 10) 为中心, 覆盖 w 轴正方向一侧的半圆.
+        let sector = Sector::new((10, 10), 0.0, std::f64::consts::PI).unwrap();
+        let inside = refined.within_sector(&sector);
+        assert_eq!(
+            inside.iter().collect::<Vec<_>>(),
+            vec![((10, 15), Modified::B2F), ((15, 15), Modified::B2F)]
+        );
+        assert_eq!(Refined::new().counts(), (0, 0));
+    }
+
+    #[test]
+    fn test_refine_surface_iterative_converges() {
+        // 半径为 9 的肝脏 (100 HU), 周围为脂肪 (-100 HU).
+        let truth = disc(9.0);
+        let scan = Array2::from_shape_fn((32, 32), |p| if truth(p) { 100.0f32 } else { -100.0 });
+        // 初始标签明显偏小.
+        let init = disc(4.0);
+        let mut label =
+            Array2::from_shape_fn(
+                (32, 32),
+                |p| {
+                    if init(p) {
+                        LITS_LIVER
+                    } else {
+                        LITS_BACKGROUND
+                    }
+                },
+            );
+        let sector = Sector::new_circle((16, 16)).unwrap();
+
+        let history = refine_surface_iterative(
+            ScanSlice::new(scan.view()),
+            LabelSliceMut::new(label.view_mut()),
+            3,
+            70.0,
+            HuThreshold::Centered(40.0),
+            sector,
+            10,
+        );
+
+        // 第二轮起使用重新估计的肝衰减; 在上限前提前收敛.
+        assert_eq!(history[0], 70.0);
+        assert!(history[1..].iter().all(|&a| a == 100.0));
+        assert!(history.len() < 10);
+        for (pos, &pixel) in label.indexed_iter() {
+            assert_eq!(pixel == LITS_LIVER, truth(pos), "{pos:?}");
+        }
+    }
+
+    #[test]
+    fn test_refine_surface_constrained() {
+        // 真实肝脏半径为 12, 初始标签半径为 8; `bfs_step` 足以覆盖两者之间的所有像素.
+        let truth = disc(12.0);
+        let scan = Array2::from_shape_fn((33, 33), |p| if truth(p) { 60.0f32 } else { -100.0 });
+        let init = disc(8.0);
+        let make_label = || {
+            Array2::from_shape_fn(
+                (33, 33),
+                |p| {
+                    if init(p) {
+                        LITS_LIVER
+                    } else {
+                        LITS_BACKGROUND
+                    }
+                },
+            )
+        };
+        let sector = Sector::new_circle((16, 16)).unwrap();
+        let (near, far) = ((16, 25), (16, 27));
+
+        // 不限制位移时, 两个像素都被优化为肝脏.
+        let mut free = make_label();
+        refine_surface(
+            ScanSlice::new(scan.view()),
+            LabelSliceMut::new(free.view_mut()),
+            6,
+            60.0,
+            HuThreshold::Centered(30.0),
+            sector,
+        );
+        assert_eq!((free[near], free[far]), (LITS_LIVER, LITS_LIVER));
+
+        // 像素分辨率为 0.8 毫米, 最大位移为 1.5 毫米: `near` 到原始边界的距离为
+        // 0.5 像素 (0.4 毫米), `far` 为 2.5 像素 (2 毫米).
+        let orig = make_label();
+        let mut label = make_label();
+        let delta = refine_surface_constrained(
+            ScanSlice::new(scan.view()),
+            LabelSliceMut::new(label.view_mut()),
+            6,
+            60.0,
+            HuThreshold::Centered(30.0),
+            sector,
+            1.5,
+            0.8,
+        );
+        assert_eq!(label[near], LITS_LIVER);
+        assert_eq!(label[far], LITS_BACKGROUND);
+        assert!(!delta.is_empty() && delta.iter().all(|(_, how)| how == Modified::B2F));
+
+        // 所有被修改的像素到原始前景的距离都不超过 1.5 毫米 (加半个像素).
+        let fg: Vec<Idx2d> = orig
+            .indexed_iter()
+            .filter_map(|(p, &v)| is_liver(v).then_some(p))
+            .collect();
+        for (pos, _) in delta.iter() {
+            let d = fg
+                .iter()
+                .map(|f| f64::hypot(f.0 as f64 - pos.0 as f64, f.1 as f64 - pos.1 as f64))
+                .fold(f64::INFINITY, f64::min);
+            assert!((d - 0.5) * 0.8 <= 1.5, "{pos:?}");
+        }
+        // 受限的结果是不受限结果的子集.
+        assert!(label
+            .indexed_iter()
+            .all(|(p, &v)| !is_liver(v) || is_liver(free[p])));
+    }
+
+    #[test]
+    fn test_refine_surface_with_visited() {
+        let truth = disc(9.0);
+        let scan = Array2::from_shape_fn((32, 32), |p| if truth(p) { 100.0f32 } else { -100.0 });
+        let init = disc(6.0);
+        let make_label = || {
+            Array2::from_shape_fn(
+                (32, 32),
+                |p| {
+                    if init(p) {
+                        LITS_LIVER
+                    } else {
+                        LITS_BACKGROUND
+                    }
+                },
+            )
+        };
+        let (bfs_step, threshold) = (2, HuThreshold::Centered(40.0));
+        // 只覆盖 w 轴正方向一侧的半圆.
+        let sector = Sector::new((16, 16), 0.0, std::f64::consts::PI).unwrap();
+
+        let orig = make_label();
+        let mut label = make_label();
+        let (delta, visited) = refine_surface_with_visited(
+            ScanSlice::new(scan.view()),
+            LabelSliceMut::new(label.view_mut()),
+            bfs_step,
+            100.0,
+            threshold,
+            sector,
+        );
+
+        // 与 `refine_surface` 的结果一致.
+        let mut expected = make_label();
+        let expected_delta = refine_surface(
+            ScanSlice::new(scan.view()),
+            LabelSliceMut::new(expected.view_mut()),
+            bfs_step,
+            100.0,
+            threshold,
+            sector,
+        );
+        assert_eq!(label, expected);
+        assert_eq!(delta.counts(), expected_delta.counts());
+
+        // 被考察的像素都在扇区内, 且与初始表面的 4-相邻距离小于 `bfs_step`.
+        let orig_sli = crate::LabelSlice::new(orig.view());
+        let is_surface = |p: Idx2d| {
+            (is_liver(orig[p]) && orig_sli.is_n4_containing_background(p))
+                || (is_background(orig[p]) && orig_sli.is_n4_having(p, is_liver_or_tumor))
+        };
+        let surface: Vec<Idx2d> = orig
+            .indexed_iter()
+            .map(|(p, _)| p)
+            .filter(|&p| is_surface(p))
+            .collect();
+        let near = |p: Idx2d| {
+            surface
+                .iter()
+                .any(|s| s.0.abs_diff(p.0) + s.1.abs_diff(p.1) < bfs_step as usize)
+        };
+        for (pos, _) in orig.indexed_iter() {
+            assert_eq!(
+                visited.contains(&pos),
+                sector.contains(pos) && near(pos),
+                "{pos:?}"
+            );
+        }
+        // 所有修改都发生在被考察的像素上, 且不是被考察的像素都未被修改.
+        assert!(delta.iter().all(|(p, _)| visited.contains(&p)));
+        assert!(!delta.is_empty());
+        for (pos, &pixel) in label.indexed_iter() {
+            if !visited.contains(&pos) {
+                assert_eq!(pixel, orig[pos]);
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! 轮廓多边形的化简.
+
+use crate::Idx2d;
+
+/// 使用 Ramer-Douglas-Peucker 算法化简首尾相连的多边形 (如 `mulberry` 追踪得到的轮廓),
+/// 在减少顶点个数的同时保证原多边形的每个顶点到化简结果的距离不超过 `epsilon` (像素).
+/// 适合在导出 SVG/GeoJSON 等矢量格式之前调用.
+///
+/// 与 [`crate::post_proc::signed_polygon_area`] 一样, 最后一个顶点自动与第一个顶点相连;
+/// 若最后一个顶点与第一个顶点重复, 则重复的顶点会被去除. 结果是原顶点的子序列,
+/// 顺序不变, 且总是包含第一个顶点. 少于 3 个 (去重后) 顶点时原样返回.
+///
+/// 若 `epsilon` 为负数或 NaN, 则程序 panic.
+pub fn simplify_polygon(points: &[Idx2d], epsilon: f64) -> Vec<Idx2d> {
+    assert!(epsilon >= 0.0, "非法的容差 {epsilon}");
+    let points = match points {
+        [first, rest @ .., last] if first == last && !rest.is_empty() => {
+            &points[..points.len() - 1]
+        }
+        _ => points,
+    };
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    // 闭合多边形没有天然的端点: 以第一个顶点和距其最远的顶点为锚点, 分别化简两侧的折线.
+    let first = points[0];
+    let (far, _) = points
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (i, squared_distance(first, p)))
+        .fold((0, 0.0), |acc, cur| if cur.1 > acc.1 { cur } else { acc });
+    if far == 0 {
+        // 所有顶点重合.
+        return vec![first];
+    }
+
+    let mut keep = vec![false; points.len() + 1];
+    keep[0] = true;
+    keep[far] = true;
+    // 第 `points.len()` 个位置代表回到第一个顶点.
+    let ring: Vec<Idx2d> = points.iter().copied().chain([first]).collect();
+    rdp(&ring, 0, far, epsilon, &mut keep);
+    rdp(&ring, far, points.len(), epsilon, &mut keep);
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&p, k)| k.then_some(p))
+        .collect()
+}
+
+/// 对 `points[start..=end]` 执行 Ramer-Douglas-Peucker 算法,
+/// 将需要保留的顶点在 `keep` 中标记为 `true`. 两个端点由调用者负责标记.
+fn rdp(points: &[Idx2d], start: usize, end: usize, epsilon: f64, keep: &mut [bool]) {
+    // 使用显式栈, 避免长轮廓导致过深的递归.
+    let mut stack = vec![(start, end)];
+    while let Some((s, e)) = stack.pop() {
+        if e <= s + 1 {
+            continue;
+        }
+        let (idx, dist) = (s + 1..e)
+            .map(|i| (i, segment_distance(points[i], points[s], points[e])))
+            .fold((s, -1.0), |acc, cur| if cur.1 > acc.1 { cur } else { acc });
+        if dist > epsilon {
+            keep[idx] = true;
+            stack.push((s, idx));
+            stack.push((idx, e));
+        }
+    }
+}
+
+#[inline]
+fn squared_distance((a, b): Idx2d, (c, d): Idx2d) -> f64 {
+    (a as f64 - c as f64).powi(2) + (b as f64 - d as f64).powi(2)
+}
+
+/// 点 `p` 到线段 `a`-`b` 的欧几里得距离.
+fn segment_distance(p: Idx2d, a: Idx2d, b: Idx2d) -> f64 {
+    let (px, py) = (p.0 as f64, p.1 as f64);
+    let (ax, ay) = (a.0 as f64, a.1 as f64);
+    let (dx, dy) = (b.0 as f64 - ax, b.1 as f64 - ay);
+    let len2 = dx * dx + dy * dy;
+    if len2 == 0.0 {
+        return squared_distance(p, a).sqrt();
+    }
+    let t = (((px - ax) * dx + (py - ay) * dy) / len2).clamp(0.0, 1.0);
+    (px - ax - t * dx).hypot(py - ay - t * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 以 (h, w) 给出的 20 * 30 矩形的顺时针像素轮廓, 每条边上每隔 3 个像素向内凹陷 1 像素.
+    fn noisy_rectangle() -> Vec<Idx2d> {
+        let (h0, w0, h1, w1) = (10, 10, 30, 40);
+        let mut contour = vec![];
+        for w in w0..w1 {
+            contour.push((h0 + usize::from(w != w0 && w % 3 == 0), w));
+        }
+        for h in h0..h1 {
+            contour.push((h, w1 - usize::from(h != h0 && h % 3 == 0)));
+        }
+        for w in (w0 + 1..=w1).rev() {
+            contour.push((h1 - usize::from(w != w1 && w % 3 == 0), w));
+        }
+        for h in (h0 + 1..=h1).rev() {
+            contour.push((h, w0 + usize::from(h != h1 && h % 3 == 0)));
+        }
+        contour
+    }
+
+    #[test]
+    fn test_simplify_polygon() {
+        let contour = noisy_rectangle();
+        assert_eq!(contour.len(), 100);
+
+        let simplified = simplify_polygon(&contour, 1.5);
+        assert_eq!(simplified, [(10, 10), (10, 40), (30, 40), (30, 10)]);
+
+        // 容差为 0 时只去掉共线的顶点, 结果仍在原轮廓之内.
+        let exact = simplify_polygon(&contour, 0.0);
+        assert!(exact.len() > 4 && exact.len() < contour.len());
+        assert!(exact.iter().all(|p| contour.contains(p)));
+
+        // 每个原顶点到化简结果的距离都不超过容差.
+        for eps in [0.5, 1.0, 3.0] {
+            let s = simplify_polygon(&contour, eps);
+            for &p in contour.iter() {
+                let d = (0..s.len())
+                    .map(|i| segment_distance(p, s[i], s[(i + 1) % s.len()]))
+                    .fold(f64::INFINITY, f64::min);
+                assert!(d <= eps, "{p:?}: {d} > {eps}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_simplify_polygon_degenerate() {
+        assert!(simplify_polygon(&[], 1.0).is_empty());
+        assert_eq!(simplify_polygon(&[(1, 2), (3, 4)], 1.0), [(1, 2), (3, 4)]);
+        // 重复的尾顶点被去除.
+        let closed = [(0, 0), (0, 5), (5, 5), (5, 0), (0, 0)];
+        assert_eq!(
+            simplify_polygon(&closed, 0.5),
+            [(0, 0), (0, 5), (5, 5), (5, 0)]
+        );
+        assert_eq!(simplify_polygon(&[(3, 3); 4], 1.0), [(3, 3)]);
+    }
+}
@@ -15,7 +15,9 @@ pub use crate::data::{CtData3d, CtLabel, CtScan, NiftiHeaderAttr};
 pub use crate::data::slice::ImgDisplay;
 
 pub use crate::consts::gray::{LITS_BACKGROUND, LITS_BOUNDARY, LITS_LIVER, LITS_TUMOR};
-pub use crate::consts::{ElemType, LITS_TESTING_SET_LEN, LITS_TRAINING_SET_LEN};
+pub use crate::consts::{
+    Connectivity, ElemType, Foreground, LITS_TESTING_SET_LEN, LITS_TRAINING_SET_LEN,
+};
 
 pub use crate::dataset::home_dataset_dir_with;
 pub use crate::dataset::{self, lits_train};